@@ -0,0 +1,7 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Generating the gRPC code needs `protoc`, only required by the grpc feature
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/nash_stats.proto")?;
+
+    Ok(())
+}
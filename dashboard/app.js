@@ -0,0 +1,207 @@
+"use strict";
+
+const MAX_FEED_ROWS = 50;
+const STATS_REFRESH_MS = 60_000;
+
+const pairSelect = document.getElementById("pair");
+const timeframeSelect = document.getElementById("timeframe");
+const statusLabel = document.getElementById("status");
+const ordersBody = document.getElementById("orders");
+const gauges = document.getElementById("gauges");
+const volumeCanvas = document.getElementById("volume");
+const chartHint = document.getElementById("chart-hint");
+
+let socket = null;
+
+function token() {
+  return localStorage.getItem("nash-stats-token") || "";
+}
+
+async function api(path) {
+  const headers = token() ? { Authorization: `Bearer ${token()}` } : {};
+  const response = await fetch(path, { headers });
+
+  if (!response.ok) {
+    throw new Error(`${path}: ${response.status} ${await response.text()}`);
+  }
+
+  return response.json();
+}
+
+function setStatus(text, live) {
+  statusLabel.textContent = text;
+  statusLabel.classList.toggle("live", live);
+}
+
+function formatAmount(value, digits) {
+  return value.toLocaleString(undefined, { maximumFractionDigits: digits });
+}
+
+async function refreshStats() {
+  let pairs;
+
+  try {
+    pairs = await api("/stats");
+  } catch (err) {
+    setStatus(err.message, false);
+    return;
+  }
+
+  const selected = pairSelect.value;
+  pairSelect.replaceChildren(new Option("All pairs", ""));
+
+  for (const pair of pairs) {
+    const name = `${pair.crypto_symbol}/${pair.fiat_symbol}`;
+    pairSelect.add(new Option(name, name, false, name === selected));
+  }
+
+  gauges.replaceChildren(
+    ...pairs
+      .filter((pair) => !selected || `${pair.crypto_symbol}/${pair.fiat_symbol}` === selected)
+      .map(gauge),
+  );
+}
+
+function gauge(pair) {
+  const share = pair.orders ? (pair.buys / pair.orders) * 100 : 50;
+  const element = document.createElement("div");
+  const name = document.createElement("strong");
+  const bar = document.createElement("div");
+  const buys = document.createElement("span");
+  const legend = document.createElement("div");
+
+  name.textContent = `${pair.crypto_symbol}/${pair.fiat_symbol}`;
+  bar.className = "gauge-bar";
+  buys.style.width = `${share}%`;
+  bar.append(buys);
+  legend.className = "gauge-legend";
+  legend.append(
+    legendItem(`${pair.buys} buys`, "buy"),
+    legendItem(`${formatAmount(pair.fiat_volume, 0)} ${pair.fiat_symbol}`, ""),
+    legendItem(`${pair.sells} sells`, "sell"),
+  );
+  element.append(name, bar, legend);
+
+  return element;
+}
+
+function legendItem(text, className) {
+  const item = document.createElement("span");
+  item.className = className;
+  item.textContent = text;
+
+  return item;
+}
+
+async function refreshChart() {
+  const pair = pairSelect.value;
+  const context = volumeCanvas.getContext("2d");
+  context.clearRect(0, 0, volumeCanvas.width, volumeCanvas.height);
+  chartHint.hidden = Boolean(pair);
+
+  if (!pair) {
+    return;
+  }
+
+  const params = new URLSearchParams({ pair, timeframe: timeframeSelect.value });
+  let candles;
+
+  try {
+    candles = await api(`/candles?${params}`);
+  } catch (err) {
+    setStatus(err.message, false);
+    return;
+  }
+
+  const max = Math.max(...candles.map((candle) => candle.volume * candle.close), 1);
+  const width = volumeCanvas.width / Math.max(candles.length, 1);
+  context.fillStyle = getComputedStyle(document.body).getPropertyValue("--accent");
+
+  candles.forEach((candle, index) => {
+    const height = ((candle.volume * candle.close) / max) * (volumeCanvas.height - 20);
+    context.fillRect(index * width + 1, volumeCanvas.height - height, width - 2, height);
+  });
+
+  context.fillStyle = getComputedStyle(document.body).getPropertyValue("--muted");
+  context.fillText(`${formatAmount(max, 0)} ${pair.split("/")[1]}`, 4, 12);
+}
+
+function subscribe() {
+  if (socket?.readyState !== WebSocket.OPEN) {
+    return;
+  }
+
+  const pair = pairSelect.value;
+  socket.send(JSON.stringify({ type: "subscribe", pairs: pair ? [pair] : [] }));
+  ordersBody.replaceChildren();
+}
+
+function connect() {
+  const scheme = location.protocol === "https:" ? "wss" : "ws";
+  const query = token() ? `?access_token=${encodeURIComponent(token())}` : "";
+  socket = new WebSocket(`${scheme}://${location.host}/ws${query}`);
+
+  socket.onopen = () => {
+    setStatus("Live", true);
+    subscribe();
+  };
+
+  socket.onclose = () => {
+    setStatus("Disconnected, retrying", false);
+    setTimeout(connect, 5_000);
+  };
+
+  socket.onmessage = (event) => {
+    const message = JSON.parse(event.data);
+
+    if (message.type === "order") {
+      addOrder(message);
+    } else if (message.type === "error") {
+      setStatus(message.message, false);
+    }
+  };
+}
+
+function addOrder(order) {
+  const row = ordersBody.insertRow(0);
+  row.className = order.type;
+
+  for (const text of [
+    new Date(order.created_at).toLocaleTimeString(),
+    order.type,
+    `${formatAmount(order.crypto_amount, 8)} ${order.crypto_symbol}`,
+    formatAmount(order.fiat_price, 2),
+    `${formatAmount(order.fiat_amount, 2)} ${order.fiat_symbol}`,
+  ]) {
+    row.insertCell().textContent = text;
+  }
+
+  while (ordersBody.rows.length > MAX_FEED_ROWS) {
+    ordersBody.deleteRow(-1);
+  }
+}
+
+pairSelect.addEventListener("change", () => {
+  subscribe();
+  refreshStats();
+  refreshChart();
+});
+
+timeframeSelect.addEventListener("change", refreshChart);
+
+document.getElementById("token").addEventListener("click", () => {
+  const value = prompt("API token, empty when the API is open", token());
+
+  if (value !== null) {
+    localStorage.setItem("nash-stats-token", value.trim());
+    location.reload();
+  }
+});
+
+refreshStats();
+refreshChart();
+connect();
+setInterval(() => {
+  refreshStats();
+  refreshChart();
+}, STATS_REFRESH_MS);
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{task::spawn_blocking, time::interval};
+use tracing::{debug, error, info};
+
+use crate::{args::Args, db::insert_my_orders, fetch::Order};
+
+/// Completed order of the authenticated account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyOrder {
+    pub id: String,
+    pub completed_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub order: Order,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MyOrdersResponse {
+    completed_orders: Vec<MyOrder>,
+}
+
+/// Stores the completed orders of the account owning `api_key` every `every`,
+/// the already stored ones being ignored
+pub fn spawn_my_orders(args: &Args, client: reqwest::Client, api_key: String, every: Duration) {
+    let url = args.my_orders_url.clone();
+    let persist_path = args.persist_path.clone();
+
+    info!(
+        "Fetching the account orders every {}",
+        humantime::format_duration(every)
+    );
+
+    tokio::spawn(async move {
+        let mut ticks = interval(every);
+
+        loop {
+            ticks.tick().await;
+
+            let orders = match fetch_my_orders(&client, &url, &api_key).await {
+                Ok(orders) => orders,
+                Err(err) => {
+                    error!("Failed to fetch the account orders: {err}");
+                    continue;
+                }
+            };
+
+            let persist_path = persist_path.clone();
+            let stored = spawn_blocking(move || insert_my_orders(&orders, &persist_path)).await;
+
+            match stored
+                .map_err(anyhow::Error::from)
+                .and_then(|stored| stored)
+            {
+                Ok(inserted) => debug!("{inserted} new account orders"),
+                Err(err) => error!("Failed to insert the account orders: {err}"),
+            }
+        }
+    });
+}
+
+async fn fetch_my_orders(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: &str,
+) -> anyhow::Result<Vec<MyOrder>> {
+    let response = client
+        .get(url)
+        .bearer_auth(api_key)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<MyOrdersResponse>()
+        .await?;
+
+    Ok(response.completed_orders)
+}
@@ -0,0 +1,437 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    db::{get_candles, get_orders_since},
+    export::Pair,
+    fetch::{Order, OrderType},
+    indicators::{Indicator, IndicatorPoint, Timeframe, WARM_UP, compute},
+    store::Store,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertRules {
+    pub rules: Vec<AlertRule>,
+    pub indicator_rules: Vec<IndicatorRule>,
+}
+
+impl AlertRules {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+
+        let rules = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse rules file {}", path.display()))?;
+
+        Ok(rules)
+    }
+}
+
+/// Conditions an order must all satisfy for the alert to fire, unset conditions always match
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: Option<OrderType>,
+    pub pair: Option<Pair>,
+    /// Crypto symbol, matching every pair of that crypto
+    pub symbol: Option<String>,
+    pub min_crypto_amount: Option<f64>,
+    pub min_fiat_amount: Option<f64>,
+    pub max_fiat_amount: Option<f64>,
+    pub min_fiat_price: Option<f64>,
+    pub max_fiat_price: Option<f64>,
+    /// Deviation of the price from the mean price of the pair over `window`, like 0.02 for 2%
+    pub min_price_deviation: Option<f64>,
+    /// Fiat volume of the pair over `window`, including the order
+    pub min_window_volume: Option<f64>,
+    #[serde(default = "default_window", deserialize_with = "duration")]
+    pub window: Duration,
+    /// Names of the sinks notified, like `discord` or `ntfy`, every notifier when empty
+    #[serde(default)]
+    pub notify: Vec<String>,
+    /// Time after firing during which the rule stays silent
+    #[serde(default, deserialize_with = "duration")]
+    pub cooldown: Duration,
+}
+
+fn default_window() -> Duration {
+    Duration::from_secs(15 * 60)
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    humantime::parse_duration(&value).map_err(serde::de::Error::custom)
+}
+
+impl AlertRule {
+    pub fn matches(&self, order: &Order) -> bool {
+        self.ty.as_ref().is_none_or(|ty| *ty == order.ty)
+            && self
+                .symbol
+                .as_ref()
+                .is_none_or(|symbol| *symbol == order.crypto_symbol)
+            && self.pair.as_ref().is_none_or(|pair| {
+                pair.crypto_symbol == order.crypto_symbol && pair.fiat_symbol == order.fiat_symbol
+            })
+            && self
+                .min_crypto_amount
+                .is_none_or(|min| order.crypto_amount >= min)
+            && self
+                .min_fiat_amount
+                .is_none_or(|min| order.fiat_amount >= min)
+            && self
+                .max_fiat_amount
+                .is_none_or(|max| order.fiat_amount <= max)
+            && self
+                .min_fiat_price
+                .is_none_or(|min| order.fiat_price >= min)
+            && self
+                .max_fiat_price
+                .is_none_or(|max| order.fiat_price <= max)
+    }
+
+    /// Whether the rule notifies through the sink named `sink`
+    pub fn notifies(&self, sink: &str) -> bool {
+        self.notify.is_empty()
+            || self
+                .notify
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(sink))
+    }
+}
+
+/// Evaluates the alert rules on every new order, keeping the recent orders of
+/// every pair for the window conditions and the last firing of every rule
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    window: Duration,
+    orders: HashMap<Pair, VecDeque<(DateTime<Utc>, f64, f64)>>,
+    fired: HashMap<usize, DateTime<Utc>>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            window: rules
+                .iter()
+                .map(|rule| rule.window)
+                .max()
+                .unwrap_or_default(),
+            rules,
+            orders: HashMap::new(),
+            fired: HashMap::new(),
+        }
+    }
+
+    /// Replaces the rules, keeping the recent orders and the cooldowns of
+    /// the rules still named the same
+    pub fn set_rules(&mut self, rules: Vec<AlertRule>) {
+        self.fired = self
+            .fired
+            .drain()
+            .filter_map(|(i, fired_at)| {
+                rules
+                    .iter()
+                    .position(|rule| rule.name == self.rules[i].name)
+                    .map(|i| (i, fired_at))
+            })
+            .collect();
+        self.window = rules
+            .iter()
+            .map(|rule| rule.window)
+            .max()
+            .unwrap_or_default();
+        self.rules = rules;
+    }
+
+    /// Returns the rules fired by the order seen at `at`, with their index and message
+    pub fn evaluate(
+        &mut self,
+        order: &Order,
+        at: DateTime<Utc>,
+    ) -> Vec<(usize, &AlertRule, String)> {
+        let recent = self.orders.entry(Pair::of(order)).or_default();
+        let oldest = at - TimeDelta::from_std(self.window).unwrap_or_default();
+
+        while recent
+            .front()
+            .is_some_and(|(seen_at, _, _)| *seen_at < oldest)
+        {
+            recent.pop_front();
+        }
+
+        let mut fired = Vec::new();
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.matches(order) {
+                continue;
+            }
+
+            let since = at - TimeDelta::from_std(rule.window).unwrap_or_default();
+            let in_window = recent.iter().filter(|(seen_at, _, _)| *seen_at >= since);
+            let (count, price_sum, volume) = in_window.fold(
+                (0, 0.0, 0.0),
+                |(count, price_sum, volume), (_, price, amount)| {
+                    (count + 1, price_sum + price, volume + amount)
+                },
+            );
+            let deviation =
+                (count > 0).then(|| (order.fiat_price / (price_sum / count as f64) - 1.0).abs());
+
+            if rule
+                .min_price_deviation
+                .is_some_and(|min| deviation.is_none_or(|deviation| deviation < min))
+                || rule
+                    .min_window_volume
+                    .is_some_and(|min| volume + order.fiat_amount < min)
+            {
+                continue;
+            }
+
+            if self.fired.get(&i).is_some_and(|fired_at| {
+                at < *fired_at + TimeDelta::from_std(rule.cooldown).unwrap_or_default()
+            }) {
+                continue;
+            }
+
+            self.fired.insert(i, at);
+
+            let mut message = format!("{}: {order}", rule.name);
+
+            if rule.min_price_deviation.is_some()
+                && let Some(deviation) = deviation
+            {
+                message.push_str(&format!(
+                    ", {:.1}% from the {} mean price",
+                    deviation * 100.0,
+                    humantime::format_duration(rule.window)
+                ));
+            }
+
+            if rule.min_window_volume.is_some() {
+                message.push_str(&format!(
+                    ", {:.2} {} traded over {}",
+                    volume + order.fiat_amount,
+                    order.fiat_symbol,
+                    humantime::format_duration(rule.window)
+                ));
+            }
+
+            fired.push((i, rule, message));
+        }
+
+        recent.push_back((at, order.fiat_price, order.fiat_amount));
+
+        fired
+    }
+}
+
+/// Fires when an indicator of a pair crosses a level at the close of a candle
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndicatorRule {
+    pub name: String,
+    pub pair: Pair,
+    pub timeframe: Timeframe,
+    pub indicator: Indicator,
+    pub crosses_above: Option<f64>,
+    pub crosses_below: Option<f64>,
+}
+
+impl IndicatorRule {
+    /// Returns the start of the candles whose close crossed a level of the rule
+    pub fn crossings(&self, points: &[IndicatorPoint]) -> Vec<DateTime<Utc>> {
+        points
+            .windows(2)
+            .filter_map(|window| {
+                let previous = window[0].get(self.indicator)?;
+                let current = window[1].get(self.indicator)?;
+                let above = self
+                    .crosses_above
+                    .is_some_and(|level| previous <= level && current > level);
+                let below = self
+                    .crosses_below
+                    .is_some_and(|level| previous >= level && current < level);
+
+                (above || below).then_some(window[1].start)
+            })
+            .collect()
+    }
+
+    /// Computes the indicators of the candles between `from` and `to`, with the
+    /// earlier candles they need to settle
+    fn points(
+        &self,
+        persist_path: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<IndicatorPoint>> {
+        let timeframe = self.timeframe.duration();
+        let candles = get_candles(persist_path, &self.pair, timeframe, self.warm_up(from), to)?;
+
+        Ok(compute(&candles))
+    }
+
+    /// Same as [`IndicatorRule::points`], reading the candles off the runtime
+    async fn stored_points(
+        &self,
+        store: &Store,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<IndicatorPoint>> {
+        let timeframe = self.timeframe.duration();
+        let candles = store
+            .get_candles(self.pair.clone(), timeframe, self.warm_up(from), to)
+            .await?;
+
+        Ok(compute(&candles))
+    }
+
+    /// Start of the earlier candles the indicators at `from` need to settle
+    fn warm_up(&self, from: DateTime<Utc>) -> DateTime<Utc> {
+        from - self.timeframe.duration() * WARM_UP as i32
+    }
+}
+
+/// Checks the indicator rules whenever a candle of their timeframe closes
+#[derive(Debug)]
+pub struct IndicatorAlerts {
+    rules: Vec<IndicatorRule>,
+    /// Start of the latest closed candle checked for each rule
+    checked: HashMap<usize, DateTime<Utc>>,
+}
+
+impl IndicatorAlerts {
+    pub fn new(rules: Vec<IndicatorRule>) -> Self {
+        Self {
+            rules,
+            checked: HashMap::new(),
+        }
+    }
+
+    /// Replaces the rules, the ones still named the same not checking the
+    /// candle they already checked again
+    pub fn set_rules(&mut self, rules: Vec<IndicatorRule>) {
+        self.checked = self
+            .checked
+            .drain()
+            .filter_map(|(i, closed)| {
+                rules
+                    .iter()
+                    .position(|rule| rule.name == self.rules[i].name)
+                    .map(|i| (i, closed))
+            })
+            .collect();
+        self.rules = rules;
+    }
+
+    /// Returns the alert messages of the rules whose indicator crossed a level
+    /// at the close of the latest candle
+    pub async fn check(
+        &mut self,
+        store: &Store,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut messages = Vec::new();
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            let timeframe = rule.timeframe.duration();
+            let closed = now.duration_trunc(timeframe)? - timeframe;
+
+            if self.checked.insert(i, closed) == Some(closed) {
+                continue;
+            }
+
+            let points = rule
+                .stored_points(store, closed, closed + timeframe)
+                .await?;
+
+            if let Some(point) = points.last()
+                && rule.crossings(&points).last() == Some(&closed)
+            {
+                messages.push(format!(
+                    "{}: {:?} of {} on {} candles is {:.2}",
+                    rule.name,
+                    rule.indicator,
+                    rule.pair,
+                    rule.timeframe,
+                    point.get(rule.indicator).unwrap_or_default()
+                ));
+            }
+        }
+
+        Ok(messages)
+    }
+}
+
+/// How often a rule would have fired over the stored history
+#[derive(Debug)]
+pub struct BacktestResult<'a> {
+    pub name: &'a str,
+    pub fired: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+pub fn backtest<'a>(
+    persist_path: &str,
+    rules: &'a AlertRules,
+    since: DateTime<Utc>,
+) -> anyhow::Result<(usize, Vec<BacktestResult<'a>>)> {
+    let orders = get_orders_since(persist_path, since)?;
+    let mut engine = AlertEngine::new(rules.rules.clone());
+    let mut fired_at = vec![Vec::new(); rules.rules.len()];
+
+    for (created_at, order) in &orders {
+        for (i, _, _) in engine.evaluate(order, *created_at) {
+            fired_at[i].push(*created_at);
+        }
+    }
+
+    let results = rules
+        .rules
+        .iter()
+        .zip(fired_at)
+        .map(|(rule, fired_at)| BacktestResult {
+            name: &rule.name,
+            fired: fired_at.len(),
+            first: fired_at.first().copied(),
+            last: fired_at.last().copied(),
+        })
+        .collect::<Vec<_>>();
+
+    let now = Utc::now();
+    let indicator_results = rules
+        .indicator_rules
+        .iter()
+        .map(|rule| {
+            let fired_at = rule
+                .crossings(&rule.points(persist_path, since, now)?)
+                .into_iter()
+                .filter(|start| *start >= since)
+                .collect::<Vec<_>>();
+
+            Ok(BacktestResult {
+                name: &rule.name,
+                fired: fired_at.len(),
+                first: fired_at.first().copied(),
+                last: fired_at.last().copied(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok((orders.len(), [results, indicator_results].concat()))
+}
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{args::Args, export::Pair, fetch::Order};
+
+/// An order whose price is far from the recent prices of its pair
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub order: Order,
+    pub mean_price: f64,
+    pub std_dev: f64,
+    pub z_score: f64,
+}
+
+/// Keeps the latest prices of every pair, to compare each new order with
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    threshold: f64,
+    window: usize,
+    min_samples: usize,
+    prices: HashMap<Pair, VecDeque<f64>>,
+}
+
+impl AnomalyDetector {
+    /// Returns no detector when no threshold is configured
+    pub fn from_args(args: &Args) -> Option<Self> {
+        Some(Self {
+            threshold: args.anomaly_threshold?,
+            window: args.anomaly_window.max(2),
+            min_samples: args.anomaly_min_samples.max(2),
+            prices: HashMap::new(),
+        })
+    }
+
+    /// Compares the order price with the previous ones of its pair, then adds
+    /// it to the window so that a lasting move stops being flagged
+    pub fn observe(&mut self, order: &Order) -> Option<Anomaly> {
+        let prices = self.prices.entry(Pair::of(order)).or_default();
+        let anomaly = (prices.len() >= self.min_samples)
+            .then(|| {
+                let mean = prices.iter().sum::<f64>() / prices.len() as f64;
+                let variance = prices
+                    .iter()
+                    .map(|price| (price - mean).powi(2))
+                    .sum::<f64>()
+                    / (prices.len() - 1) as f64;
+
+                (mean, variance.sqrt())
+            })
+            .filter(|(_, std_dev)| *std_dev > 0.0)
+            .map(|(mean, std_dev)| Anomaly {
+                order: order.clone(),
+                mean_price: mean,
+                std_dev,
+                z_score: (order.fiat_price - mean) / std_dev,
+            })
+            .filter(|anomaly| anomaly.z_score.abs() >= self.threshold);
+
+        if prices.len() == self.window {
+            prices.pop_front();
+        }
+
+        prices.push_back(order.fiat_price);
+
+        anomaly
+    }
+}
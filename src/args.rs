@@ -1,11 +1,1194 @@
-use clap::Parser;
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand, ValueEnum};
+use directories::ProjectDirs;
+use tracing::level_filters::LevelFilter;
+
+use crate::{
+    db::MEMORY_PATH,
+    dedup::DedupWindow,
+    export::Pair,
+    fetch::OrderType,
+    indicators::Timeframe,
+    logging::{LogFormat, LogRotation},
+    metrics::MetricsBackend,
+    numbers::Locale,
+    pnl::CostMethod,
+    scheduler::Schedule,
+    source::SourceKind,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    #[arg(long, env)]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// DuckDB file the orders are stored in, in the data directory of the
+    /// platform by default, like `~/.local/share/nash-stats/orders.duckdb`
+    #[arg(
+        long,
+        env,
+        default_value_t = default_persist_path(),
+        default_value_if("ephemeral", "true", MEMORY_PATH)
+    )]
     pub persist_path: String,
 
+    /// Keeps everything in memory, nothing being written to disk
+    #[arg(long, env, conflicts_with = "persist_path")]
+    pub ephemeral: bool,
+
+    /// Fetches, logs and notifies new orders without writing anything to the DB
+    #[arg(long, env)]
+    pub dry_run: bool,
+
+    /// Comma separated exchanges the orders are collected from
+    #[arg(long, env, value_enum, value_delimiter = ',', default_value = "nash")]
+    pub sources: Vec<SourceKind>,
+
+    /// Phoenix WebSocket of the `nash-stream` source, required by it as Nash
+    /// doesn't document a streaming endpoint
+    #[arg(long, env)]
+    pub stream_url: Option<String>,
+
+    /// Channel the completed orders are pushed on, required by the `nash-stream` source
+    #[arg(long, env)]
+    pub stream_topic: Option<String>,
+
+    /// Event of the channel carrying a completed order, required by the `nash-stream` source
+    #[arg(long, env)]
+    pub stream_event: Option<String>,
+
+    /// Interval between two heartbeats, the socket being reconnected after two silent ones
+    #[arg(long, env, default_value = "30s", value_parser = humantime::parse_duration)]
+    pub stream_heartbeat: Duration,
+
     #[arg(long, env, default_value_t = 2)]
     pub fetch_interval: u64,
+
+    /// HTTP, HTTPS or SOCKS5 proxy of every request, like `socks5://localhost:1080`,
+    /// the hosts of `NO_PROXY` excepted
+    #[arg(long, env)]
+    pub proxy: Option<String>,
+
+    /// PEM file with the certificates of additional trusted certificate authorities
+    #[arg(long, env)]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Disables the verification of the TLS certificates
+    #[arg(long, env)]
+    pub insecure: bool,
+
+    /// Time after which an HTTP request is given up, for a stalled API to count
+    /// as a failed fetch
+    #[arg(long, env, default_value = "30s", value_parser = humantime::parse_duration)]
+    pub request_timeout: Duration,
+
+    /// Random deviation of every fetch interval, in percent of it, like 10 for ±10%
+    #[arg(long, env, default_value_t = 0.0)]
+    pub fetch_jitter: f64,
+
+    /// Cron expression with seconds the fetches run at, like `*/5 * * * * *`, replacing the intervals
+    #[arg(long, env, conflicts_with = "adaptive_polling")]
+    pub schedule: Option<Schedule>,
+
+    /// Comma separated fetch intervals of some sources, as `SOURCE=DURATION` like `nash=5s`
+    #[arg(long, env, value_delimiter = ',', value_parser = parse_source_interval)]
+    pub source_intervals: Vec<(String, Duration)>,
+
+    #[arg(long, env, value_enum, default_value_t = LogFormat::Compact)]
+    pub log_format: LogFormat,
+
+    /// Level of the console logs, overridden by `RUST_LOG`
+    #[arg(long, env, default_value_t = LevelFilter::INFO)]
+    pub log_level: LevelFilter,
+
+    #[arg(long, env, default_value = "/logs/logs.txt")]
+    pub log_file: Option<PathBuf>,
+
+    /// Disables the log file
+    #[arg(long, env)]
+    pub no_log_file: bool,
+
+    #[arg(long, env, default_value_t = LevelFilter::INFO)]
+    pub log_file_level: LevelFilter,
+
+    /// How often the log file is rotated, the date being appended to its name when rotated
+    #[arg(long, env, value_enum, default_value_t = LogRotation::Never)]
+    pub log_rotation: LogRotation,
+
+    /// Number of rotated log files to keep, all by default
+    #[arg(long, env)]
+    pub log_max_files: Option<usize>,
+
+    /// OTLP/HTTP endpoint the spans of the fetch cycles are exported to, like
+    /// `http://localhost:4318`, disabled when not set
+    #[cfg(feature = "otlp")]
+    #[arg(long, env)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Comma separated `key=value` headers sent with the exported spans
+    #[cfg(feature = "otlp")]
+    #[arg(long, env, value_delimiter = ',')]
+    pub otlp_headers: Vec<String>,
+
+    /// Breaks the lock left on the database by another collector or writing command, for when it
+    /// isn't running anymore
+    #[arg(long)]
+    pub force: bool,
+
+    /// Adapts the fetch interval to the activity, between the min and max intervals
+    #[arg(long, env)]
+    pub adaptive_polling: bool,
+
+    #[arg(long, env, default_value_t = 1)]
+    pub min_fetch_interval: u64,
+
+    #[arg(long, env, default_value_t = 30)]
+    pub max_fetch_interval: u64,
+
+    /// How long to poll at `--burst-interval` after a fetch whose whole window
+    /// was new orders, extended by every gap found meanwhile, disabled when not set
+    #[arg(long, env, value_parser = humantime::parse_duration)]
+    pub burst_duration: Option<Duration>,
+
+    #[arg(long, env, default_value = "500ms", value_parser = humantime::parse_duration)]
+    pub burst_interval: Duration,
+
+    /// Maximum time between two orders on the same pair and side for them to belong to the same session
+    #[arg(long, env, default_value = "60s", value_parser = humantime::parse_duration)]
+    pub session_gap: Duration,
+
+    /// Orders remembered to tell the new ones apart, as a count, a time since
+    /// they were last fetched or both like `1000,24h`, loaded from the stored
+    /// orders at startup
+    #[arg(long, env, default_value = "1000,24h")]
+    pub dedup_window: DedupWindow,
+
+    /// Number of new orders buffered before being written in a single
+    /// transaction, orders are written as they come when neither this nor
+    /// `--write-buffer-interval` is set
+    #[arg(long, env)]
+    pub write_buffer_rows: Option<usize>,
+
+    /// How often the buffered orders are written
+    #[arg(long, env, value_parser = humantime::parse_duration)]
+    pub write_buffer_interval: Option<Duration>,
+
+    /// Address of the HTTP server exposing `/healthz`, the API and the dashboard, disabled when not set
+    #[arg(long, env)]
+    pub http_addr: Option<SocketAddr>,
+
+    /// Unix socket the running collector accepts the `ctl` commands on, disabled when not set
+    #[arg(long, env)]
+    pub control_socket: Option<PathBuf>,
+
+    /// Address of the gRPC server, disabled when not set
+    #[cfg(feature = "grpc")]
+    #[arg(long, env)]
+    pub grpc_addr: Option<SocketAddr>,
+
+    /// Address of the Arrow Flight server streaming the stored orders, disabled when not set
+    #[cfg(feature = "flight")]
+    #[arg(long, env)]
+    pub flight_addr: Option<SocketAddr>,
+
+    /// Rows older than this number of days are pruned on the retention schedule, kept forever when not set
+    #[arg(long, env)]
+    pub retention_days: Option<u32>,
+
+    /// When the rows are pruned, `hourly`, `daily` or a cron expression with seconds
+    #[arg(long, env, default_value = "daily")]
+    pub retention_schedule: Schedule,
+
+    /// When the `pair_summary_snapshot` table is refreshed, `hourly`, `daily` or a cron expression with seconds
+    #[arg(long, env, default_value = "hourly")]
+    pub summary_schedule: Schedule,
+
+    /// When the new orders are rolled up into the `orders_hourly` and `orders_daily` tables, `hourly`, `daily` or a cron expression with seconds
+    #[arg(long, env, default_value = "hourly")]
+    pub rollup_schedule: Schedule,
+
+    /// When the database is maintained like the `maintain` command does, `hourly`, `daily` or a cron expression with seconds, never when not set
+    #[arg(long, env)]
+    pub maintain_schedule: Option<Schedule>,
+
+    /// Directory the pruned orders are archived to, as Parquet files partitioned by day
+    #[arg(long, env)]
+    pub archive_dir: Option<String>,
+
+    /// S3 bucket the exports and archives are uploaded to, disabled when not set
+    #[arg(long, env)]
+    pub s3_bucket: Option<String>,
+
+    /// Endpoint of S3 compatible storages, like MinIO or R2
+    #[arg(long, env)]
+    pub s3_endpoint: Option<String>,
+
+    #[arg(long, env)]
+    pub s3_region: Option<String>,
+
+    #[arg(long, env)]
+    pub s3_access_key_id: Option<String>,
+
+    #[arg(long, env)]
+    pub s3_secret_access_key: Option<String>,
+
+    /// Key prefix of the uploaded files
+    #[arg(long, env, default_value = "")]
+    pub s3_prefix: String,
+
+    #[arg(long, env, default_value_t = 5)]
+    pub s3_max_retries: usize,
+
+    /// TOML file with the export subscriptions
+    #[arg(long, env)]
+    pub config: Option<PathBuf>,
+
+    /// Number of consecutive failed fetches after which a critical alert is raised
+    #[arg(long, env, default_value_t = 5)]
+    pub max_consecutive_failures: u32,
+
+    /// Time without a successful fetch after which a critical alert is raised
+    #[arg(long, env, default_value = "5m", value_parser = humantime::parse_duration)]
+    pub max_fetch_silence: Duration,
+
+    /// Length of the rolling window the buy/sell imbalance of each pair is computed over
+    #[arg(long, env, default_value = "15m", value_parser = humantime::parse_duration)]
+    pub imbalance_window: Duration,
+
+    /// Share of the window volume on one side, from 0.5 to 1, above which an alert is raised
+    #[arg(long, env)]
+    pub imbalance_alert_threshold: Option<f64>,
+
+    /// Number of orders in the window below which no imbalance alert is raised
+    #[arg(long, env, default_value_t = 10)]
+    pub imbalance_min_orders: usize,
+
+    /// Length of the rolling window the realized volatility of each pair is computed over
+    #[arg(long, env, default_value = "1h", value_parser = humantime::parse_duration)]
+    pub volatility_window: Duration,
+
+    /// Realized volatility of the window, like 0.02 for 2%, above which an alert is raised
+    #[arg(long, env)]
+    pub volatility_alert_threshold: Option<f64>,
+
+    /// Number of orders in the window below which no volatility alert is raised
+    #[arg(long, env, default_value_t = 10)]
+    pub volatility_min_orders: usize,
+
+    /// Number of standard deviations from the recent mean price above which an order is
+    /// flagged as an anomaly, enables the detection when set
+    #[arg(long, env)]
+    pub anomaly_threshold: Option<f64>,
+
+    /// Number of latest prices of each pair the mean and standard deviation are computed over
+    #[arg(long, env, default_value_t = 100)]
+    pub anomaly_window: usize,
+
+    /// Number of prices of a pair needed before its orders can be flagged
+    #[arg(long, env, default_value_t = 20)]
+    pub anomaly_min_samples: usize,
+
+    /// How often reference prices are fetched from CoinGecko, enables the comparison when set
+    #[arg(long, env, value_parser = humantime::parse_duration)]
+    pub reference_interval: Option<Duration>,
+
+    /// Base URL of the CoinGecko compatible API
+    #[arg(long, env, default_value = "https://api.coingecko.com/api/v3")]
+    pub reference_url: String,
+
+    #[arg(long, env)]
+    pub coingecko_api_key: Option<String>,
+
+    /// CoinGecko ids of the tracked cryptos, as `SYMBOL=id`
+    #[arg(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "BTC=bitcoin,ETH=ethereum,USDC=usd-coin,USDT=tether,NEO=neo"
+    )]
+    pub reference_coin_ids: Vec<String>,
+
+    /// Fiat currencies the reference prices are fetched in
+    #[arg(long, env, value_delimiter = ',', default_value = "EUR,USD")]
+    pub reference_fiats: Vec<String>,
+
+    /// Premium or discount to the reference price, like 0.02 for 2%, above which an alert is raised
+    #[arg(long, env, requires = "reference_interval")]
+    pub premium_alert_threshold: Option<f64>,
+
+    /// GraphQL endpoint of the Nash exchange API
+    #[arg(long, env, default_value = "https://app.nash.io/api/graphql")]
+    pub nash_graphql_url: String,
+
+    /// How often the Nash markets are stored, disabled when not set
+    #[arg(long, env, value_parser = humantime::parse_duration)]
+    pub markets_interval: Option<Duration>,
+
+    /// How often the Nash tickers are stored, disabled when not set
+    #[arg(long, env, value_parser = humantime::parse_duration)]
+    pub tickers_interval: Option<Duration>,
+
+    /// Nash API key, enables the collection of the completed orders of its account when set
+    #[arg(long, env)]
+    pub nash_api_key: Option<String>,
+
+    /// Authenticated endpoint listing the completed orders of the account
+    #[arg(
+        long,
+        env,
+        default_value = "https://app.nash.io/api/cash/my_completed_orders"
+    )]
+    pub my_orders_url: String,
+
+    /// How often the completed orders of the account are fetched
+    #[arg(long, env, default_value = "5m", value_parser = humantime::parse_duration)]
+    pub my_orders_interval: Duration,
+
+    /// Currency every fiat amount is converted to, like `USD`, enables the normalization when set
+    #[arg(long, env)]
+    pub fx_base: Option<String>,
+
+    /// Base URL of the Frankfurter compatible API providing the ECB daily rates
+    #[arg(long, env, default_value = "https://api.frankfurter.app")]
+    pub fx_url: String,
+
+    /// Rhai script whose `process(order)` function filters, transforms or annotates every new order
+    #[arg(long, env)]
+    pub script: Option<PathBuf>,
+
+    /// Clock skew with the API server, in seconds, above which a warning is logged
+    #[arg(long, env, default_value_t = 5)]
+    pub max_clock_skew: i64,
+
+    /// Number of fetches between two metrics summaries in the logs
+    #[arg(long, env, default_value_t = 300)]
+    pub metrics_log_interval: u64,
+
+    /// Where the metrics go, summarized in the logs or published to StatsD after every fetch
+    #[arg(long, env, value_enum, default_value_t = MetricsBackend::Log)]
+    pub metrics_backend: MetricsBackend,
+
+    #[arg(long, env, default_value = "127.0.0.1")]
+    pub statsd_host: String,
+
+    #[arg(long, env, default_value_t = 8125)]
+    pub statsd_port: u16,
+
+    /// Prepended to the name of every metric, followed by a dot
+    #[arg(long, env, default_value = "nash_stats")]
+    pub statsd_prefix: String,
+
+    /// Comma separated DogStatsD `key:value` tags added to every metric
+    #[arg(long, env, value_delimiter = ',')]
+    pub statsd_tags: Vec<String>,
+
+    /// InfluxDB base URL, enables the InfluxDB sink when set
+    #[arg(long, env)]
+    pub influx_url: Option<String>,
+
+    #[arg(long, env)]
+    pub influx_org: Option<String>,
+
+    #[arg(long, env)]
+    pub influx_bucket: Option<String>,
+
+    #[arg(long, env)]
+    pub influx_token: Option<String>,
+
+    /// ClickHouse HTTP interface URL, like `http://localhost:8123`, enables the ClickHouse sink when set
+    #[arg(long, env)]
+    pub clickhouse_url: Option<String>,
+
+    #[arg(long, env)]
+    pub clickhouse_user: Option<String>,
+
+    #[arg(long, env)]
+    pub clickhouse_password: Option<String>,
+
+    /// Database of the tables, created with them when missing
+    #[arg(long, env, default_value = "default")]
+    pub clickhouse_database: String,
+
+    #[arg(long, env, default_value = "nash_orders")]
+    pub clickhouse_orders_table: String,
+
+    /// Table of the per-cycle aggregates
+    #[arg(long, env, default_value = "nash_cycles")]
+    pub clickhouse_cycles_table: String,
+
+    /// Name of this collector in the rows, the host name by default
+    #[arg(long, env)]
+    pub clickhouse_collector: Option<String>,
+
+    /// Number of orders written in a single insert
+    #[arg(long, env, default_value_t = 1000)]
+    pub clickhouse_batch_rows: usize,
+
+    /// Longest time the orders are batched before being written
+    #[arg(long, env, default_value = "10s", value_parser = humantime::parse_duration)]
+    pub clickhouse_batch_interval: Duration,
+
+    /// Lets ClickHouse buffer the inserts server-side, for many collectors writing small batches
+    #[arg(long, env)]
+    pub clickhouse_async_insert: bool,
+
+    /// Elasticsearch or OpenSearch URL, like `http://localhost:9200`, enables the Elasticsearch sink when set
+    #[arg(long, env)]
+    pub elasticsearch_url: Option<String>,
+
+    #[arg(long, env)]
+    pub elasticsearch_username: Option<String>,
+
+    #[arg(long, env)]
+    pub elasticsearch_password: Option<String>,
+
+    /// Encoded API key, used instead of the username and password when set
+    #[arg(long, env)]
+    pub elasticsearch_api_key: Option<String>,
+
+    /// The orders are indexed into `<prefix>-YYYY.MM`, mapped by the index template of the same name
+    #[arg(long, env, default_value = "nash-orders")]
+    pub elasticsearch_index_prefix: String,
+
+    /// Comma separated Kafka bootstrap servers, enables the Kafka sink when set
+    #[cfg(feature = "kafka")]
+    #[arg(long, env)]
+    pub kafka_brokers: Option<String>,
+
+    #[cfg(feature = "kafka")]
+    #[arg(long, env, default_value = "nash-orders")]
+    pub kafka_topic: String,
+
+    /// How long, in seconds, undelivered orders are buffered while the brokers are unreachable
+    #[cfg(feature = "kafka")]
+    #[arg(long, env, default_value_t = 300)]
+    pub kafka_buffer_timeout: u64,
+
+    /// MQTT broker host, enables the MQTT sink when set
+    #[arg(long, env)]
+    pub mqtt_host: Option<String>,
+
+    #[arg(long, env, default_value_t = 1883)]
+    pub mqtt_port: u16,
+
+    #[arg(long, env)]
+    pub mqtt_tls: bool,
+
+    #[arg(long, env)]
+    pub mqtt_username: Option<String>,
+
+    #[arg(long, env)]
+    pub mqtt_password: Option<String>,
+
+    /// Topic template, `{symbol}`, `{fiat}` and `{type}` are replaced by the order values
+    #[arg(long, env, default_value = "nash/orders/{symbol}")]
+    pub mqtt_topic: String,
+
+    #[arg(long, env, default_value_t = 1)]
+    pub mqtt_qos: u8,
+
+    /// Redis URL, like `redis://localhost:6379`, enables the Redis sink when set
+    #[arg(long, env)]
+    pub redis_url: Option<String>,
+
+    /// Channel every new order is published to
+    #[arg(long, env, default_value = "nash:orders")]
+    pub redis_channel: String,
+
+    /// Channel template the orders are also published to, `{symbol}`, `{fiat}`
+    /// and `{type}` being replaced by the order values, disabled when empty
+    #[arg(long, env, default_value = "nash:orders:{symbol}")]
+    pub redis_symbol_channel: String,
+
+    /// NATS server URL, like `nats://localhost:4222`, enables the NATS sink when set
+    #[arg(long, env)]
+    pub nats_url: Option<String>,
+
+    /// Subject template, `{symbol}`, `{fiat}` and `{type}` are replaced by the order values
+    #[arg(long, env, default_value = "nash.orders.{symbol}")]
+    pub nats_subject: String,
+
+    /// Publishes to JetStream and waits for the stream to acknowledge every
+    /// order, a stream must already capture the subjects
+    #[arg(long, env)]
+    pub nats_jetstream: bool,
+
+    /// Orders kept while NATS is unreachable, the oldest being dropped beyond
+    #[arg(long, env, default_value_t = 10_000)]
+    pub nats_buffer_size: usize,
+
+    /// Discord webhook URL, enables the Discord sink when set
+    #[arg(long, env)]
+    pub discord_webhook_url: Option<String>,
+
+    /// Also post a summary of the previous day to the Discord webhook
+    #[arg(long, env)]
+    pub discord_daily_summary: bool,
+
+    /// Minijinja template of the Discord embed description of each order, with `order` and `cycle` variables
+    #[arg(long, env)]
+    pub discord_template: Option<PathBuf>,
+
+    /// Slack incoming webhook URL, enables the Slack sink when set
+    #[arg(long, env)]
+    pub slack_webhook_url: Option<String>,
+
+    /// Orders below this fiat amount are not posted to Slack
+    #[arg(long, env, default_value_t = 0.0)]
+    pub slack_min_fiat_amount: f64,
+
+    /// Orders from this fiat amount are posted to Slack as alerts
+    #[arg(long, env)]
+    pub slack_alert_fiat_amount: Option<f64>,
+
+    /// Minijinja template of the Slack message of each order, with `order` and `cycle` variables
+    #[arg(long, env)]
+    pub slack_template: Option<PathBuf>,
+
+    /// ntfy topic URL, like `https://ntfy.sh/my-topic`, enables ntfy notifications when set
+    #[arg(long, env)]
+    pub ntfy_url: Option<String>,
+
+    #[arg(long, env)]
+    pub ntfy_token: Option<String>,
+
+    /// Orders below this fiat amount are not notified
+    #[arg(long, env, default_value_t = 10_000.0)]
+    pub ntfy_min_fiat_amount: f64,
+
+    /// Orders from this fiat amount are notified with high priority
+    #[arg(long, env, default_value_t = 50_000.0)]
+    pub ntfy_high_fiat_amount: f64,
+
+    /// Orders from this fiat amount are notified with max priority
+    #[arg(long, env, default_value_t = 100_000.0)]
+    pub ntfy_urgent_fiat_amount: f64,
+
+    /// Minijinja template of the ntfy body of each order, with `order` and `cycle` variables
+    #[arg(long, env)]
+    pub ntfy_template: Option<PathBuf>,
+
+    /// Shows the new orders as native notifications of the workstation
+    #[cfg(feature = "desktop")]
+    #[arg(long, env)]
+    pub desktop_notify: bool,
+
+    /// Orders below this fiat amount are not shown
+    #[cfg(feature = "desktop")]
+    #[arg(long, env, default_value_t = 0.0)]
+    pub desktop_min_fiat_amount: f64,
+
+    /// Only the orders of this pair are shown, like `BTC/USD`
+    #[cfg(feature = "desktop")]
+    #[arg(long, env)]
+    pub desktop_pair: Option<Pair>,
+
+    /// Only the orders of this type are shown
+    #[cfg(feature = "desktop")]
+    #[arg(long, env)]
+    pub desktop_type: Option<OrderType>,
+
+    /// Notifications shown per minute at most, the orders beyond being counted in a single one
+    #[cfg(feature = "desktop")]
+    #[arg(long, env, default_value_t = 10)]
+    pub desktop_max_per_minute: usize,
+
+    /// Timezone the timestamps are displayed in, like `Europe/Paris`, while they're stored in UTC
+    #[arg(long, env, default_value = "UTC")]
+    pub timezone: Tz,
+
+    /// Locale of the numbers of the logs, stats tables and notifications
+    #[arg(long, env, value_enum, default_value_t = Locale::En)]
+    pub locale: Locale,
+
+    /// Decimals of the crypto amounts of the logs, stats tables and notifications, trailing zeros left out, per currency when not set
+    #[arg(long, env)]
+    pub crypto_decimals: Option<usize>,
+
+    /// Decimals of the fiat amounts and prices of the logs, stats tables and notifications, per currency when not set
+    #[arg(long, env)]
+    pub fiat_decimals: Option<usize>,
+
+    /// Locale of the numbers and order sides of the templated messages
+    #[arg(long, env, value_enum, default_value_t = Locale::En)]
+    pub notification_locale: Locale,
+
+    /// SMTP server, enables the email digest when set
+    #[arg(long, env)]
+    pub smtp_host: Option<String>,
+
+    #[arg(long, env, default_value_t = 587)]
+    pub smtp_port: u16,
+
+    #[arg(long, env)]
+    pub smtp_username: Option<String>,
+
+    #[arg(long, env)]
+    pub smtp_password: Option<String>,
+
+    #[arg(long, env)]
+    pub digest_from: Option<String>,
+
+    /// Comma separated recipients of the digest
+    #[arg(long, env, value_delimiter = ',')]
+    pub digest_to: Vec<String>,
+
+    /// `hourly`, `daily` or a cron expression with seconds
+    #[arg(long, env, default_value = "daily")]
+    pub digest_schedule: Schedule,
+
+    /// Minijinja template of the digest body, replacing the built-in one
+    #[arg(long, env)]
+    pub digest_template: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Work with alert rules
+    Alerts {
+        #[command(subcommand)]
+        command: AlertsCommand,
+    },
+
+    /// Checks the config file and shows the settings in effect
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Sends a command to the running collector through `--control-socket`
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+
+    /// Checks the database, the API and the config, printing actionable findings
+    Doctor,
+
+    /// Prints the collector health from the database, exiting with an error when unhealthy
+    Health,
+
+    /// Creates the database, or with `--interactive` walks through a first
+    /// setup, writes it as an environment file and tries it
+    Init {
+        /// Asks for the persist path, fetch interval, sources and notifiers,
+        /// then runs a test fetch and sends a test notification
+        #[arg(long)]
+        interactive: bool,
+
+        /// Environment file the answers are written to, loadable with
+        /// `docker run --env-file` or systemd `EnvironmentFile=`
+        #[arg(long, default_value = "nash-stats.env")]
+        output: PathBuf,
+    },
+
+    /// Prints the uptime, cycles, inserted orders, errors, last fetch latency
+    /// and database size of the collector running with `--control-socket`,
+    /// or the totals of the fetch runs stored in the database
+    Status,
+
+    /// Reads every table back, rebuilds the indexes and reclaims the space of the deleted rows
+    Maintain,
+
+    /// Inserts the orders of another database, like a second collector's, skipping the ones already stored
+    Merge {
+        /// DuckDB file the orders are read from, left untouched
+        #[arg(long)]
+        from: PathBuf,
+    },
+
+    /// Moves the whole collector state, database and config, between hosts
+    State {
+        #[command(subcommand)]
+        command: StateCommand,
+    },
+
+    /// Applies the pending schema migrations, which the collector also does at startup
+    Migrate {
+        /// Only lists the pending migrations
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Deletes the rows older than the retention
+    Prune {
+        /// Overrides `--retention-days`
+        #[arg(long)]
+        older_than_days: Option<u32>,
+    },
+
+    /// Prints activity and session statistics per pair
+    Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommand>,
+
+        /// How far back to look, like `30d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        since: Duration,
+
+        /// Also draws price and volume charts of `pair` in the terminal
+        #[arg(long, requires = "pair")]
+        chart: bool,
+
+        /// Pair as `CRYPTO/FIAT`
+        #[arg(long)]
+        pair: Option<Pair>,
+    },
+
+    /// Computes the cost basis and realized gains of the account orders, per pair and per year
+    Pnl {
+        #[arg(long, value_enum, default_value_t = CostMethod::Fifo)]
+        method: CostMethod,
+
+        /// CSV file of trades to use instead of the collected account orders, with the
+        /// `completed_at`, `type`, `crypto_symbol`, `crypto_amount`, `fiat_symbol` and
+        /// `fiat_amount` columns
+        #[arg(long)]
+        csv: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+
+    /// Exports a consistent snapshot of the database to a directory, while the collector runs
+    Backup {
+        /// Directory the backup is written to, it must not exist or be empty
+        #[arg(long)]
+        output: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = BackupCompression::Zstd)]
+        compression: BackupCompression,
+
+        /// Loads the backup back in memory to check its row counts
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Imports a backup into a new database at `--persist-path`
+    Restore {
+        /// Directory written by the backup command
+        #[arg(long)]
+        input: PathBuf,
+    },
+
+    /// Renders the price and volume charts of a pair to a PNG or SVG file
+    Chart {
+        /// Pair as `CRYPTO/FIAT`
+        #[arg(long)]
+        pair: Pair,
+
+        /// How far back to look, like `30d` or `12h`
+        #[arg(long, default_value = "7d", value_parser = humantime::parse_duration)]
+        since: Duration,
+
+        /// Output file, rendered as SVG when its extension is `.svg` and as PNG otherwise
+        #[arg(long)]
+        output: PathBuf,
+    },
+
+    /// Appends the activity of a day, a row per pair, to a Google Sheet, or
+    /// writes the stored orders as Arrow IPC or JSON Lines
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportTarget::Sheets)]
+        format: ExportTarget,
+
+        /// Id of the Google Sheet, from its URL
+        #[arg(long, required_if_eq("format", "sheets"))]
+        sheets: Option<String>,
+
+        /// Tab of the sheet the rows are appended to
+        #[arg(long, default_value = "Daily")]
+        sheet: String,
+
+        /// Key file of the service account the sheet is shared with
+        #[arg(
+            long,
+            env = "GOOGLE_APPLICATION_CREDENTIALS",
+            required_if_eq("format", "sheets")
+        )]
+        credentials: Option<PathBuf>,
+
+        /// Day exported, yesterday by default for a sheet and every day for Arrow
+        #[arg(long)]
+        day: Option<NaiveDate>,
+
+        /// Pair of the orders written as Arrow or JSON Lines, all pairs by default
+        #[arg(long)]
+        pair: Option<Pair>,
+
+        /// Arrow IPC file the orders are written to, an Arrow IPC stream on stdout by default
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// With the jsonl format, keeps printing the orders as they are inserted
+        #[arg(short, long, conflicts_with = "day")]
+        follow: bool,
+
+        /// How often the database is checked for new orders when following
+        #[arg(long, default_value = "2s", value_parser = humantime::parse_duration)]
+        interval: Duration,
+    },
+
+    /// Generates a report of the activity over a date range
+    Report {
+        /// First day of the report, a week before `to` by default
+        #[arg(long)]
+        from: Option<NaiveDate>,
+
+        /// Day after the last day of the report, today by default
+        #[arg(long)]
+        to: Option<NaiveDate>,
+
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+        format: ReportFormat,
+
+        /// File the report is written to, stdout by default
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Prints the latest stored orders, and with `--follow` the ones a running collector inserts
+    Tail {
+        /// Keeps printing orders as they are inserted
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of past orders to print first
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+
+        /// How often the database is checked for new orders
+        #[arg(long, default_value = "2s", value_parser = humantime::parse_duration)]
+        interval: Duration,
+
+        /// Only prints orders of this pair, as `CRYPTO/FIAT`
+        #[arg(long)]
+        pair: Option<Pair>,
+
+        /// Only prints `buy` or `sell` orders
+        #[arg(long = "type")]
+        ty: Option<OrderType>,
+
+        /// Only prints orders worth at least this fiat amount
+        #[arg(long)]
+        min_fiat_amount: Option<f64>,
+    },
+
+    /// Runs a read-only SQL query against the database and prints the result
+    Query {
+        /// SELECT query to run
+        sql: String,
+
+        #[arg(long, value_enum, default_value_t = QueryFormat::Table)]
+        format: QueryFormat,
+    },
+
+    /// Prints the table and view definitions with their column documentation
+    Schema {
+        #[arg(long, value_enum, default_value_t = SchemaFormat::Sql)]
+        format: SchemaFormat,
+
+        /// Also creates or refreshes the `information` view documenting every column
+        #[arg(long)]
+        create_view: bool,
+    },
+}
+
+impl Command {
+    /// Whether the command writes to the database, locking it like the collector
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            Command::Init { .. }
+                | Command::Maintain
+                | Command::Merge { .. }
+                | Command::State {
+                    command: StateCommand::Import { .. }
+                }
+                | Command::Migrate { dry_run: false }
+                | Command::Prune { .. }
+                | Command::Restore { .. }
+                | Command::Schema {
+                    create_view: true,
+                    ..
+                }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BackupCompression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Zstd,
+}
+
+impl BackupCompression {
+    pub fn as_duckdb(&self) -> &'static str {
+        match self {
+            BackupCompression::Uncompressed => "UNCOMPRESSED",
+            BackupCompression::Snappy => "SNAPPY",
+            BackupCompression::Gzip => "GZIP",
+            BackupCompression::Zstd => "ZSTD",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportTarget {
+    /// A row per pair appended to a Google Sheet
+    Sheets,
+    /// The orders themselves, for pyarrow, polars or the R arrow package
+    Arrow,
+    /// The orders themselves on stdout, an object per line for `jq` and the like
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum QueryFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SchemaFormat {
+    Sql,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommand {
+    /// Shows the largest orders, the most traded pairs and the most active blockchains
+    Top {
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        /// Number of rows of each table
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Shows the distribution and percentiles of the order fiat amounts of each pair
+    Histogram {
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        /// Pair as `CRYPTO/FIAT`, all pairs when missing
+        #[arg(long)]
+        pair: Option<Pair>,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+
+    /// Shows the moving averages, RSI and MACD of the candles of a pair
+    Indicators {
+        /// Pair as `CRYPTO/FIAT`
+        #[arg(long)]
+        pair: Pair,
+
+        #[arg(long, value_enum, default_value_t = Timeframe::OneHour)]
+        timeframe: Timeframe,
+
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "2d", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+
+    /// Shows the premium or discount of the order prices to the stored reference prices
+    Premium {
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+
+    /// Shows the share of the fiat volume bought on each pair, from the stored imbalances
+    Imbalance {
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+
+    /// Shows the volume executed at each price level of a pair
+    Profile {
+        /// Pair as `CRYPTO/FIAT`
+        #[arg(long)]
+        pair: Pair,
+
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        /// Number of price levels the price range is split in
+        #[arg(long, default_value_t = 20)]
+        levels: usize,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+
+    /// Shows the orders by hour of the day and day of the week, in the `--timezone`
+    Heatmap {
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "28d", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        /// Pair as `CRYPTO/FIAT`, all pairs when missing
+        #[arg(long)]
+        pair: Option<Pair>,
+
+        /// What the shades of the terminal heatmap stand for
+        #[arg(long, value_enum, default_value_t = HeatmapMetric::Orders)]
+        metric: HeatmapMetric,
+
+        #[arg(long, value_enum, default_value_t = QueryFormat::Table)]
+        format: QueryFormat,
+    },
+
+    /// Shows the realized volatility of the order prices of each pair, over the whole period
+    /// and over rolling windows
+    Volatility {
+        /// How far back to look, like `7d` or `12h`
+        #[arg(long, default_value = "24h", value_parser = humantime::parse_duration)]
+        window: Duration,
+
+        /// Length of the rolling windows, like `1h`
+        #[arg(long, default_value = "1h", value_parser = humantime::parse_duration)]
+        rolling: Duration,
+
+        /// Pair as `CRYPTO/FIAT`, all pairs when missing
+        #[arg(long)]
+        pair: Option<Pair>,
+
+        #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+        format: StatsFormat,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum HeatmapMetric {
+    /// Number of orders
+    Orders,
+    /// Fiat volume, only meaningful for a single fiat currency
+    Volume,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum StatsFormat {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum AlertsCommand {
+    /// Evaluates alert rules against the stored orders and reports how often each would have fired
+    Backtest {
+        /// TOML file with the alert rules, the ones of the config file when missing
+        #[arg(long)]
+        rules: Option<PathBuf>,
+
+        /// How far back to look, like `30d` or `12h`
+        #[arg(long, default_value = "30d", value_parser = humantime::parse_duration)]
+        since: Duration,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Parses a config file and checks its names and tokens, exiting with an error when invalid
+    Validate {
+        /// Config file checked, the one of `--config` by default
+        file: Option<PathBuf>,
+    },
+
+    /// Prints every option with where its value comes from, then the config file, secrets redacted
+    PrintEffective,
+}
+
+#[derive(Subcommand)]
+pub enum StateCommand {
+    /// Bundles the database as Parquet, its schema and the config file into a tar archive
+    Export {
+        /// Archive written
+        #[arg(long)]
+        output: PathBuf,
+    },
+
+    /// Restores an archive written by `state export` into a new database at `--persist-path`
+    Import {
+        /// Archive read
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Where the config of the archive is written, left out when not set
+        #[arg(long)]
+        config_output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum CtlCommand {
+    /// Stops fetching until `resume`, the fetches in progress completing
+    Pause,
+
+    /// Fetches again after a `pause`
+    Resume,
+
+    /// Fetches every source right away, even when paused
+    FetchNow,
+
+    /// Writes the orders held by the write buffer
+    Flush,
+
+    /// Changes the interval between two fetches of every source until restart
+    SetInterval {
+        /// Interval, like `5s`
+        #[arg(value_parser = humantime::parse_duration)]
+        interval: Duration,
+    },
+
+    /// Prints whether the collector is paused and healthy, with its metrics
+    Status,
+}
+
+/// `orders.duckdb` in the data directory of the platform, or in the working
+/// directory when there is no home directory
+fn default_persist_path() -> String {
+    ProjectDirs::from("", "", "nash-stats")
+        .map(|dirs| dirs.data_dir().join("orders.duckdb"))
+        .unwrap_or_else(|| PathBuf::from("orders.duckdb"))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn parse_source_interval(value: &str) -> Result<(String, Duration), String> {
+    let (source, interval) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Source interval {value} should be formatted as SOURCE=DURATION"))?;
+    let interval = humantime::parse_duration(interval).map_err(|err| err.to_string())?;
+
+    Ok((source.to_string(), interval))
 }
@@ -0,0 +1,69 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{db::OrderCursor, fetch::Order};
+
+/// Atom feed of `orders`, newest first, titled after the filter that selected them
+pub fn render(title: &str, self_url: &str, orders: &[(OrderCursor, Order)]) -> String {
+    let updated = orders
+        .first()
+        .map_or(DateTime::UNIX_EPOCH, |(cursor, _)| cursor.created_at);
+    let mut feed = String::new();
+
+    let _ = write!(
+        feed,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <id>tag:nash-stats,2024:{}</id>
+  <title>{}</title>
+  <updated>{}</updated>
+  <link rel="self" href="{}"/>
+  <author><name>nash-stats</name></author>
+"#,
+        escape(self_url),
+        escape(title),
+        timestamp(updated),
+        escape(self_url)
+    );
+
+    for (cursor, order) in orders {
+        let _ = write!(
+            feed,
+            r#"  <entry>
+    <id>tag:nash-stats,2024:order:{}</id>
+    <title>{} {} {} for {} {}</title>
+    <updated>{}</updated>
+    <category term="{}/{}"/>
+    <content type="text">{}</content>
+  </entry>
+"#,
+            escape(&cursor.hash),
+            order.ty,
+            order.crypto_amount,
+            escape(&order.crypto_symbol),
+            order.fiat_amount,
+            escape(&order.fiat_symbol),
+            timestamp(cursor.created_at),
+            escape(&order.crypto_symbol),
+            escape(&order.fiat_symbol),
+            escape(&order.to_string())
+        );
+    }
+
+    feed.push_str("</feed>\n");
+
+    feed
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
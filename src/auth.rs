@@ -0,0 +1,184 @@
+use std::{borrow::Cow, sync::Arc};
+
+use axum::{
+    Router,
+    extract::{Query, Request, State},
+    http::{
+        HeaderName, HeaderValue, Method, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
+    },
+    middleware::{Next, from_fn_with_state},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::debug;
+
+const API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Access to the HTTP server, `[http]` in the config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpAccess {
+    /// Tokens accepted by the API, open to anyone when empty
+    pub tokens: Vec<ApiToken>,
+    /// Origins allowed to call the API from a browser, `*` for any
+    pub cors_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiToken {
+    /// Shown in the logs instead of the token
+    pub name: String,
+    /// Sent as `Authorization: Bearer <token>`, `X-Api-Key: <token>` or, for
+    /// the browsers opening a WebSocket, the `access_token` query parameter
+    pub token: String,
+    /// Routes the token can read, all of them when not set
+    pub scopes: Option<Vec<Scope>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// `/orders` and `/ws`
+    Orders,
+    /// `/stats`, `/candles` and `/profile`
+    Stats,
+    /// `/graphql` and `/graphql/ws`
+    Graphql,
+    /// `/openapi.json`
+    Docs,
+}
+
+impl ApiToken {
+    fn allows(&self, scope: Scope) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.contains(&scope))
+    }
+}
+
+#[derive(Clone)]
+struct Guard {
+    tokens: Arc<[ApiToken]>,
+    scope: Scope,
+}
+
+impl HttpAccess {
+    pub fn is_open(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Requires a token allowed to read `scope` on the routes of `router`
+    pub fn protect<S>(&self, router: Router<S>, scope: Scope) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if self.is_open() {
+            return router;
+        }
+
+        let guard = Guard {
+            tokens: self.tokens.clone().into(),
+            scope,
+        };
+
+        router.route_layer(from_fn_with_state(guard, authorize))
+    }
+
+    /// Returns `None` when no origin is allowed, the browsers then only
+    /// calling the API from its own origin
+    pub fn cors(&self) -> anyhow::Result<Option<CorsLayer>> {
+        if self.cors_origins.is_empty() {
+            return Ok(None);
+        }
+
+        let cors = CorsLayer::new()
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([AUTHORIZATION, CONTENT_TYPE, API_KEY]);
+
+        if self.cors_origins.iter().any(|origin| origin == "*") {
+            return Ok(Some(cors.allow_origin(Any)));
+        }
+
+        let origins = self
+            .cors_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .map_err(|_| anyhow::anyhow!("Invalid CORS origin {origin}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Some(cors.allow_origin(AllowOrigin::list(origins))))
+    }
+}
+
+async fn authorize(State(guard): State<Guard>, request: Request, next: Next) -> Response {
+    let Some(presented) = presented_token(&request) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(WWW_AUTHENTICATE, "Bearer")],
+            "Missing API token",
+        )
+            .into_response();
+    };
+
+    let Some(token) = guard
+        .tokens
+        .iter()
+        .find(|token| constant_time_eq(token.token.as_bytes(), presented.as_bytes()))
+    else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(WWW_AUTHENTICATE, "Bearer")],
+            "Invalid API token",
+        )
+            .into_response();
+    };
+
+    if !token.allows(guard.scope) {
+        debug!(
+            "Token {} denied {:?} access to {}",
+            token.name,
+            guard.scope,
+            request.uri().path()
+        );
+
+        return (StatusCode::FORBIDDEN, "Token not allowed on this route").into_response();
+    }
+
+    next.run(request).await
+}
+
+fn presented_token(request: &Request) -> Option<Cow<'_, str>> {
+    let headers = request.headers();
+
+    if let Some(authorization) = headers.get(AUTHORIZATION)
+        && let Ok(authorization) = authorization.to_str()
+        && let Some((scheme, token)) = authorization.split_once(' ')
+        && scheme.eq_ignore_ascii_case("bearer")
+    {
+        return Some(Cow::Borrowed(token.trim()));
+    }
+
+    if let Some(api_key) = headers.get(API_KEY) {
+        return api_key.to_str().ok().map(Cow::Borrowed);
+    }
+
+    // Percent-encoded by the clients, like the dashboard
+    Query::<AccessToken>::try_from_uri(request.uri())
+        .ok()
+        .map(|Query(query)| Cow::Owned(query.access_token))
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessToken {
+    access_token: String,
+}
+
+/// Compares the tokens without leaking through timing how much of them matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
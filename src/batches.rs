@@ -0,0 +1,125 @@
+use std::sync::{Arc, LazyLock};
+
+use arrow::{
+    array::{ArrayRef, Float64Array, StringArray, TimestampMicrosecondArray},
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    record_batch::RecordBatch,
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    db::{OrderCursor, OrderFilter},
+    fetch::Order,
+};
+
+/// Orders read per query and per record batch
+pub const BATCH_ROWS: usize = 65_536;
+
+/// Columns of the orders read as Arrow, in the order of the table
+pub static SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "created_at",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("blockchain", DataType::Utf8, false),
+        Field::new("crypto_amount", DataType::Float64, false),
+        Field::new("crypto_symbol", DataType::Utf8, false),
+        Field::new("fiat_amount", DataType::Float64, false),
+        Field::new("fiat_price", DataType::Float64, false),
+        Field::new("fiat_symbol", DataType::Utf8, false),
+        Field::new("hash", DataType::Utf8, false),
+    ]))
+});
+
+/// Orders matching a filter created in a time range, read a page at a time
+/// in creation order, the caller querying the page after [`OrderRange::after`]
+#[derive(Debug, Clone)]
+pub struct OrderRange {
+    pub filter: OrderFilter,
+    pub after: Option<OrderCursor>,
+    to: Option<DateTime<Utc>>,
+}
+
+impl OrderRange {
+    pub fn new(
+        filter: OrderFilter,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            filter,
+            // No hash is empty, so the orders created at `from` are included
+            after: from.map(|created_at| OrderCursor {
+                created_at,
+                hash: String::new(),
+            }),
+            to,
+        }
+    }
+
+    /// Keeps the orders of the page read after the current cursor that are in
+    /// the range and moves the cursor past them, empty once the range is exhausted
+    pub fn advance(&mut self, mut page: Vec<(OrderCursor, Order)>) -> Vec<(OrderCursor, Order)> {
+        if let Some(to) = self.to {
+            page.retain(|(cursor, _)| cursor.created_at < to);
+        }
+
+        if let Some((last, _)) = page.last() {
+            self.after = Some(last.clone());
+        }
+
+        page
+    }
+
+    /// Converts the page read after the current cursor, `None` once the range
+    /// is exhausted
+    pub fn batch(
+        &mut self,
+        page: Vec<(OrderCursor, Order)>,
+    ) -> anyhow::Result<Option<RecordBatch>> {
+        let page = self.advance(page);
+
+        if page.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(record_batch(&page)?))
+    }
+}
+
+fn record_batch(orders: &[(OrderCursor, Order)]) -> anyhow::Result<RecordBatch> {
+    let strings = |value: fn(&(OrderCursor, Order)) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(orders.iter().map(value)))
+    };
+    let floats = |value: fn(&Order) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(
+            orders.iter().map(|(_, order)| value(order)),
+        ))
+    };
+
+    let columns = vec![
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                orders
+                    .iter()
+                    .map(|(cursor, _)| cursor.created_at.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ) as ArrayRef,
+        Arc::new(StringArray::from_iter_values(
+            orders.iter().map(|(_, order)| order.ty.to_string()),
+        )),
+        strings(|(_, order)| &order.blockchain),
+        floats(|order| order.crypto_amount),
+        strings(|(_, order)| &order.crypto_symbol),
+        floats(|order| order.fiat_amount),
+        floats(|order| order.fiat_price),
+        strings(|(_, order)| &order.fiat_symbol),
+        strings(|(cursor, _)| &cursor.hash),
+    ];
+
+    Ok(RecordBatch::try_new(SCHEMA.clone(), columns)?)
+}
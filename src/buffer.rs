@@ -0,0 +1,80 @@
+use std::{future::pending, time::Duration};
+
+use chrono::TimeDelta;
+use tokio::time::{Interval, MissedTickBehavior, interval};
+use tracing::{debug, error};
+
+use crate::{args::Args, db::NewOrder, store::Store};
+
+/// New orders held in memory to be written in a single transaction, when
+/// enough of them accumulated or on a timer
+#[derive(Debug)]
+pub struct WriteBuffer {
+    orders: Vec<NewOrder>,
+    max_rows: Option<usize>,
+    timer: Option<Interval>,
+}
+
+impl WriteBuffer {
+    /// Returns `None` when neither `--write-buffer-rows` nor
+    /// `--write-buffer-interval` is set, the orders being written as they come
+    pub fn from_args(args: &Args) -> Option<Self> {
+        if args.write_buffer_rows.is_none() && args.write_buffer_interval.is_none() {
+            return None;
+        }
+
+        let timer = args.write_buffer_interval.map(|every| {
+            let mut timer = interval(every.max(Duration::from_millis(1)));
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            timer
+        });
+
+        Some(Self {
+            orders: Vec::new(),
+            max_rows: args.write_buffer_rows,
+            timer,
+        })
+    }
+
+    /// Buffers `order`, returns whether the buffer is full
+    pub fn push(&mut self, order: NewOrder) -> bool {
+        self.orders.push(order);
+
+        self.max_rows
+            .is_some_and(|max_rows| self.orders.len() >= max_rows)
+    }
+
+    /// Completes at the next scheduled flush, never without a timer
+    pub async fn tick(&mut self) {
+        match &mut self.timer {
+            Some(timer) => {
+                timer.tick().await;
+            }
+            None => pending().await,
+        }
+    }
+
+    /// Writes the buffered orders in a single transaction, kept buffered for
+    /// the next flush when it fails, returns the number written
+    pub async fn flush(&mut self, store: &Store, session_gap: TimeDelta) -> usize {
+        if self.orders.is_empty() {
+            return 0;
+        }
+
+        let buffered = self.orders.len();
+
+        match store.insert_orders(self.orders.clone(), session_gap).await {
+            Ok(inserted) => {
+                debug!("Flushed {inserted} of {buffered} buffered orders");
+                self.orders.clear();
+                inserted
+            }
+            Err(err) => {
+                error!(
+                    "Failed to flush {buffered} buffered orders, kept for the next flush: {err}"
+                );
+                0
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs;
+
+use anyhow::Context;
+use reqwest::{Certificate, NoProxy, Proxy};
+use tracing::warn;
+
+use crate::args::Args;
+
+/// Builds the HTTP client shared by the sources and sinks, with the proxy,
+/// TLS and timeout settings of the args
+pub fn http_client(args: &Args) -> anyhow::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().timeout(args.request_timeout);
+
+    if let Some(url) = &args.proxy {
+        let proxy = Proxy::all(url)
+            .with_context(|| format!("Invalid proxy {url}"))?
+            .no_proxy(NoProxy::from_env());
+
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(path) = &args.ca_bundle {
+        let pem = fs::read(path)
+            .with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+
+        for certificate in Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if args.insecure {
+        warn!("TLS certificates are not verified, the API responses could be tampered with");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    Ok(builder.build()?)
+}
@@ -0,0 +1,433 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::{
+    select,
+    sync::{broadcast, mpsc},
+    time::{self, sleep_until},
+};
+use tracing::{Instrument, Span, debug_span, error, field::Empty, info, instrument, warn};
+
+use crate::{
+    args::Args,
+    control::CollectorSignal,
+    db::FetchRun,
+    dedup::SeenOrders,
+    fetch::{FetchError, Order, Timing},
+    gaps::{Burst, Gap, GapTracker},
+    liveness::{Liveness, LivenessChange},
+    polling::PollInterval,
+    scheduler::Schedule,
+    source::Source,
+};
+
+/// Wait after a rate-limited fetch when the API didn't say how long, doubled
+/// at every consecutive one
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(30);
+
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+/// How often the time since the last successful fetch is checked between fetches
+const SILENCE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Fetch of a single source, with the orders its previous fetch didn't return
+#[derive(Debug)]
+pub struct Collected {
+    pub run: FetchRun,
+    pub timing: Option<Timing>,
+    pub new_orders: Vec<Order>,
+    pub gap: Option<Gap>,
+    /// The source returned the same orders as its previous fetch
+    pub not_modified: bool,
+    /// Class of the error of a failed fetch
+    pub error_kind: Option<&'static str>,
+    /// The API is rate limiting, the next fetch being delayed by this long
+    pub rate_limited: Option<Duration>,
+    /// Burst of rapid polling that ended with this fetch
+    pub burst: Option<Burst>,
+    pub liveness_change: Option<LivenessChange>,
+    pub healthy: bool,
+    /// Span of the fetch, the writes of its orders belonging to it
+    pub span: Span,
+}
+
+/// A source and the state kept between its fetches
+pub struct Collector {
+    source: Source,
+    seen: SeenOrders,
+    /// Number of orders returned by the previous fetch
+    window_size: usize,
+    gaps: GapTracker,
+    liveness: Liveness,
+    interval: PollInterval,
+    schedule: Option<Schedule>,
+    max_clock_skew: i64,
+    /// Order fields unknown to the collector already reported
+    extra_fields: HashSet<String>,
+    /// Consecutive fetches rejected by the rate limit
+    rate_limited: u32,
+    burst_duration: Option<Duration>,
+    burst_interval: Duration,
+    burst: Option<BurstState>,
+    /// Paused from the control socket
+    paused: bool,
+}
+
+/// Burst in progress
+#[derive(Debug)]
+struct BurstState {
+    started_at: DateTime<Utc>,
+    until: Instant,
+    fetches: u64,
+    gaps: u64,
+}
+
+impl Collector {
+    pub fn new(source: Source, stored: Vec<(DateTime<Utc>, Order)>, args: &Args) -> Self {
+        Self {
+            interval: PollInterval::from_args(args, source.name()),
+            source,
+            seen: SeenOrders::new(args.dedup_window, stored),
+            window_size: 0,
+            gaps: GapTracker::default(),
+            liveness: Liveness::from_args(args),
+            schedule: args.schedule.clone(),
+            max_clock_skew: args.max_clock_skew,
+            extra_fields: HashSet::new(),
+            rate_limited: 0,
+            burst_duration: args.burst_duration,
+            burst_interval: args.burst_interval,
+            burst: None,
+            paused: false,
+        }
+    }
+
+    /// Fetches the source on its own interval, or at every run of the
+    /// schedule, until the receiving end of `collected` is dropped. The source
+    /// going silent between fetches is reported on `liveness`
+    pub fn spawn(
+        mut self,
+        collected: mpsc::Sender<Collected>,
+        liveness: mpsc::Sender<(&'static str, LivenessChange)>,
+        mut signals: broadcast::Receiver<CollectorSignal>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                let started_at = Utc::now();
+
+                let collected_fetch = self.collect().await;
+                let wait = collected_fetch.rate_limited;
+
+                if collected.send(collected_fetch).await.is_err() {
+                    break;
+                }
+
+                if self.burst.is_some() {
+                    self.wait(
+                        wait.map_or(self.burst_interval, |wait| wait.max(self.burst_interval)),
+                        &liveness,
+                        &mut signals,
+                    )
+                    .await;
+                    continue;
+                }
+
+                match &self.schedule {
+                    Some(schedule) => {
+                        let now = Utc::now();
+                        let next = schedule.next_after(
+                            now + wait
+                                .and_then(|wait| TimeDelta::from_std(wait).ok())
+                                .unwrap_or_default(),
+                        );
+
+                        // Runs missed while fetching are skipped rather than caught up
+                        if schedule.next_after(started_at) < now {
+                            warn!(
+                                "Fetching {} lasted past its next scheduled run, which is skipped",
+                                self.source.name()
+                            );
+                        }
+
+                        self.wait(
+                            (next - now).to_std().unwrap_or_default(),
+                            &liveness,
+                            &mut signals,
+                        )
+                        .await;
+                    }
+                    None => {
+                        let delay = self.interval.next_delay();
+
+                        self.wait(
+                            wait.map_or(delay, |wait| wait.max(delay)),
+                            &liveness,
+                            &mut signals,
+                        )
+                        .await;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Sleeps for `delay`, and on while paused, a `fetch-now` ending the wait
+    /// right away
+    async fn wait(
+        &mut self,
+        delay: Duration,
+        liveness: &mpsc::Sender<(&'static str, LivenessChange)>,
+        signals: &mut broadcast::Receiver<CollectorSignal>,
+    ) {
+        let mut deadline = time::Instant::now() + delay;
+        let mut silence_check = time::interval(SILENCE_CHECK_INTERVAL);
+
+        loop {
+            let paused = self.paused;
+
+            select! {
+                _ = sleep_until(deadline), if !paused => return,
+                _ = silence_check.tick(), if !paused => {
+                    if let Some(change) = self.liveness.check_silence()
+                        && liveness.send((self.source.name(), change)).await.is_err()
+                    {
+                        return;
+                    }
+                }
+                signal = signals.recv() => match signal {
+                    Ok(CollectorSignal::Pause) => self.paused = true,
+                    Ok(CollectorSignal::Resume) => self.paused = false,
+                    Ok(CollectorSignal::FetchNow) => return,
+                    Ok(CollectorSignal::SetInterval(interval)) => {
+                        self.interval.set(interval);
+
+                        if self.schedule.is_none() && self.burst.is_none() {
+                            deadline = time::Instant::now() + self.interval.next_delay();
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    // The main loop stopped, the next send failing ends the task
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+            }
+        }
+    }
+
+    #[instrument(
+        name = "cycle",
+        level = "debug",
+        skip_all,
+        fields(
+            source = self.source.name(),
+            orders_returned = Empty,
+            new_orders = Empty,
+            latency_ms = Empty,
+        )
+    )]
+    async fn collect(&mut self) -> Collected {
+        let run = FetchRun {
+            source: self.source.name(),
+            started_at: Utc::now(),
+            ..Default::default()
+        };
+
+        let fetch_result = self.source.fetch().instrument(debug_span!("fetch")).await;
+
+        let liveness_change = match &fetch_result {
+            Ok(_) => self.liveness.record_success(),
+            Err(err) => self.liveness.record_failure(err),
+        };
+
+        let mut collected = Collected {
+            run,
+            timing: None,
+            new_orders: Vec::new(),
+            gap: None,
+            not_modified: false,
+            error_kind: None,
+            rate_limited: None,
+            burst: None,
+            liveness_change,
+            healthy: self.liveness.is_healthy(),
+            span: Span::current(),
+        };
+
+        if fetch_result.is_ok() {
+            self.rate_limited = 0;
+        }
+
+        match fetch_result {
+            Ok(fetched) if fetched.not_modified => {
+                collected.rate_limited = self.quota_exhausted(fetched.retry_after);
+                let window_size = self.window_size;
+
+                collected.run.http_status = Some(fetched.status);
+                collected.run.latency = Some(fetched.timing.latency);
+                collected.run.orders_returned = Some(window_size);
+                collected.timing = Some(fetched.timing);
+                collected.not_modified = true;
+                collected.gap = self.gaps.observe(0, window_size);
+
+                self.interval.update(0, window_size);
+            }
+            Ok(fetched) => {
+                collected.rate_limited = self.quota_exhausted(fetched.retry_after);
+
+                let current_orders = fetched.orders;
+
+                collected.run.http_status = Some(fetched.status);
+                collected.run.latency = Some(fetched.timing.latency);
+                collected.run.orders_returned = Some(current_orders.len());
+                collected.timing = Some(fetched.timing);
+
+                if let Some(skew) = fetched.timing.skew
+                    && skew.num_seconds().abs() > self.max_clock_skew
+                {
+                    warn!(
+                        "Local clock is {}ms off from the {} API server, order timestamps may be inaccurate",
+                        -skew.num_milliseconds(),
+                        self.source.name()
+                    );
+                }
+
+                collected.new_orders = debug_span!("dedup")
+                    .in_scope(|| self.seen.observe(&current_orders, collected.run.started_at));
+
+                for order in &collected.new_orders {
+                    for field in order.extra.keys() {
+                        if self.extra_fields.insert(field.clone()) {
+                            warn!(
+                                "The {} API returns the unknown order field {field}, stored in the extra column",
+                                self.source.name()
+                            );
+                        }
+                    }
+                }
+
+                collected.gap = self
+                    .gaps
+                    .observe(collected.new_orders.len(), current_orders.len());
+
+                self.interval
+                    .update(collected.new_orders.len(), current_orders.len());
+                self.window_size = current_orders.len();
+            }
+            Err(err) => {
+                let fetch_error = err.downcast_ref::<FetchError>();
+
+                collected.run.http_status = fetch_error.and_then(FetchError::status);
+                collected.run.error = Some(err.to_string());
+
+                if let Some(FetchError::RateLimited { retry_after }) = fetch_error {
+                    self.rate_limited += 1;
+
+                    // Without advice, back off further at every consecutive rejection
+                    let wait = retry_after.unwrap_or_else(|| {
+                        RATE_LIMIT_BACKOFF
+                            .saturating_mul(2u32.saturating_pow(self.rate_limited - 1))
+                            .min(MAX_RATE_LIMIT_BACKOFF)
+                    });
+
+                    warn!(
+                        "Rate limited by the {} API, backing off for {}",
+                        self.source.name(),
+                        humantime::format_duration(wait)
+                    );
+
+                    collected.rate_limited = Some(wait);
+                } else {
+                    error!("Failed to fetch {}: {err}", self.source.name());
+
+                    collected.error_kind = Some(fetch_error.map_or("other", FetchError::kind));
+                }
+            }
+        }
+
+        collected.burst = self.update_burst(collected.gap.is_some());
+
+        collected
+            .span
+            .record("orders_returned", collected.run.orders_returned)
+            .record("new_orders", collected.new_orders.len())
+            .record(
+                "latency_ms",
+                collected
+                    .run
+                    .latency
+                    .map(|latency| latency.as_millis() as u64),
+            );
+
+        collected
+    }
+
+    /// Starts or extends a burst on a gap, returns the burst that just ended
+    fn update_burst(&mut self, gap: bool) -> Option<Burst> {
+        let duration = self.burst_duration?;
+
+        if let Some(burst) = &mut self.burst {
+            burst.fetches += 1;
+        }
+
+        if gap {
+            let burst = self.burst.get_or_insert_with(|| {
+                info!(
+                    "Gap in the {} orders, polling every {} for {}",
+                    self.source.name(),
+                    humantime::format_duration(self.burst_interval),
+                    humantime::format_duration(duration)
+                );
+
+                BurstState {
+                    started_at: Utc::now(),
+                    until: Instant::now(),
+                    fetches: 0,
+                    gaps: 0,
+                }
+            });
+
+            burst.until = Instant::now() + duration;
+            burst.gaps += 1;
+
+            return None;
+        }
+
+        if self
+            .burst
+            .as_ref()
+            .is_some_and(|burst| Instant::now() < burst.until)
+        {
+            return None;
+        }
+
+        let burst = self.burst.take()?;
+
+        info!(
+            "Burst polling of {} over after {} fetches, back to the normal interval",
+            self.source.name(),
+            burst.fetches
+        );
+
+        Some(Burst {
+            started_at: burst.started_at,
+            ended_at: Utc::now(),
+            fetches: burst.fetches,
+            gaps: burst.gaps,
+        })
+    }
+
+    /// Logs the wait the API asked for on a successful fetch, as its quota ran out
+    fn quota_exhausted(&self, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(wait) = retry_after {
+            warn!(
+                "Request quota of the {} API exhausted, waiting {} before the next fetch",
+                self.source.name(),
+                humantime::format_duration(wait)
+            );
+        }
+
+        retry_after
+    }
+}
@@ -0,0 +1,53 @@
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    alerts::{AlertRules, backtest},
+    args::{AlertsCommand, Args},
+    config::Config,
+};
+
+pub fn run(command: &AlertsCommand, args: &Args) -> anyhow::Result<()> {
+    match command {
+        AlertsCommand::Backtest { rules, since } => {
+            let rules = match rules {
+                Some(rules) => AlertRules::load(rules)?,
+                None => Config::load(args.config.as_deref())?.alerts,
+            };
+            let since = Utc::now() - TimeDelta::from_std(*since)?;
+            let (orders, results) = backtest(&args.persist_path, &rules, since)?;
+
+            println!("{orders} orders since {since}");
+            println!();
+            println!(
+                "{:<30} {:>8} {:<20} {:<20}",
+                "rule", "fired", "first", "last"
+            );
+
+            for result in results {
+                println!(
+                    "{:<30} {:>8} {:<20} {:<20}",
+                    result.name,
+                    result.fired,
+                    result
+                        .first
+                        .map(|at| {
+                            at.with_timezone(&args.timezone)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                        })
+                        .unwrap_or_default(),
+                    result
+                        .last
+                        .map(|at| {
+                            at.with_timezone(&args.timezone)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                        })
+                        .unwrap_or_default()
+                );
+            }
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use crate::{
+    args::{Args, BackupCompression},
+    db::{backup, restore, verify_backup},
+};
+
+pub fn run_backup(
+    output: &Path,
+    compression: BackupCompression,
+    verify: bool,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let output = output.to_string_lossy();
+    let counts = backup(&args.persist_path, &output, compression.as_duckdb())?;
+
+    for (table, count) in &counts {
+        println!("{table}: {count} rows");
+    }
+
+    if verify {
+        verify_backup(&output, &counts)?;
+        println!("Backup verified");
+    }
+
+    println!("Backup written to {output}");
+
+    Ok(())
+}
+
+pub fn run_restore(input: &Path, args: &Args) -> anyhow::Result<()> {
+    let input = input.to_string_lossy();
+
+    for (table, count) in restore(&args.persist_path, &input)? {
+        println!("{table}: {count} rows");
+    }
+
+    println!("{input} restored to {}", args.persist_path);
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use plotters::{coord::Shift, prelude::*};
+
+use crate::{
+    args::Args,
+    db::get_pair_orders,
+    export::Pair,
+    fetch::{Order, OrderType},
+};
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 800;
+
+/// Number of bars of the volume chart
+const VOLUME_BUCKETS: i32 = 60;
+
+pub fn run(pair: &Pair, since: Duration, output: &Path, args: &Args) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(since)?;
+    let orders = get_pair_orders(&args.persist_path, pair, from, to)?;
+
+    if orders.is_empty() {
+        bail!("No {pair} orders since {from}");
+    }
+
+    let is_svg = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+
+    if is_svg {
+        draw(
+            SVGBackend::new(output, (WIDTH, HEIGHT)).into_drawing_area(),
+            pair,
+            from,
+            to,
+            &orders,
+        )
+    } else {
+        draw(
+            BitMapBackend::new(output, (WIDTH, HEIGHT)).into_drawing_area(),
+            pair,
+            from,
+            to,
+            &orders,
+        )
+    }
+}
+
+fn draw<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    pair: &Pair,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    orders: &[(DateTime<Utc>, Order)],
+) -> anyhow::Result<()> {
+    let to_anyhow =
+        |err: DrawingAreaErrorKind<DB::ErrorType>| anyhow!("Failed to draw chart: {err}");
+
+    root.fill(&WHITE).map_err(to_anyhow)?;
+
+    let (upper, lower) = root.split_vertically(HEIGHT * 2 / 3);
+
+    let (min_price, max_price) = orders
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(min, max), (_, order)| {
+            (min.min(order.fiat_price), max.max(order.fiat_price))
+        });
+    let margin = ((max_price - min_price) * 0.05).max(max_price * 0.001);
+
+    let mut price_chart = ChartBuilder::on(&upper)
+        .caption(format!("{pair} price"), ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(80)
+        .build_cartesian_2d(from..to, (min_price - margin)..(max_price + margin))
+        .map_err(to_anyhow)?;
+
+    price_chart
+        .configure_mesh()
+        .x_label_formatter(&|time| time.format("%m-%d %H:%M").to_string())
+        .y_desc(&pair.fiat_symbol)
+        .draw()
+        .map_err(to_anyhow)?;
+
+    price_chart
+        .draw_series(LineSeries::new(
+            orders.iter().map(|(at, order)| (*at, order.fiat_price)),
+            BLUE.mix(0.5),
+        ))
+        .map_err(to_anyhow)?;
+
+    price_chart
+        .draw_series(orders.iter().map(|(at, order)| {
+            Circle::new((*at, order.fiat_price), 3, side_color(&order.ty).filled())
+        }))
+        .map_err(to_anyhow)?;
+
+    let bucket = (to - from) / VOLUME_BUCKETS;
+    let mut volumes = vec![(0.0, 0.0); VOLUME_BUCKETS as usize];
+
+    for (at, order) in orders {
+        let index = ((*at - from).num_milliseconds() / bucket.num_milliseconds().max(1))
+            .clamp(0, VOLUME_BUCKETS as i64 - 1) as usize;
+
+        match order.ty {
+            OrderType::Buy => volumes[index].0 += order.fiat_amount,
+            OrderType::Sell => volumes[index].1 += order.fiat_amount,
+        }
+    }
+
+    let max_volume = volumes
+        .iter()
+        .map(|(buy, sell)| buy + sell)
+        .fold(0.0, f64::max);
+
+    let mut volume_chart = ChartBuilder::on(&lower)
+        .caption(format!("{pair} volume"), ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(80)
+        .build_cartesian_2d(from..to, 0.0..max_volume * 1.05)
+        .map_err(to_anyhow)?;
+
+    volume_chart
+        .configure_mesh()
+        .x_label_formatter(&|time| time.format("%m-%d %H:%M").to_string())
+        .y_desc(&pair.fiat_symbol)
+        .draw()
+        .map_err(to_anyhow)?;
+
+    // Buy volume is stacked on top of the sell volume
+    volume_chart
+        .draw_series(volumes.iter().enumerate().flat_map(|(i, (buy, sell))| {
+            let start = from + bucket * i as i32;
+            let end = start + bucket;
+
+            [
+                Rectangle::new(
+                    [(start, 0.0), (end, *sell)],
+                    side_color(&OrderType::Sell).filled(),
+                ),
+                Rectangle::new(
+                    [(start, *sell), (end, sell + buy)],
+                    side_color(&OrderType::Buy).filled(),
+                ),
+            ]
+        }))
+        .map_err(to_anyhow)?;
+
+    root.present().map_err(to_anyhow)?;
+
+    Ok(())
+}
+
+fn side_color(ty: &OrderType) -> RGBColor {
+    match ty {
+        OrderType::Buy => RGBColor(46, 125, 50),
+        OrderType::Sell => RGBColor(198, 40, 40),
+    }
+}
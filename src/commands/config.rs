@@ -0,0 +1,214 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{Context, bail};
+use clap::{CommandFactory, parser::ValueSource};
+
+use crate::{
+    args::{Args, ConfigCommand},
+    config::Config,
+};
+
+/// Parts of the argument and config key names whose values are secrets
+const SECRET_PARTS: &[&str] = &["password", "token", "secret", "key", "webhook", "headers"];
+
+const REDACTED: &str = "<redacted>";
+
+pub fn run(command: &ConfigCommand, args: &Args) -> anyhow::Result<()> {
+    match command {
+        ConfigCommand::Validate { file } => {
+            let Some(file) = file.as_deref().or(args.config.as_deref()) else {
+                bail!("No config file to validate, pass one or set --config");
+            };
+
+            validate(file)
+        }
+        ConfigCommand::PrintEffective => print_effective(args),
+    }
+}
+
+/// Parses the config file, which rejects unknown keys, then checks what the
+/// parsing can't
+fn validate(file: &Path) -> anyhow::Result<()> {
+    let config = Config::load(Some(file))?;
+    let mut errors = Vec::new();
+
+    let mut export_names = HashSet::new();
+
+    for export in &config.exports {
+        if !export_names.insert(&export.name) {
+            errors.push(format!("Export {} is defined twice", export.name));
+        }
+    }
+
+    let mut token_names = HashSet::new();
+    let mut tokens = HashSet::new();
+
+    for token in &config.http.tokens {
+        if !token_names.insert(&token.name) {
+            errors.push(format!("API token {} is defined twice", token.name));
+        }
+
+        if token.token.len() < 16 {
+            errors.push(format!(
+                "API token {} is shorter than 16 characters",
+                token.name
+            ));
+        }
+
+        if !tokens.insert(&token.token) {
+            errors.push(format!(
+                "API token {} has the same value as another token",
+                token.name
+            ));
+        }
+
+        if token.scopes.as_ref().is_some_and(Vec::is_empty) {
+            errors.push(format!(
+                "API token {} has no scope, remove `scopes` to allow every route",
+                token.name
+            ));
+        }
+    }
+
+    let mut rule_names = HashSet::new();
+
+    for name in config
+        .alerts
+        .rules
+        .iter()
+        .map(|rule| &rule.name)
+        .chain(config.alerts.indicator_rules.iter().map(|rule| &rule.name))
+    {
+        if !rule_names.insert(name) {
+            errors.push(format!("Alert rule {name} is defined twice"));
+        }
+    }
+
+    if !errors.is_empty() {
+        for error in &errors {
+            println!("[fail] {error}");
+        }
+
+        bail!("{} has {} errors", file.display(), errors.len());
+    }
+
+    println!(
+        "{} is valid: {} exports, {} alert rules, {} indicator rules, {} API tokens",
+        file.display(),
+        config.exports.len(),
+        config.alerts.rules.len(),
+        config.alerts.indicator_rules.len(),
+        config.http.tokens.len()
+    );
+
+    Ok(())
+}
+
+/// Prints every option with its value and where it comes from, the command
+/// line winning over the environment and the environment over the defaults,
+/// then the config file, secrets redacted
+fn print_effective(args: &Args) -> anyhow::Result<()> {
+    let matches = Args::command().get_matches();
+
+    println!("# Options");
+    println!();
+
+    for arg in Args::command().get_arguments() {
+        let id = arg.get_id().as_str();
+
+        if arg.is_positional() || matches!(id, "help" | "version") {
+            continue;
+        }
+
+        let value = match matches.get_raw(id) {
+            Some(values) => values
+                .map(|value| redact(id, &value.to_string_lossy()))
+                .collect::<Vec<_>>()
+                .join(","),
+            None => continue,
+        };
+        let source = match matches.value_source(id) {
+            Some(ValueSource::CommandLine) => "command line",
+            Some(ValueSource::EnvVariable) => "environment",
+            Some(ValueSource::DefaultValue) => "default",
+            _ => "unknown",
+        };
+
+        println!(
+            "{:<32} {value:<40} {source}",
+            format!("--{}", id.replace('_', "-"))
+        );
+    }
+
+    println!();
+    println!("# Config file");
+    println!();
+
+    let Some(path) = &args.config else {
+        println!("No config file, every section is empty");
+
+        return Ok(());
+    };
+
+    // Parsed first so that an invalid file isn't printed as if it was used
+    Config::load(Some(path))?;
+
+    let mut content: toml::Table = toml::from_str(
+        &fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?,
+    )?;
+
+    for (key, value) in content.iter_mut() {
+        redact_value(key, value);
+    }
+
+    println!("{}", path.display());
+    println!();
+    print!("{}", toml::to_string_pretty(&content)?);
+
+    Ok(())
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_lowercase();
+
+    SECRET_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// Hides secret values, and the credentials of the URLs of the other ones
+fn redact(name: &str, value: &str) -> String {
+    if is_secret(name) && !value.is_empty() {
+        return REDACTED.to_string();
+    }
+
+    match value.split_once("://") {
+        Some((scheme, rest))
+            if rest
+                .split('/')
+                .next()
+                .is_some_and(|host| host.contains('@')) =>
+        {
+            let (_, host) = rest.split_once('@').unwrap_or_default();
+
+            format!("{scheme}://{REDACTED}@{host}")
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn redact_value(key: &str, value: &mut toml::Value) {
+    match value {
+        toml::Value::String(string) => *string = redact(key, string),
+        toml::Value::Array(values) => {
+            for value in values {
+                redact_value(key, value);
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                redact_value(key, value);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,16 @@
+use anyhow::bail;
+
+use crate::{
+    args::{Args, CtlCommand},
+    control::send,
+};
+
+pub async fn run(command: &CtlCommand, args: &Args) -> anyhow::Result<()> {
+    let Some(path) = &args.control_socket else {
+        bail!("--control-socket is required to reach the running collector");
+    };
+
+    println!("{}", send(path, command).await?);
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::fmt::Display;
+
+use chrono::TimeDelta;
+
+use crate::{
+    args::Args,
+    client::http_client,
+    config::Config,
+    db::{
+        check_connection, count_duplicate_orders, count_suspected_duplicates, get_connection,
+        get_last_activity, get_longest_silences, get_row_counts,
+    },
+    migrations::pending,
+    source::Source,
+};
+
+/// Silences between orders longer than this are reported
+const SILENCE_WARNING: TimeDelta = TimeDelta::hours(1);
+
+enum Finding {
+    Ok(String),
+    Warning(String),
+    Failure(String),
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Finding::Ok(message) => write!(f, "[ ok ] {message}"),
+            Finding::Warning(message) => write!(f, "[warn] {message}"),
+            Finding::Failure(message) => write!(f, "[fail] {message}"),
+        }
+    }
+}
+
+/// Runs every check, printing a finding for each, and fails when any check fails
+pub async fn run(args: &Args) -> anyhow::Result<()> {
+    let mut findings = Vec::new();
+
+    match check_connection(&args.persist_path) {
+        Ok(()) => {
+            findings.push(Finding::Ok(format!("{} is accessible", args.persist_path)));
+            check_data(args, &mut findings);
+        }
+        Err(err) => findings.push(Finding::Failure(format!(
+            "{} is not accessible: {err}, check the path and that no other process holds it",
+            args.persist_path
+        ))),
+    }
+
+    for source in Source::from_args(args, &http_client(args)?)? {
+        match source.fetch().await {
+            Ok(fetched) => findings.push(Finding::Ok(format!(
+                "{} API reachable, {} orders returned in {}ms",
+                source.name(),
+                fetched.orders.len(),
+                fetched.timing.latency.as_millis()
+            ))),
+            Err(err) => findings.push(Finding::Failure(format!(
+                "{} API unreachable: {err}, check the network and proxy settings",
+                source.name()
+            ))),
+        }
+    }
+
+    match Config::load(args.config.as_deref()) {
+        Ok(config) => findings.push(Finding::Ok(format!(
+            "Config valid, {} export subscriptions",
+            config.exports.len()
+        ))),
+        Err(err) => findings.push(Finding::Failure(format!("Invalid config: {err:#}"))),
+    }
+
+    for finding in &findings {
+        println!("{finding}");
+    }
+
+    let failures = findings
+        .iter()
+        .filter(|finding| matches!(finding, Finding::Failure(_)))
+        .count();
+
+    if failures > 0 {
+        anyhow::bail!("{failures} checks failed");
+    }
+
+    Ok(())
+}
+
+fn check_data(args: &Args, findings: &mut Vec<Finding>) {
+    match get_connection(&args.persist_path).and_then(|conn| pending(&conn)) {
+        Ok(pending) if pending.is_empty() => {
+            findings.push(Finding::Ok("Schema is up to date".to_string()))
+        }
+        Ok(pending) => findings.push(Finding::Warning(format!(
+            "{} schema migrations pending, run the migrate command or restart the collector",
+            pending.len()
+        ))),
+        Err(err) => findings.push(Finding::Failure(format!(
+            "Failed to read schema version: {err}"
+        ))),
+    }
+
+    match get_row_counts(&args.persist_path) {
+        Ok(counts) => {
+            let counts = counts
+                .iter()
+                .map(|(table, count)| format!("{table} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            findings.push(Finding::Ok(format!("Row counts: {counts}")));
+        }
+        Err(err) => findings.push(Finding::Failure(format!("Failed to count rows: {err}"))),
+    }
+
+    match count_duplicate_orders(&args.persist_path) {
+        Ok(0) => findings.push(Finding::Ok("No duplicate orders".to_string())),
+        Ok(duplicates) => findings.push(Finding::Warning(format!(
+            "{duplicates} duplicate orders, two collectors may have written to the same DB"
+        ))),
+        Err(err) => findings.push(Finding::Failure(format!(
+            "Failed to look for duplicates: {err}"
+        ))),
+    }
+
+    match count_suspected_duplicates(&args.persist_path) {
+        Ok(0) => {}
+        Ok(suspected) => findings.push(Finding::Warning(format!(
+            "{suspected} orders have the same content as an earlier one, see the suspected_duplicates view"
+        ))),
+        Err(err) => findings.push(Finding::Failure(format!(
+            "Failed to look for suspected duplicates: {err}"
+        ))),
+    }
+
+    match get_longest_silences(&args.persist_path, 5) {
+        Ok(silences) => {
+            for (from, to) in silences
+                .into_iter()
+                .filter(|(from, to)| *to - *from > SILENCE_WARNING)
+            {
+                findings.push(Finding::Warning(format!(
+                    "No orders from {from} to {to}, check the fetch_runs table for errors during that period"
+                )));
+            }
+        }
+        Err(err) => findings.push(Finding::Failure(format!("Failed to look for gaps: {err}"))),
+    }
+
+    match get_last_activity(&args.persist_path) {
+        Ok((Some(last_success), _)) => findings.push(Finding::Ok(format!(
+            "Last successful fetch at {last_success}"
+        ))),
+        Ok((None, _)) => findings.push(Finding::Warning(
+            "No successful fetch recorded, is the collector running?".to_string(),
+        )),
+        Err(err) => findings.push(Finding::Failure(format!("Failed to read activity: {err}"))),
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write, stdout},
+    path::Path,
+    time::Duration,
+};
+
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use chrono::{NaiveDate, TimeDelta, Utc};
+use serde_json::{Value, json};
+use tokio::time::sleep;
+
+use crate::{
+    args::Args,
+    batches::{BATCH_ROWS, OrderRange, SCHEMA},
+    client::http_client,
+    db::{OrderFilter, get_orders_page, get_summary},
+    export::Pair,
+    http::OrderItem,
+    sheets::SheetsClient,
+};
+
+/// Appends a row per pair with the activity of `day` to the sheet
+pub async fn run_sheets(
+    spreadsheet_id: &str,
+    sheet: &str,
+    credentials: &Path,
+    day: Option<NaiveDate>,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let day = day.unwrap_or_else(|| Utc::now().date_naive() - TimeDelta::days(1));
+    let from = day.and_time(Default::default()).and_utc();
+    let pairs = get_summary(&args.persist_path, from, from + TimeDelta::days(1))?;
+
+    if pairs.is_empty() {
+        println!("No orders on {day}, nothing appended");
+
+        return Ok(());
+    }
+
+    let rows = pairs
+        .iter()
+        .map(|pair| {
+            vec![
+                json!(day.to_string()),
+                json!(format!("{}/{}", pair.crypto_symbol, pair.fiat_symbol)),
+                json!(pair.orders),
+                json!(pair.buys),
+                json!(pair.sells),
+                json!(pair.crypto_volume),
+                json!(pair.fiat_volume),
+                json!(pair.avg_price),
+                json!(pair.largest_fiat_amount),
+            ]
+        })
+        .collect::<Vec<Vec<Value>>>();
+
+    let client = SheetsClient::connect(http_client(args)?, credentials).await?;
+    client
+        .append(spreadsheet_id, &format!("{sheet}!A1"), rows)
+        .await?;
+
+    println!("Appended {} pairs of {day} to the sheet", pairs.len());
+
+    Ok(())
+}
+
+/// Writes the orders of `pair` created on `day`, or all the stored ones, as an
+/// Arrow IPC file, or as an Arrow IPC stream on stdout without `output`
+pub fn run_arrow(
+    day: Option<NaiveDate>,
+    pair: Option<Pair>,
+    output: Option<&Path>,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let mut range = order_range(day, pair);
+    let mut next_batch = || {
+        let page = get_orders_page(
+            &args.persist_path,
+            &range.filter,
+            range.after.as_ref(),
+            BATCH_ROWS,
+            false,
+        )?;
+
+        range.batch(page)
+    };
+    let mut rows = 0;
+
+    match output {
+        Some(output) => {
+            let mut writer = FileWriter::try_new(BufWriter::new(File::create(output)?), &SCHEMA)?;
+
+            while let Some(batch) = next_batch()? {
+                rows += batch.num_rows();
+                writer.write(&batch)?;
+            }
+
+            writer.finish()?;
+            writer.into_inner()?.flush()?;
+
+            println!("{rows} orders written to {}", output.display());
+        }
+        None => {
+            let mut writer = StreamWriter::try_new(BufWriter::new(stdout().lock()), &SCHEMA)?;
+
+            while let Some(batch) = next_batch()? {
+                rows += batch.num_rows();
+                writer.write(&batch)?;
+            }
+
+            writer.finish()?;
+            writer.into_inner()?.flush()?;
+
+            // stdout carries the stream, so the count goes to stderr
+            eprintln!("{rows} orders written");
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the orders of `pair` created on `day`, or all the stored ones, as
+/// JSON Lines, then with `follow` the ones inserted since
+pub async fn run_jsonl(
+    day: Option<NaiveDate>,
+    pair: Option<Pair>,
+    follow: bool,
+    interval: Duration,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let mut range = order_range(day, pair);
+
+    loop {
+        let page = get_orders_page(
+            &args.persist_path,
+            &range.filter,
+            range.after.as_ref(),
+            BATCH_ROWS,
+            false,
+        )?;
+        let exhausted = page.len() < BATCH_ROWS;
+        let orders = range.advance(page);
+        let mut out = stdout().lock();
+
+        for (cursor, order) in &orders {
+            serde_json::to_writer(&mut out, &OrderItem::new(cursor.created_at, order))?;
+            out.write_all(b"\n")?;
+        }
+
+        // Flushed at every page so that a pipe sees the orders as they come
+        out.flush()?;
+        drop(out);
+
+        if exhausted || orders.is_empty() {
+            if !follow {
+                break;
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    Ok(())
+}
+
+fn order_range(day: Option<NaiveDate>, pair: Option<Pair>) -> OrderRange {
+    let from = day.map(|day| day.and_time(Default::default()).and_utc());
+
+    OrderRange::new(
+        OrderFilter {
+            pair,
+            ..Default::default()
+        },
+        from,
+        from.map(|from| from + TimeDelta::days(1)),
+    )
+}
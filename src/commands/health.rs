@@ -0,0 +1,43 @@
+use anyhow::bail;
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::Args,
+    db::get_last_activity,
+    health::{Health, HealthReport},
+};
+
+/// Prints the health report from the database, and fails when the collector
+/// hasn't fetched successfully for longer than the allowed silence
+pub fn run(args: &Args) -> anyhow::Result<()> {
+    let max_silence = TimeDelta::from_std(args.max_fetch_silence)?;
+
+    let report = match get_last_activity(&args.persist_path) {
+        Ok((last_success, last_insert)) => HealthReport {
+            health: Health {
+                healthy: last_success.is_some_and(|at| Utc::now() - at <= max_silence),
+                last_success,
+                last_insert,
+            },
+            db_accessible: true,
+            db_error: None,
+        },
+        Err(err) => HealthReport {
+            health: Health {
+                healthy: false,
+                last_success: None,
+                last_insert: None,
+            },
+            db_accessible: false,
+            db_error: Some(err.to_string()),
+        },
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.is_healthy() {
+        bail!("Collector is unhealthy");
+    }
+
+    Ok(())
+}
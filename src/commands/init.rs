@@ -0,0 +1,210 @@
+use std::{
+    fs,
+    io::{Write, stdin, stdout},
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use chrono::TimeDelta;
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    args::Args,
+    client::http_client,
+    config::NotifierConfig,
+    db::{create_parent_dir, init},
+    sink::Sink,
+    source::{Source, SourceKind},
+};
+
+pub async fn run(interactive: bool, output: &Path, args: &Args) -> anyhow::Result<()> {
+    if !interactive {
+        init(&args.persist_path, TimeDelta::from_std(args.session_gap)?)?;
+        println!("Database ready at {}", args.persist_path);
+
+        return Ok(());
+    }
+
+    let answers = ask(args)?;
+
+    if output.exists()
+        && !confirm(
+            &format!("{} exists, overwrite it?", output.display()),
+            false,
+        )?
+    {
+        bail!("Setup cancelled, {} left untouched", output.display());
+    }
+
+    let content = answers
+        .iter()
+        .map(|(name, value)| format!("{name}={value}\n"))
+        .collect::<String>();
+
+    fs::write(output, content).with_context(|| format!("Failed to write {}", output.display()))?;
+
+    println!();
+    println!("[ ok ] Settings written to {}", output.display());
+
+    try_settings(&answers).await?;
+
+    println!();
+    println!(
+        "Setup complete, start the collector with the variables of {}, like `docker run --env-file {}`",
+        output.display(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Walks through the settings a first collector needs, as environment variables
+fn ask(args: &Args) -> anyhow::Result<Vec<(&'static str, String)>> {
+    let mut answers = Vec::new();
+
+    println!("Answer each question, or press enter to keep the value in brackets");
+    println!();
+
+    answers.push((
+        "PERSIST_PATH",
+        prompt("DuckDB file the orders are stored in", &args.persist_path)?,
+    ));
+
+    let interval = prompt(
+        "Seconds between two fetches",
+        &args.fetch_interval.to_string(),
+    )?;
+    interval
+        .parse::<u64>()
+        .with_context(|| format!("{interval} is not a number of seconds"))?;
+    answers.push(("FETCH_INTERVAL", interval));
+
+    let sources = prompt(
+        &format!(
+            "Sources, comma separated among {}",
+            possible_values::<SourceKind>()
+        ),
+        "nash",
+    )?;
+    for source in sources.split(',') {
+        SourceKind::from_str(source.trim(), true).map_err(anyhow::Error::msg)?;
+    }
+    answers.push(("SOURCES", sources));
+
+    let min_fiat_amount = prompt("Smallest fiat amount of the notified orders", "0")?;
+    min_fiat_amount
+        .parse::<f64>()
+        .with_context(|| format!("{min_fiat_amount} is not an amount"))?;
+
+    if let Some(url) = prompt_optional("Discord webhook URL")? {
+        answers.push(("DISCORD_WEBHOOK_URL", url));
+    }
+
+    if let Some(url) = prompt_optional("Slack incoming webhook URL")? {
+        answers.push(("SLACK_WEBHOOK_URL", url));
+        answers.push(("SLACK_MIN_FIAT_AMOUNT", min_fiat_amount.clone()));
+    }
+
+    if let Some(url) = prompt_optional("ntfy topic URL, like https://ntfy.sh/my-topic")? {
+        answers.push(("NTFY_URL", url));
+        answers.push(("NTFY_MIN_FIAT_AMOUNT", min_fiat_amount));
+    }
+
+    Ok(answers)
+}
+
+/// Creates the database, fetches each source once and sends a test alert to
+/// each notifier, failing at the first step that doesn't work
+async fn try_settings(answers: &[(&'static str, String)]) -> anyhow::Result<()> {
+    let args = Args::try_parse_from(["nash-stats".to_string()].into_iter().chain(
+        answers.iter().flat_map(|(name, value)| {
+            [
+                format!("--{}", name.to_lowercase().replace('_', "-")),
+                value.clone(),
+            ]
+        }),
+    ))?;
+
+    create_parent_dir(&args.persist_path)?;
+    init(&args.persist_path, TimeDelta::from_std(args.session_gap)?)
+        .with_context(|| format!("{} can't be created", args.persist_path))?;
+    println!("[ ok ] Database ready at {}", args.persist_path);
+
+    let client = http_client(&args)?;
+
+    for source in Source::from_args(&args, &client)? {
+        let fetched = source.fetch().await.with_context(|| {
+            format!(
+                "{} API unreachable, check the network and proxy settings",
+                source.name()
+            )
+        })?;
+
+        println!(
+            "[ ok ] {} API reachable, {} orders returned",
+            source.name(),
+            fetched.orders.len()
+        );
+    }
+
+    for sink in Sink::from_args(&args, &NotifierConfig::default(), &client)? {
+        sink.alert("Test notification of the nash-stats setup")
+            .await
+            .with_context(|| format!("{} notification failed", sink.name()))?;
+
+        println!("[ ok ] {} notified", sink.name());
+    }
+
+    Ok(())
+}
+
+fn prompt(question: &str, default: &str) -> anyhow::Result<String> {
+    print!("{question} [{default}]: ");
+    stdout().flush()?;
+
+    let answer = read_line()?;
+
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer
+    })
+}
+
+fn prompt_optional(question: &str) -> anyhow::Result<Option<String>> {
+    print!("{question} (leave empty to skip): ");
+    stdout().flush()?;
+
+    let answer = read_line()?;
+
+    Ok((!answer.is_empty()).then_some(answer))
+}
+
+fn confirm(question: &str, default: bool) -> anyhow::Result<bool> {
+    let answer = prompt(question, if default { "Y/n" } else { "y/N" })?;
+
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+fn read_line() -> anyhow::Result<String> {
+    let mut line = String::new();
+
+    if stdin().read_line(&mut line)? == 0 {
+        bail!("Setup cancelled, the input was closed");
+    }
+
+    Ok(line.trim().to_string())
+}
+
+fn possible_values<T: ValueEnum>() -> String {
+    T::value_variants()
+        .iter()
+        .filter_map(|value| value.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,31 @@
+use crate::{args::Args, db::maintain};
+
+pub fn run(args: &Args) -> anyhow::Result<()> {
+    let maintenance = maintain(&args.persist_path)?;
+
+    if let (Some(before), Some(after)) = (maintenance.size_before, maintenance.size_after) {
+        println!(
+            "Size: {before} bytes before, {after} bytes after ({:+} bytes)",
+            after as i64 - before as i64
+        );
+    }
+
+    for (table, count) in &maintenance.row_counts {
+        println!("{table}: {count} rows read back");
+    }
+
+    for index in &maintenance.indexes_rebuilt {
+        println!("Index {index} rebuilt");
+    }
+
+    if maintenance.duplicate_hashes > 0 {
+        anyhow::bail!(
+            "{} orders share their hash with another one, the orders_hash index was left as is",
+            maintenance.duplicate_hashes
+        );
+    }
+
+    println!("Database healthy");
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::path::Path;
+
+use chrono::TimeDelta;
+
+use crate::{args::Args, db::merge};
+
+pub fn run(from: &Path, args: &Args) -> anyhow::Result<()> {
+    let merged = merge(
+        &args.persist_path,
+        &from.to_string_lossy(),
+        TimeDelta::from_std(args.session_gap)?,
+    )?;
+
+    println!(
+        "{} orders inserted, {} skipped as already stored",
+        merged.inserted, merged.skipped
+    );
+
+    Ok(())
+}
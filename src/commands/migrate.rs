@@ -0,0 +1,32 @@
+use crate::{
+    args::Args,
+    db::get_connection,
+    migrations::{current_version, migrate, pending},
+};
+
+pub fn run(dry_run: bool, args: &Args) -> anyhow::Result<()> {
+    let mut conn = get_connection(&args.persist_path)?;
+
+    println!("Schema version {}", current_version(&conn)?);
+
+    let migrations = if dry_run {
+        pending(&conn)?
+    } else {
+        migrate(&mut conn)?
+    };
+
+    if migrations.is_empty() {
+        println!("Schema is up to date");
+    }
+
+    for migration in migrations {
+        println!(
+            "{} migration {} {}",
+            if dry_run { "Pending" } else { "Applied" },
+            migration.version,
+            migration.name
+        );
+    }
+
+    Ok(())
+}
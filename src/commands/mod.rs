@@ -0,0 +1,129 @@
+use anyhow::bail;
+
+use crate::{
+    args::{Args, Command, ExportTarget},
+    db::OrderFilter,
+};
+
+mod alerts;
+mod backup;
+mod chart;
+mod config;
+mod ctl;
+mod doctor;
+mod export;
+mod health;
+mod init;
+mod maintain;
+mod merge;
+mod migrate;
+mod pnl;
+mod prune;
+mod query;
+mod report;
+mod schema;
+mod state;
+mod stats;
+mod status;
+mod tail;
+
+pub async fn run(command: &Command, args: &Args) -> anyhow::Result<()> {
+    match command {
+        Command::Alerts { command } => alerts::run(command, args),
+        Command::Config { command } => config::run(command, args),
+        Command::Ctl { command } => ctl::run(command, args).await,
+        Command::Schema {
+            format,
+            create_view,
+        } => schema::run(*format, *create_view, args),
+        Command::Backup {
+            output,
+            compression,
+            verify,
+        } => backup::run_backup(output, *compression, *verify, args),
+        Command::Restore { input } => backup::run_restore(input, args),
+        Command::Doctor => doctor::run(args).await,
+        Command::Health => health::run(args),
+        Command::Init {
+            interactive,
+            output,
+        } => init::run(*interactive, output, args).await,
+        Command::Maintain => maintain::run(args),
+        Command::State { command } => state::run(command, args),
+        Command::Status => status::run(args).await,
+        Command::Merge { from } => merge::run(from, args),
+        Command::Migrate { dry_run } => migrate::run(*dry_run, args),
+        Command::Prune { older_than_days } => prune::run(*older_than_days, args).await,
+        Command::Tail {
+            follow,
+            lines,
+            interval,
+            pair,
+            ty,
+            min_fiat_amount,
+        } => {
+            let filter = OrderFilter {
+                pair: pair.clone(),
+                ty: ty.clone(),
+                min_fiat_amount: *min_fiat_amount,
+            };
+
+            tail::run(*follow, *lines, *interval, &filter, args).await
+        }
+        Command::Pnl {
+            method,
+            csv,
+            format,
+        } => pnl::run(*method, csv.as_deref(), *format, args),
+        Command::Query { sql, format } => query::run(sql, *format, args),
+        Command::Stats {
+            command,
+            since,
+            chart,
+            pair,
+        } => stats::run(
+            command.as_ref(),
+            *since,
+            chart.then_some(pair.as_ref()).flatten(),
+            args,
+        ),
+        Command::Chart {
+            pair,
+            since,
+            output,
+        } => chart::run(pair, *since, output, args),
+        Command::Export {
+            format: ExportTarget::Sheets,
+            sheets: Some(sheets),
+            sheet,
+            credentials: Some(credentials),
+            day,
+            ..
+        } => export::run_sheets(sheets, sheet, credentials, *day, args).await,
+        Command::Export {
+            format: ExportTarget::Sheets,
+            ..
+        } => bail!("--sheets and --credentials are required to export to a sheet"),
+        Command::Export {
+            format: ExportTarget::Arrow,
+            day,
+            pair,
+            output,
+            ..
+        } => export::run_arrow(*day, pair.clone(), output.as_deref(), args),
+        Command::Export {
+            format: ExportTarget::Jsonl,
+            day,
+            pair,
+            follow,
+            interval,
+            ..
+        } => export::run_jsonl(*day, pair.clone(), *follow, *interval, args).await,
+        Command::Report {
+            from,
+            to,
+            format,
+            output,
+        } => report::run(*from, *to, *format, output.as_deref(), args),
+    }
+}
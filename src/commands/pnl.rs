@@ -0,0 +1,159 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::Datelike;
+use serde::Serialize;
+use serde_json::json;
+use tracing::warn;
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_my_trades,
+    pnl::{CostMethod, Disposal, compute},
+};
+
+/// Realized gains of a group of disposals
+#[derive(Debug, Default, Serialize)]
+struct Realized {
+    disposals: u64,
+    crypto_amount: f64,
+    proceeds: f64,
+    cost_basis: f64,
+    gain: f64,
+}
+
+impl Realized {
+    fn add(&mut self, disposal: &Disposal) {
+        self.disposals += 1;
+        self.crypto_amount += disposal.crypto_amount;
+        self.proceeds += disposal.proceeds;
+        self.cost_basis += disposal.cost_basis;
+        self.gain += disposal.gain();
+    }
+}
+
+pub fn run(
+    method: CostMethod,
+    csv: Option<&Path>,
+    format: StatsFormat,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let csv = csv.map(|csv| csv.to_string_lossy());
+    let trades = get_my_trades(&args.persist_path, csv.as_deref())?;
+    let ledger = compute(&trades, method);
+
+    let uncovered = ledger
+        .disposals
+        .iter()
+        .filter(|disposal| disposal.uncovered_amount > 0.0)
+        .count();
+
+    if uncovered > 0 {
+        warn!(
+            "{uncovered} sells exceed the crypto bought before them, the excess is counted at no cost"
+        );
+    }
+
+    let mut per_pair = BTreeMap::<(String, String), Realized>::new();
+    let mut per_year = BTreeMap::<(i32, String), Realized>::new();
+
+    for disposal in &ledger.disposals {
+        per_pair
+            .entry((disposal.crypto_symbol.clone(), disposal.fiat_symbol.clone()))
+            .or_default()
+            .add(disposal);
+        per_year
+            .entry((disposal.at.year(), disposal.fiat_symbol.clone()))
+            .or_default()
+            .add(disposal);
+    }
+
+    if let StatsFormat::Json = format {
+        let report = json!({
+            "method": format!("{method:?}").to_lowercase(),
+            "pairs": per_pair
+                .iter()
+                .map(|((crypto_symbol, fiat_symbol), realized)| json!({
+                    "crypto_symbol": crypto_symbol,
+                    "fiat_symbol": fiat_symbol,
+                    "realized": realized,
+                }))
+                .collect::<Vec<_>>(),
+            "years": per_year
+                .iter()
+                .map(|((year, fiat_symbol), realized)| json!({
+                    "year": year,
+                    "fiat_symbol": fiat_symbol,
+                    "realized": realized,
+                }))
+                .collect::<Vec<_>>(),
+            "positions": ledger.positions,
+            "disposals": ledger.disposals,
+        });
+
+        println!("{}", serde_json::to_string_pretty(&report)?);
+
+        return Ok(());
+    }
+
+    println!(
+        "Realized gains of {} trades, {method:?} cost basis",
+        trades.len()
+    );
+    println!();
+    println!(
+        "{:<12} {:>6} {:>16} {:>14} {:>14} {:>14}",
+        "pair", "sells", "crypto sold", "proceeds", "cost basis", "gain"
+    );
+
+    for ((crypto_symbol, fiat_symbol), realized) in &per_pair {
+        println!(
+            "{:<12} {:>6} {:>16.6} {:>14.2} {:>14.2} {:>+14.2}",
+            format!("{crypto_symbol}/{fiat_symbol}"),
+            realized.disposals,
+            realized.crypto_amount,
+            realized.proceeds,
+            realized.cost_basis,
+            realized.gain
+        );
+    }
+
+    println!();
+    println!("Per year");
+    println!();
+    println!(
+        "{:<6} {:<6} {:>6} {:>14} {:>14} {:>14}",
+        "year", "fiat", "sells", "proceeds", "cost basis", "gain"
+    );
+
+    for ((year, fiat_symbol), realized) in &per_year {
+        println!(
+            "{:<6} {:<6} {:>6} {:>14.2} {:>14.2} {:>+14.2}",
+            year,
+            fiat_symbol,
+            realized.disposals,
+            realized.proceeds,
+            realized.cost_basis,
+            realized.gain
+        );
+    }
+
+    println!();
+    println!("Open positions");
+    println!();
+    println!(
+        "{:<12} {:>16} {:>14} {:>14}",
+        "pair", "crypto held", "cost basis", "avg cost"
+    );
+
+    for position in &ledger.positions {
+        println!(
+            "{:<12} {:>16.6} {:>14.2} {:>14.2}",
+            format!("{}/{}", position.crypto_symbol, position.fiat_symbol),
+            position.crypto_amount,
+            position.cost_basis,
+            position.cost_basis / position.crypto_amount
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::anyhow;
+use chrono::{TimeDelta, Utc};
+
+use crate::{args::Args, retention::apply_retention, storage::ObjectStorage};
+
+pub async fn run(older_than_days: Option<u32>, args: &Args) -> anyhow::Result<()> {
+    let days = older_than_days
+        .or(args.retention_days)
+        .ok_or_else(|| anyhow!("Either --older-than-days or --retention-days is required"))?;
+
+    let before = Utc::now() - TimeDelta::days(days.into());
+    let pruned = apply_retention(
+        args.persist_path.clone(),
+        before,
+        args.archive_dir.clone(),
+        ObjectStorage::from_args(args)?,
+    )
+    .await?;
+
+    println!("{pruned} (older than {before})");
+
+    Ok(())
+}
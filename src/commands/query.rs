@@ -0,0 +1,89 @@
+use crate::{
+    args::{Args, QueryFormat},
+    db::{run_json_query, run_query},
+};
+
+pub fn run(sql: &str, format: QueryFormat, args: &Args) -> anyhow::Result<()> {
+    if let QueryFormat::Json = format {
+        let rows = run_json_query(&args.persist_path, sql)?;
+
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+
+        return Ok(());
+    }
+
+    let result = run_query(&args.persist_path, sql)?;
+    let rows = result
+        .rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|value| value.unwrap_or_else(|| "NULL".to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        QueryFormat::Csv => {
+            println!("{}", csv_line(&result.columns));
+
+            for row in &rows {
+                println!("{}", csv_line(row));
+            }
+        }
+        _ => {
+            let widths = result
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    rows.iter()
+                        .map(|row| row[i].chars().count())
+                        .fold(column.chars().count(), usize::max)
+                })
+                .collect::<Vec<_>>();
+
+            println!("{}", table_line(&result.columns, &widths));
+            println!(
+                "{}",
+                widths
+                    .iter()
+                    .map(|width| "-".repeat(*width))
+                    .collect::<Vec<_>>()
+                    .join("  ")
+            );
+
+            for row in &rows {
+                println!("{}", table_line(row, &widths));
+            }
+
+            println!();
+            println!("{} rows", rows.len());
+        }
+    }
+
+    Ok(())
+}
+
+fn table_line(values: &[String], widths: &[usize]) -> String {
+    values
+        .iter()
+        .zip(widths)
+        .map(|(value, width)| format!("{value:<width$}"))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+fn csv_line(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,109 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chrono::{NaiveDate, TimeDelta, Utc};
+use minijinja::{Environment, context};
+
+use crate::{
+    args::{Args, ReportFormat},
+    db::{get_daily_volumes, get_largest_orders, get_summary},
+};
+
+const MARKDOWN_TEMPLATE: &str = include_str!("../../templates/report.md");
+const HTML_TEMPLATE: &str = include_str!("../../templates/report.html");
+
+/// Number of orders listed in the top orders section
+const TOP_ORDERS: usize = 10;
+
+/// Width of the longest bar of the Markdown charts
+const BAR_WIDTH: f64 = 30.0;
+
+pub fn run(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    format: ReportFormat,
+    output: Option<&Path>,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let to = to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = from.unwrap_or(to - TimeDelta::days(7));
+    let from_time = from.and_time(Default::default()).and_utc();
+    let to_time = to.and_time(Default::default()).and_utc();
+
+    let pairs = get_summary(&args.persist_path, from_time, to_time)?;
+    let volumes = get_daily_volumes(&args.persist_path, from_time, to_time)?;
+    let top_orders = get_largest_orders(&args.persist_path, from_time, to_time, TOP_ORDERS)?;
+
+    // Bars are scaled per fiat currency, as their volumes can't be compared
+    let mut max_volumes = HashMap::<&str, f64>::new();
+
+    for volume in &volumes {
+        let max = max_volumes.entry(&volume.fiat_symbol).or_default();
+        *max = max.max(volume.buy_fiat_volume + volume.sell_fiat_volume);
+    }
+
+    let days = volumes
+        .iter()
+        .map(|volume| {
+            let fiat_volume = volume.buy_fiat_volume + volume.sell_fiat_volume;
+            let max = max_volumes[volume.fiat_symbol.as_str()];
+            let share = if max > 0.0 { fiat_volume / max } else { 0.0 };
+            let buy_share = if fiat_volume > 0.0 {
+                volume.buy_fiat_volume / fiat_volume
+            } else {
+                0.0
+            };
+
+            context! {
+                day => volume.day.to_string(),
+                fiat_symbol => volume.fiat_symbol,
+                orders => volume.orders,
+                fiat_volume,
+                share => (share * 100.0).round() as u32,
+                buy_share => (buy_share * 100.0).round() as u32,
+                bar => "█".repeat((share * BAR_WIDTH).round() as usize),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let top_orders = top_orders
+        .into_iter()
+        .map(|(created_at, order)| {
+            context! {
+                created_at => created_at
+                    .with_timezone(&args.timezone)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+                order => order.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let template = match format {
+        ReportFormat::Markdown => MARKDOWN_TEMPLATE,
+        ReportFormat::Html => HTML_TEMPLATE,
+    };
+
+    let mut environment = Environment::new();
+    environment.set_auto_escape_callback(move |_| match format {
+        ReportFormat::Markdown => minijinja::AutoEscape::None,
+        ReportFormat::Html => minijinja::AutoEscape::Html,
+    });
+
+    let report = environment.render_str(
+        template,
+        context! {
+            from => from.to_string(),
+            to => to.to_string(),
+            pairs,
+            days,
+            top_orders,
+        },
+    )?;
+
+    match output {
+        Some(path) => fs::write(path, report)?,
+        None => print!("{report}"),
+    }
+
+    Ok(())
+}
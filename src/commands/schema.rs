@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    args::{Args, SchemaFormat},
+    db::{create_information_view, get_columns, get_schema_sql},
+};
+
+pub fn run(format: SchemaFormat, create_view: bool, args: &Args) -> anyhow::Result<()> {
+    if create_view {
+        create_information_view(&args.persist_path)?;
+    }
+
+    let columns = get_columns(&args.persist_path)?;
+
+    match format {
+        SchemaFormat::Sql => {
+            for statement in get_schema_sql(&args.persist_path)? {
+                println!("{statement}");
+                println!();
+            }
+
+            for column in columns {
+                if let Some(description) = column.description {
+                    println!("-- {}.{}: {description}", column.table, column.column);
+                }
+            }
+        }
+        SchemaFormat::Json => {
+            let mut tables = BTreeMap::<_, Vec<_>>::new();
+
+            for column in columns {
+                tables.entry(column.table.clone()).or_default().push(column);
+            }
+
+            println!("{}", serde_json::to_string_pretty(&tables)?);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::{fs, fs::File, path::Path};
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+use crate::{
+    args::{Args, BackupCompression, StateCommand},
+    db::{backup, get_connection, restore},
+    migrations::current_version,
+};
+
+/// Describes the content of a state archive, written as `state.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct StateManifest {
+    collector_version: String,
+    schema_version: i64,
+    exported_at: DateTime<Utc>,
+    row_counts: Vec<(String, i64)>,
+    /// Whether the archive holds the config file as `config.toml`
+    config: bool,
+}
+
+const MANIFEST: &str = "state.json";
+const CONFIG: &str = "config.toml";
+const DATABASE: &str = "database";
+
+pub fn run(command: &StateCommand, args: &Args) -> anyhow::Result<()> {
+    match command {
+        StateCommand::Export { output } => export(output, args),
+        StateCommand::Import {
+            input,
+            config_output,
+        } => import(input, config_output.as_deref(), args),
+    }
+}
+
+/// Bundles the database, as `EXPORT DATABASE` writes it, the config file and a
+/// manifest into a tar archive
+fn export(output: &Path, args: &Args) -> anyhow::Result<()> {
+    let staging = staging_dir()?;
+    let database = staging.path().join(DATABASE);
+    let row_counts = backup(
+        &args.persist_path,
+        &database.to_string_lossy(),
+        BackupCompression::Zstd.as_duckdb(),
+    )?;
+
+    let manifest = StateManifest {
+        collector_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: current_version(&get_connection(&args.persist_path)?)?,
+        exported_at: Utc::now(),
+        row_counts,
+        config: args.config.is_some(),
+    };
+
+    let mut archive = tar::Builder::new(File::create(output)?);
+    let manifest_file = staging.path().join(MANIFEST);
+    fs::write(&manifest_file, serde_json::to_vec_pretty(&manifest)?)?;
+    archive.append_path_with_name(&manifest_file, MANIFEST)?;
+    archive.append_dir_all(DATABASE, &database)?;
+
+    if let Some(config) = &args.config {
+        archive
+            .append_path_with_name(config, CONFIG)
+            .with_context(|| format!("Failed to add the config {}", config.display()))?;
+    }
+
+    archive.into_inner()?.sync_all()?;
+
+    for (table, count) in &manifest.row_counts {
+        println!("{table}: {count} rows");
+    }
+
+    println!(
+        "State at schema version {} exported to {}",
+        manifest.schema_version,
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Restores the database of an archive into a new database at `--persist-path`,
+/// writing its config to `config_output`
+fn import(input: &Path, config_output: Option<&Path>, args: &Args) -> anyhow::Result<()> {
+    let staging = staging_dir()?;
+    tar::Archive::new(File::open(input)?).unpack(staging.path())?;
+
+    let manifest: StateManifest = serde_json::from_slice(
+        &fs::read(staging.path().join(MANIFEST))
+            .with_context(|| format!("{} is not a state archive", input.display()))?,
+    )?;
+    let schema_version = current_version(&get_connection(&args.persist_path)?)?;
+
+    if manifest.schema_version > schema_version {
+        bail!(
+            "The state is at schema version {}, newer than the {schema_version} this collector knows, upgrade it first",
+            manifest.schema_version
+        );
+    }
+
+    for (table, count) in restore(
+        &args.persist_path,
+        &staging.path().join(DATABASE).to_string_lossy(),
+    )? {
+        println!("{table}: {count} rows");
+    }
+
+    match (manifest.config, config_output) {
+        (true, Some(config_output)) => {
+            fs::copy(staging.path().join(CONFIG), config_output)?;
+            println!("Config written to {}", config_output.display());
+        }
+        (true, None) => println!("The state holds a config, pass --config-output to write it"),
+        (false, _) => {}
+    }
+
+    println!(
+        "State exported at {} by collector {} imported to {}, migrated at the next start",
+        manifest.exported_at, manifest.collector_version, args.persist_path
+    );
+
+    Ok(())
+}
+
+/// Directory the archive is assembled in or unpacked to, private to the user
+/// and under a random name, removed once done
+fn staging_dir() -> anyhow::Result<TempDir> {
+    Ok(tempfile::Builder::new()
+        .prefix("nash-stats-state-")
+        .tempdir()?)
+}
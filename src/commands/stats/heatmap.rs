@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, TimeDelta, Timelike, Utc, Weekday};
+use serde::Serialize;
+
+use crate::{
+    args::{Args, HeatmapMetric, QueryFormat},
+    db::get_hourly_activity,
+    export::Pair,
+};
+
+/// From no activity to the busiest cell
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+const DAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Activity of an hour of the day on a day of the week
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct Cell {
+    orders: u64,
+    fiat_volume: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct HeatmapCell {
+    day: String,
+    hour: u32,
+    orders: u64,
+    fiat_volume: f64,
+}
+
+pub fn run(
+    window: Duration,
+    pair: Option<&Pair>,
+    metric: HeatmapMetric,
+    format: QueryFormat,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+
+    // The orders are grouped by UTC hour, which are whole hours of the
+    // timezone unless its offset has minutes
+    let mut grid = [[Cell::default(); 24]; 7];
+
+    for activity in get_hourly_activity(&args.persist_path, pair, from, to)? {
+        let at = activity.hour.with_timezone(&args.timezone);
+        let cell = &mut grid[at.weekday().num_days_from_monday() as usize][at.hour() as usize];
+
+        cell.orders += activity.orders;
+        cell.fiat_volume += activity.fiat_volume;
+    }
+
+    let cells = DAYS.iter().zip(&grid).flat_map(|(day, hours)| {
+        hours.iter().enumerate().map(|(hour, cell)| HeatmapCell {
+            day: day.to_string(),
+            hour: hour as u32,
+            orders: cell.orders,
+            fiat_volume: cell.fiat_volume,
+        })
+    });
+
+    match format {
+        QueryFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&cells.collect::<Vec<_>>())?
+            );
+        }
+        QueryFormat::Csv => {
+            println!("day,hour,orders,fiat_volume");
+
+            for cell in cells {
+                println!(
+                    "{},{},{},{}",
+                    cell.day, cell.hour, cell.orders, cell.fiat_volume
+                );
+            }
+        }
+        QueryFormat::Table => print_heatmap(&grid, metric, from, args),
+    }
+
+    Ok(())
+}
+
+/// Draws a row per day and two columns per hour, the darker the busier
+fn print_heatmap(grid: &[[Cell; 24]; 7], metric: HeatmapMetric, from: DateTime<Utc>, args: &Args) {
+    let value = |cell: &Cell| match metric {
+        HeatmapMetric::Orders => cell.orders as f64,
+        HeatmapMetric::Volume => cell.fiat_volume,
+    };
+    let max = grid
+        .iter()
+        .flatten()
+        .fold(0.0, |max: f64, cell| max.max(value(cell)));
+
+    println!(
+        "{} by hour of the day since {from}, in {}",
+        match metric {
+            HeatmapMetric::Orders => "Orders",
+            HeatmapMetric::Volume => "Fiat volume",
+        },
+        args.timezone
+    );
+    println!();
+    println!(
+        "    {}",
+        (0..24)
+            .step_by(3)
+            .map(|hour| format!("{hour:<6}"))
+            .collect::<String>()
+    );
+
+    for (day, hours) in DAYS.iter().zip(grid) {
+        let line = hours
+            .iter()
+            .flat_map(|cell| {
+                let shade = if max > 0.0 && value(cell) > 0.0 {
+                    // Any activity shows, the busiest cell being the darkest
+                    SHADES[1 + ((value(cell) / max) * (SHADES.len() - 2) as f64).round() as usize]
+                } else {
+                    SHADES[0]
+                };
+
+                [shade, shade]
+            })
+            .collect::<String>();
+
+        println!("{day} {line} {:.0}", hours.iter().map(value).sum::<f64>());
+    }
+
+    println!();
+    println!(
+        "{} is {max:.0}, blank hours had no orders",
+        SHADES[SHADES.len() - 1].to_string().repeat(2)
+    );
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_size_distributions,
+    export::Pair,
+};
+
+/// Width of the longest bar
+const BAR_WIDTH: usize = 40;
+
+pub fn run(
+    window: Duration,
+    pair: Option<&Pair>,
+    format: StatsFormat,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let distributions = get_size_distributions(&args.persist_path, pair, from, to)?;
+
+    if let StatsFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&distributions)?);
+
+        return Ok(());
+    }
+
+    println!("Order sizes since {from}");
+
+    for distribution in distributions {
+        let max_orders = distribution
+            .buckets
+            .iter()
+            .map(|bucket| bucket.orders)
+            .max()
+            .unwrap_or_default();
+
+        println!();
+        println!(
+            "{}/{}: {} orders, min {:.2}, p50 {:.2}, p90 {:.2}, p99 {:.2}, max {:.2}",
+            distribution.crypto_symbol,
+            distribution.fiat_symbol,
+            distribution.orders,
+            distribution.min,
+            distribution.p50,
+            distribution.p90,
+            distribution.p99,
+            distribution.max
+        );
+        println!();
+
+        for bucket in distribution.buckets {
+            println!(
+                "{:>12} - {:<12} {:>8} {}",
+                bucket.lower,
+                bucket.upper,
+                bucket.orders,
+                "█".repeat((bucket.orders as usize * BAR_WIDTH).div_ceil(max_orders as usize))
+            );
+        }
+    }
+
+    Ok(())
+}
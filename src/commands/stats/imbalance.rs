@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_imbalance_summary,
+};
+
+pub fn run(window: Duration, format: StatsFormat, args: &Args) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let summaries = get_imbalance_summary(&args.persist_path, from, to)?;
+
+    if let StatsFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+
+        return Ok(());
+    }
+
+    println!("Buy share of the fiat volume since {from}");
+    println!();
+    println!(
+        "{:<12} {:>8} {:>8} {:>8} {:>8} {:<20}",
+        "pair", "latest", "avg", "min", "max", "updated at"
+    );
+
+    for summary in summaries {
+        println!(
+            "{:<12} {:>7.0}% {:>7.0}% {:>7.0}% {:>7.0}% {:<20}",
+            format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+            summary.latest * 100.0,
+            summary.avg * 100.0,
+            summary.min * 100.0,
+            summary.max * 100.0,
+            summary
+                .latest_at
+                .with_timezone(&args.timezone)
+                .format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_candles,
+    export::Pair,
+    indicators::{
+        MA_PERIOD, MACD_FAST_PERIOD, MACD_SIGNAL_PERIOD, MACD_SLOW_PERIOD, RSI_PERIOD, Timeframe,
+        WARM_UP, compute,
+    },
+};
+
+pub fn run(
+    pair: &Pair,
+    timeframe: Timeframe,
+    window: Duration,
+    format: StatsFormat,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let candle_duration = timeframe.duration();
+    let candles = get_candles(
+        &args.persist_path,
+        pair,
+        candle_duration,
+        from - candle_duration * WARM_UP as i32,
+        to,
+    )?;
+    let points = compute(&candles)
+        .into_iter()
+        .filter(|point| point.start >= from)
+        .collect::<Vec<_>>();
+
+    if let StatsFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&points)?);
+
+        return Ok(());
+    }
+
+    let value = |value: Option<f64>| {
+        value
+            .map(|value| format!("{value:.2}"))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    println!("{pair} {timeframe} candles since {from}");
+    println!();
+    println!(
+        "{:<17} {:>12} {:>12} {:>12} {:>7} {:>10} {:>10} {:>10}",
+        "start",
+        "close",
+        format!("sma{MA_PERIOD}"),
+        format!("ema{MA_PERIOD}"),
+        format!("rsi{RSI_PERIOD}"),
+        format!("macd{MACD_FAST_PERIOD}/{MACD_SLOW_PERIOD}"),
+        format!("signal{MACD_SIGNAL_PERIOD}"),
+        "histogram"
+    );
+
+    for point in points {
+        println!(
+            "{:<17} {:>12.2} {:>12} {:>12} {:>7} {:>10} {:>10} {:>10}",
+            point
+                .start
+                .with_timezone(&args.timezone)
+                .format("%Y-%m-%d %H:%M"),
+            point.close,
+            value(point.sma),
+            value(point.ema),
+            value(point.rsi),
+            value(point.macd),
+            value(point.macd_signal),
+            value(point.macd_histogram)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsCommand},
+    db::{get_pair_orders, get_session_summary, get_summary},
+    export::Pair,
+    fetch::Order,
+    numbers::NumberFormat,
+};
+
+mod heatmap;
+mod histogram;
+mod imbalance;
+mod indicators;
+mod premium;
+mod profile;
+mod top;
+mod volatility;
+
+/// Number of columns of the terminal charts
+const CHART_WIDTH: usize = 60;
+
+/// Number of rows of the candle chart
+const CHART_HEIGHT: usize = 12;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub fn run(
+    command: Option<&StatsCommand>,
+    since: Duration,
+    chart: Option<&Pair>,
+    args: &Args,
+) -> anyhow::Result<()> {
+    match command {
+        Some(StatsCommand::Top { window, limit }) => top::run(*window, *limit, args),
+        Some(StatsCommand::Heatmap {
+            window,
+            pair,
+            metric,
+            format,
+        }) => heatmap::run(*window, pair.as_ref(), *metric, *format, args),
+        Some(StatsCommand::Histogram {
+            window,
+            pair,
+            format,
+        }) => histogram::run(*window, pair.as_ref(), *format, args),
+        Some(StatsCommand::Indicators {
+            pair,
+            timeframe,
+            window,
+            format,
+        }) => indicators::run(pair, *timeframe, *window, *format, args),
+        Some(StatsCommand::Premium { window, format }) => premium::run(*window, *format, args),
+        Some(StatsCommand::Imbalance { window, format }) => imbalance::run(*window, *format, args),
+        Some(StatsCommand::Profile {
+            pair,
+            window,
+            levels,
+            format,
+        }) => profile::run(pair, *window, *levels, *format, args),
+        Some(StatsCommand::Volatility {
+            window,
+            rolling,
+            pair,
+            format,
+        }) => volatility::run(*window, *rolling, pair.as_ref(), *format, args),
+        None => summary(since, chart, args),
+    }
+}
+
+fn summary(since: Duration, chart: Option<&Pair>, args: &Args) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(since)?;
+    let numbers = NumberFormat::from_args(args);
+
+    println!("Activity since {from}");
+    println!();
+    println!(
+        "{:<12} {:>8} {:>6} {:>6} {:>20} {:>16} {:>14} {:>16}",
+        "pair",
+        "orders",
+        "buys",
+        "sells",
+        "crypto volume",
+        "fiat volume",
+        "avg price",
+        args.fx_base
+            .as_deref()
+            .map(|base| format!("{} volume", base.to_uppercase()))
+            .unwrap_or_default()
+    );
+
+    let mut base_fiat_volume = None;
+    let base = args.fx_base.as_deref().unwrap_or_default().to_uppercase();
+
+    for summary in get_summary(&args.persist_path, from, to)? {
+        println!(
+            "{:<12} {:>8} {:>6} {:>6} {:>20} {:>16} {:>14} {:>16}",
+            format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+            summary.orders,
+            summary.buys,
+            summary.sells,
+            numbers.crypto(summary.crypto_volume, &summary.crypto_symbol),
+            numbers.fiat(summary.fiat_volume, &summary.fiat_symbol),
+            numbers.fiat(summary.avg_price, &summary.fiat_symbol),
+            summary
+                .base_fiat_volume
+                .map(|volume| numbers.fiat(volume, &base))
+                .unwrap_or_default()
+        );
+
+        if let Some(volume) = summary.base_fiat_volume {
+            *base_fiat_volume.get_or_insert(0.0) += volume;
+        }
+    }
+
+    if let Some(volume) = base_fiat_volume {
+        println!("{:<12} {:>92}", "total", numbers.fiat(volume, &base));
+    }
+
+    println!();
+    println!("Sessions");
+    println!();
+    println!(
+        "{:<12} {:<5} {:>8} {:>12} {:>12} {:>14} {:>14}",
+        "pair", "side", "sessions", "avg orders", "max orders", "avg size", "max size"
+    );
+
+    for summary in get_session_summary(&args.persist_path, from, to)? {
+        println!(
+            "{:<12} {:<5} {:>8} {:>12.1} {:>12} {:>14.2} {:>14.2}",
+            format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+            summary.ty,
+            summary.sessions,
+            summary.avg_orders,
+            summary.max_orders,
+            summary.avg_fiat_amount,
+            summary.max_fiat_amount
+        );
+    }
+
+    if let Some(pair) = chart {
+        let orders = get_pair_orders(&args.persist_path, pair, from, to)?;
+        let candles = to_candles(&orders, from, to);
+
+        println!();
+        println!("{pair} price");
+        println!();
+        print_candles(&candles);
+        println!();
+        println!("{pair} volume");
+        println!();
+        println!(
+            "{}",
+            sparkline(
+                &candles
+                    .iter()
+                    .map(|candle| candle.map(|c| c.volume))
+                    .collect::<Vec<_>>()
+            )
+        );
+        println!(
+            "{}{:>width$}",
+            from.with_timezone(&args.timezone).format("%m-%d %H:%M"),
+            to.with_timezone(&args.timezone).format("%m-%d %H:%M"),
+            width = CHART_WIDTH - 11
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candle {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+/// Splits the period in [`CHART_WIDTH`] buckets, empty buckets having no candle
+fn to_candles(
+    orders: &[(DateTime<Utc>, Order)],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<Option<Candle>> {
+    let bucket_ms = ((to - from).num_milliseconds() / CHART_WIDTH as i64).max(1);
+    let mut candles = vec![None::<Candle>; CHART_WIDTH];
+
+    for (at, order) in orders {
+        let index =
+            ((*at - from).num_milliseconds() / bucket_ms).clamp(0, CHART_WIDTH as i64 - 1) as usize;
+        let price = order.fiat_price;
+
+        candles[index] = Some(match candles[index] {
+            Some(candle) => Candle {
+                high: candle.high.max(price),
+                low: candle.low.min(price),
+                close: price,
+                volume: candle.volume + order.fiat_amount,
+                ..candle
+            },
+            None => Candle {
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: order.fiat_amount,
+            },
+        });
+    }
+
+    candles
+}
+
+fn sparkline(values: &[Option<f64>]) -> String {
+    let max = values
+        .iter()
+        .flatten()
+        .fold(0.0, |max: f64, value| max.max(*value));
+
+    values
+        .iter()
+        .map(|value| match value {
+            Some(value) if max > 0.0 => {
+                SPARKS[((value / max) * (SPARKS.len() - 1) as f64).round() as usize]
+            }
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// Draws the candles top to bottom, `│` being the wicks and `█`/`▒` the rising/falling bodies
+fn print_candles(candles: &[Option<Candle>]) {
+    let (low, high) = candles
+        .iter()
+        .flatten()
+        .fold((f64::MAX, f64::MIN), |(low, high), candle| {
+            (low.min(candle.low), high.max(candle.high))
+        });
+
+    if low > high {
+        println!("No orders");
+        return;
+    }
+
+    let step = ((high - low) / (CHART_HEIGHT - 1) as f64).max(f64::EPSILON);
+    let row_of = |price: f64| ((price - low) / step).round() as usize;
+
+    for row in (0..CHART_HEIGHT).rev() {
+        let line = candles
+            .iter()
+            .map(|candle| match candle {
+                Some(candle) => {
+                    let (body_low, body_high) = if candle.close >= candle.open {
+                        (candle.open, candle.close)
+                    } else {
+                        (candle.close, candle.open)
+                    };
+
+                    if (row_of(body_low)..=row_of(body_high)).contains(&row) {
+                        if candle.close >= candle.open {
+                            '█'
+                        } else {
+                            '▒'
+                        }
+                    } else if (row_of(candle.low)..=row_of(candle.high)).contains(&row) {
+                        '│'
+                    } else {
+                        ' '
+                    }
+                }
+                None => ' ',
+            })
+            .collect::<String>();
+
+        println!("{:>12.2} {line}", low + step * row as f64);
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_premium_summary,
+};
+
+pub fn run(window: Duration, format: StatsFormat, args: &Args) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let summaries = get_premium_summary(&args.persist_path, from, to)?;
+
+    if let StatsFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+
+        return Ok(());
+    }
+
+    println!("Premium to the reference prices since {from}");
+    println!();
+    println!(
+        "{:<12} {:>8} {:>9} {:>9} {:>9} {:>9}",
+        "pair", "orders", "latest", "avg", "min", "max"
+    );
+
+    for summary in summaries {
+        println!(
+            "{:<12} {:>8} {:>+8.2}% {:>+8.2}% {:>+8.2}% {:>+8.2}%",
+            format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+            summary.orders,
+            summary.latest * 100.0,
+            summary.avg * 100.0,
+            summary.min * 100.0,
+            summary.max * 100.0
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_volume_profile,
+    export::Pair,
+    numbers::NumberFormat,
+};
+
+/// Width of the longest bar
+const BAR_WIDTH: usize = 40;
+
+pub fn run(
+    pair: &Pair,
+    window: Duration,
+    levels: usize,
+    format: StatsFormat,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let profile = get_volume_profile(&args.persist_path, pair, levels, from, to)?;
+
+    if let StatsFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&profile)?);
+
+        return Ok(());
+    }
+
+    let numbers = NumberFormat::from_args(args);
+
+    println!("{pair} volume by price since {from}");
+    println!();
+
+    let Some(point_of_control) = profile.point_of_control else {
+        println!("No orders");
+
+        return Ok(());
+    };
+
+    println!(
+        "{} orders, {}, most traded around {}",
+        profile.orders,
+        numbers.fiat(profile.fiat_volume, &profile.fiat_symbol),
+        numbers.fiat(point_of_control, &profile.fiat_symbol)
+    );
+    println!();
+
+    let max_volume = profile
+        .levels
+        .iter()
+        .fold(0.0, |max: f64, level| max.max(level.fiat_volume));
+
+    // Highest prices on top, like an order book
+    for level in profile.levels.iter().rev() {
+        let bar = if max_volume > 0.0 {
+            ((level.fiat_volume / max_volume) * BAR_WIDTH as f64).ceil() as usize
+        } else {
+            0
+        };
+
+        println!(
+            "{:>12.2} - {:<12.2} {:>8} {:>16} {}",
+            level.lower,
+            level.upper,
+            level.orders,
+            numbers.fiat(level.fiat_volume, &profile.fiat_symbol),
+            "█".repeat(bar)
+        );
+    }
+
+    Ok(())
+}
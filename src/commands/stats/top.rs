@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::Args,
+    db::{get_blockchain_activity, get_largest_orders, get_summary},
+    numbers::NumberFormat,
+};
+
+pub fn run(window: Duration, limit: usize, args: &Args) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let numbers = NumberFormat::from_args(args);
+
+    println!("Largest orders since {from}");
+    println!();
+    println!(
+        "{:<20} {:<12} {:<5} {:>20} {:>16} {:>14} {:<12}",
+        "created at", "pair", "side", "crypto amount", "fiat amount", "price", "blockchain"
+    );
+
+    for (created_at, order) in get_largest_orders(&args.persist_path, from, to, limit)? {
+        println!(
+            "{:<20} {:<12} {:<5} {:>20} {:>16} {:>14} {:<12}",
+            created_at
+                .with_timezone(&args.timezone)
+                .format("%Y-%m-%d %H:%M:%S"),
+            format!("{}/{}", order.crypto_symbol, order.fiat_symbol),
+            order.ty,
+            numbers.crypto(order.crypto_amount, &order.crypto_symbol),
+            numbers.fiat(order.fiat_amount, &order.fiat_symbol),
+            numbers.fiat(order.fiat_price, &order.fiat_symbol),
+            order.blockchain
+        );
+    }
+
+    let mut pairs = get_summary(&args.persist_path, from, to)?;
+    pairs.sort_by(|a, b| b.orders.cmp(&a.orders));
+
+    println!();
+    println!("Most traded pairs");
+    println!();
+    println!(
+        "{:<12} {:>8} {:>6} {:>6} {:>16} {:>16}",
+        "pair", "orders", "buys", "sells", "fiat volume", "largest"
+    );
+
+    for summary in pairs.iter().take(limit) {
+        println!(
+            "{:<12} {:>8} {:>6} {:>6} {:>16} {:>16}",
+            format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+            summary.orders,
+            summary.buys,
+            summary.sells,
+            numbers.fiat(summary.fiat_volume, &summary.fiat_symbol),
+            numbers.fiat(summary.largest_fiat_amount, &summary.fiat_symbol)
+        );
+    }
+
+    println!();
+    println!("Most active blockchains");
+    println!();
+    println!(
+        "{:<12} {:>8} {:>6} {:>16}",
+        "blockchain",
+        "orders",
+        "pairs",
+        args.fx_base
+            .as_deref()
+            .map(|base| format!("{} volume", base.to_uppercase()))
+            .unwrap_or_default()
+    );
+
+    for activity in get_blockchain_activity(&args.persist_path, from, to, limit)? {
+        println!(
+            "{:<12} {:>8} {:>6} {:>16}",
+            activity.blockchain,
+            activity.orders,
+            activity.pairs,
+            activity
+                .base_fiat_volume
+                .map(|volume| format!("{volume:.2}"))
+                .unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    args::{Args, StatsFormat},
+    db::get_volatility_summary,
+    export::Pair,
+};
+
+pub fn run(
+    window: Duration,
+    rolling: Duration,
+    pair: Option<&Pair>,
+    format: StatsFormat,
+    args: &Args,
+) -> anyhow::Result<()> {
+    let to = Utc::now();
+    let from = to - TimeDelta::from_std(window)?;
+    let summaries = get_volatility_summary(
+        &args.persist_path,
+        pair,
+        TimeDelta::from_std(rolling)?,
+        from,
+        to,
+    )?;
+
+    if let StatsFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+
+        return Ok(());
+    }
+
+    println!(
+        "Realized volatility since {from}, and over {} windows",
+        humantime::format_duration(rolling)
+    );
+    println!();
+    println!(
+        "{:<12} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        "pair", "returns", "period", "latest", "avg", "max"
+    );
+
+    for summary in summaries {
+        println!(
+            "{:<12} {:>8} {:>9.2}% {:>9.2}% {:>9.2}% {:>9.2}%",
+            format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+            summary.returns,
+            summary.realized * 100.0,
+            summary.latest * 100.0,
+            summary.avg * 100.0,
+            summary.max * 100.0
+        );
+    }
+
+    Ok(())
+}
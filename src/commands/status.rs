@@ -0,0 +1,26 @@
+use tracing::warn;
+
+use crate::{
+    args::{Args, CtlCommand},
+    control::send,
+    db::get_status,
+};
+
+/// Asks the running collector first, the database only telling the totals of
+/// the stored fetch runs
+pub async fn run(args: &Args) -> anyhow::Result<()> {
+    if let Some(path) = &args.control_socket {
+        match send(path, &CtlCommand::Status).await {
+            Ok(status) => {
+                println!("{status}");
+
+                return Ok(());
+            }
+            Err(err) => warn!("{err:#}, reading the status from the database"),
+        }
+    }
+
+    println!("{}", get_status(&args.persist_path)?);
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashSet, time::Duration};
+
+use tokio::time::sleep;
+
+use crate::{
+    args::Args,
+    db::{OrderCursor, OrderFilter, get_orders_page},
+    fetch::Order,
+};
+
+pub async fn run(
+    follow: bool,
+    lines: usize,
+    interval: Duration,
+    filter: &OrderFilter,
+    args: &Args,
+) -> anyhow::Result<()> {
+    // The latest order is fetched even with no lines to print, for the older
+    // ones not to be printed once following
+    let mut latest = get_orders_page(&args.persist_path, filter, None, lines.max(1), true)?;
+    latest.reverse();
+
+    let mut position = Position::default();
+
+    for (index, (cursor, order)) in latest.iter().enumerate() {
+        if index + lines >= latest.len() {
+            print_order(cursor, order, args);
+        }
+
+        position.advance(cursor);
+    }
+
+    while follow {
+        sleep(interval).await;
+
+        // The orders of a cycle share its timestamp and are inserted one at a
+        // time, so the latest instant is read again for the ones stored since
+        let after = position.latest.as_ref().map(|latest| OrderCursor {
+            created_at: latest.created_at,
+            hash: String::new(),
+        });
+
+        for (cursor, order) in get_orders_page(
+            &args.persist_path,
+            filter,
+            after.as_ref(),
+            usize::MAX,
+            false,
+        )? {
+            if !position.printed(&cursor) {
+                print_order(&cursor, &order, args);
+                position.advance(&cursor);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Latest order printed, with the others of the same instant
+#[derive(Debug, Default)]
+struct Position {
+    latest: Option<OrderCursor>,
+    hashes: HashSet<String>,
+}
+
+impl Position {
+    fn printed(&self, cursor: &OrderCursor) -> bool {
+        self.latest
+            .as_ref()
+            .is_some_and(|latest| cursor.created_at == latest.created_at)
+            && self.hashes.contains(&cursor.hash)
+    }
+
+    fn advance(&mut self, cursor: &OrderCursor) {
+        if self
+            .latest
+            .as_ref()
+            .is_none_or(|latest| latest.created_at != cursor.created_at)
+        {
+            self.hashes.clear();
+        }
+
+        self.hashes.insert(cursor.hash.clone());
+        self.latest = Some(cursor.clone());
+    }
+}
+
+fn print_order(cursor: &OrderCursor, order: &Order, args: &Args) {
+    println!(
+        "{} {order}",
+        cursor
+            .created_at
+            .with_timezone(&args.timezone)
+            .format("%Y-%m-%d %H:%M:%S")
+    );
+}
@@ -0,0 +1,62 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+use tracing::level_filters::LevelFilter;
+
+use crate::{alerts::AlertRules, auth::HttpAccess, export::ExportSubscription};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub exports: Vec<ExportSubscription>,
+    pub alerts: AlertRules,
+    pub http: HttpAccess,
+    pub logging: LoggingConfig,
+    pub notifiers: NotifierConfig,
+}
+
+/// Overrides of the log options, applied again on SIGHUP
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Level of the console logs, `--log-level` when not set
+    #[serde(deserialize_with = "level")]
+    pub level: Option<LevelFilter>,
+}
+
+/// Overrides of the notifier thresholds, applied again on SIGHUP, the
+/// options being used for the ones not set
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifierConfig {
+    pub slack_min_fiat_amount: Option<f64>,
+    pub slack_alert_fiat_amount: Option<f64>,
+    pub ntfy_min_fiat_amount: Option<f64>,
+    pub ntfy_high_fiat_amount: Option<f64>,
+    pub ntfy_urgent_fiat_amount: Option<f64>,
+    #[cfg(feature = "desktop")]
+    pub desktop_min_fiat_amount: Option<f64>,
+}
+
+fn level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<LevelFilter>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    value.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+impl Config {
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Config::default());
+        };
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+
+        let config = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+        Ok(config)
+    }
+}
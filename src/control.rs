@@ -0,0 +1,153 @@
+use std::{
+    fmt::Display, fs, io::ErrorKind, os::unix::fs::PermissionsExt, path::Path, str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{Context, anyhow, bail};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::{mpsc, oneshot},
+};
+use tracing::{error, info};
+
+use crate::args::CtlCommand;
+
+/// Sent by the main loop to every collector
+#[derive(Debug, Clone, Copy)]
+pub enum CollectorSignal {
+    Pause,
+    Resume,
+    FetchNow,
+    SetInterval(Duration),
+}
+
+/// Command read from the socket, answered by the main loop with a message
+/// or an error
+pub type ControlRequest = (CtlCommand, oneshot::Sender<anyhow::Result<String>>);
+
+/// Accepts a command per connection on the Unix socket at `path`, replacing
+/// the one a previous collector may have left, only its owner being allowed
+/// to connect
+pub fn spawn_control_socket(path: &Path) -> anyhow::Result<mpsc::Receiver<ControlRequest>> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            return Err(err).with_context(|| format!("Failed to remove {}", path.display()));
+        }
+        _ => {}
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind the control socket {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    info!("Control socket listening on {}", path.display());
+
+    let (requests_tx, requests_rx) = mpsc::channel(8);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let requests = requests_tx.clone();
+
+                    tokio::spawn(async move {
+                        if let Err(err) = answer(stream, requests).await {
+                            error!("Failed to answer a control command: {err}");
+                        }
+                    });
+                }
+                Err(err) => error!("Failed to accept a control connection: {err}"),
+            }
+        }
+    });
+
+    Ok(requests_rx)
+}
+
+async fn answer(stream: UnixStream, requests: mpsc::Sender<ControlRequest>) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let reply = match line.trim().parse::<CtlCommand>() {
+        Ok(command) => {
+            let (reply_tx, reply_rx) = oneshot::channel();
+
+            requests
+                .send((command, reply_tx))
+                .await
+                .map_err(|_| anyhow!("The collector is shutting down"))?;
+            reply_rx.await?
+        }
+        Err(err) => Err(err),
+    };
+
+    let reply = match reply {
+        Ok(message) => format!("ok: {message}\n"),
+        Err(err) => format!("error: {err:#}\n"),
+    };
+
+    writer.write_all(reply.as_bytes()).await?;
+    writer.shutdown().await?;
+
+    Ok(())
+}
+
+/// Sends `command` to the collector listening on `path` and returns its
+/// answer, failing when it answered with an error
+pub async fn send(path: &Path, command: &CtlCommand) -> anyhow::Result<String> {
+    let mut stream = UnixStream::connect(path).await.with_context(|| {
+        format!(
+            "Failed to connect to {}, check that the collector is running with --control-socket",
+            path.display()
+        )
+    })?;
+
+    stream.write_all(format!("{command}\n").as_bytes()).await?;
+    stream.shutdown().await?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).await?;
+
+    match reply.trim_end().split_once(": ") {
+        Some(("ok", message)) => Ok(message.to_string()),
+        Some(("error", message)) => bail!("{message}"),
+        _ => bail!("Unexpected answer from the collector: {reply}"),
+    }
+}
+
+/// The line sent on the socket
+impl Display for CtlCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CtlCommand::Pause => write!(f, "pause"),
+            CtlCommand::Resume => write!(f, "resume"),
+            CtlCommand::FetchNow => write!(f, "fetch-now"),
+            CtlCommand::Flush => write!(f, "flush"),
+            CtlCommand::SetInterval { interval } => {
+                write!(f, "set-interval {}", humantime::format_duration(*interval))
+            }
+            CtlCommand::Status => write!(f, "status"),
+        }
+    }
+}
+
+impl FromStr for CtlCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(' ') {
+            None if s == "pause" => Ok(CtlCommand::Pause),
+            None if s == "resume" => Ok(CtlCommand::Resume),
+            None if s == "fetch-now" => Ok(CtlCommand::FetchNow),
+            None if s == "flush" => Ok(CtlCommand::Flush),
+            None if s == "status" => Ok(CtlCommand::Status),
+            Some(("set-interval", interval)) => Ok(CtlCommand::SetInterval {
+                interval: humantime::parse_duration(interval.trim())?,
+            }),
+            _ => Err(anyhow!("Unknown command {s}")),
+        }
+    }
+}
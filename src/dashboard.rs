@@ -0,0 +1,34 @@
+use axum::{
+    Router,
+    extract::Path,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use rust_embed::RustEmbed;
+
+/// Static files of the dashboard, bundled into the binary
+#[derive(RustEmbed)]
+#[folder = "dashboard/"]
+struct Assets;
+
+/// The dashboard at `/`, calling the API from the browser
+pub fn router() -> Router {
+    Router::new().route("/", get(|| asset("index.html"))).route(
+        "/dashboard/{*path}",
+        get(|Path(path): Path<String>| asset(path)),
+    )
+}
+
+async fn asset(path: impl AsRef<str>) -> Response {
+    let path = path.as_ref();
+
+    match Assets::get(path) {
+        Some(file) => (
+            [(CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data,
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, format!("No asset {path}")).into_response(),
+    }
+}
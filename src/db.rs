@@ -1,30 +1,680 @@
-use chrono::Utc;
-use duckdb::{Connection, params};
+use std::{
+    fmt::Display,
+    fs,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Condvar, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::fetch::Order;
+use anyhow::{Context, anyhow, bail};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use duckdb::{Connection, Row, params, types::Value};
+use serde::Serialize;
+use serde_json::Map;
+use tracing::info;
+use utoipa::ToSchema;
 
-pub fn init(persist_path: &str) -> anyhow::Result<()> {
+use crate::{
+    account::MyOrder,
+    anomaly::Anomaly,
+    dedup::DedupWindow,
+    export::{Pair, quote},
+    fetch::{Order, OrderType},
+    gaps::{Burst, Gap},
+    imbalance::Imbalance,
+    indicators::Candle,
+    markets::{Market, Ticker},
+    migrations::{current_version, migrate},
+    pnl::Trade,
+    status::StatusSummary,
+};
+
+/// Persist path of the in-memory database used in ephemeral mode
+pub const MEMORY_PATH: &str = ":memory:";
+
+/// How long to keep retrying to open a database another process has open,
+/// DuckDB letting a single process write to it or several ones read it
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Documentation of the columns, stored as DuckDB comments so BI tools can show it
+const COLUMN_DOCS: &[(&str, &str, &str)] = &[
+    (
+        "orders",
+        "created_at",
+        "UTC time the collector first saw the order",
+    ),
+    ("orders", "type", "Side of the order, buy or sell crypto"),
+    (
+        "orders",
+        "blockchain",
+        "Blockchain the crypto was transferred on",
+    ),
+    ("orders", "crypto_amount", "Amount of crypto bought or sold"),
+    ("orders", "crypto_symbol", "Symbol of the crypto"),
+    ("orders", "fiat_amount", "Amount of fiat paid or received"),
+    ("orders", "fiat_price", "Price of one crypto unit in fiat"),
+    ("orders", "fiat_symbol", "Symbol of the fiat currency"),
+    (
+        "orders",
+        "session_id",
+        "Cluster of close orders on the same pair and side, likely a single trade split by one user",
+    ),
+    (
+        "fetch_runs",
+        "started_at",
+        "UTC time the poll cycle started",
+    ),
+    (
+        "fetch_runs",
+        "http_status",
+        "HTTP status of the API response, if one was received",
+    ),
+    (
+        "fetch_runs",
+        "latency_ms",
+        "Time until the API response headers were received",
+    ),
+    (
+        "fetch_runs",
+        "orders_returned",
+        "Number of orders in the API window",
+    ),
+    (
+        "fetch_runs",
+        "orders_inserted",
+        "Number of new orders inserted",
+    ),
+    ("fetch_runs", "error", "Error of a failed poll cycle"),
+    (
+        "gaps",
+        "detected_at",
+        "UTC time of the fetch whose whole window was new orders",
+    ),
+    (
+        "gaps",
+        "since_previous_fetch_ms",
+        "Time elapsed since the previous successful fetch",
+    ),
+    (
+        "gaps",
+        "window_size",
+        "Number of orders returned by the API",
+    ),
+    (
+        "gaps",
+        "estimated_missed",
+        "Orders likely missed between the two fetches, from the recent order rate",
+    ),
+    (
+        "bursts",
+        "started_at",
+        "UTC time of the gap that started the burst",
+    ),
+    (
+        "bursts",
+        "ended_at",
+        "UTC time the polling went back to its normal interval",
+    ),
+    ("bursts", "fetches", "Fetches made at the burst interval"),
+    (
+        "bursts",
+        "gaps",
+        "Gaps detected during the burst, the one that started it included",
+    ),
+    ("bursts", "source", "Name of the source polled"),
+    (
+        "imbalances",
+        "computed_at",
+        "UTC time of the poll cycle that changed the imbalance",
+    ),
+    ("imbalances", "crypto_symbol", "Symbol of the crypto"),
+    ("imbalances", "fiat_symbol", "Symbol of the fiat currency"),
+    (
+        "imbalances",
+        "window_s",
+        "Length of the rolling window, in seconds",
+    ),
+    ("imbalances", "orders", "Number of orders in the window"),
+    (
+        "imbalances",
+        "buy_volume",
+        "Fiat amount of the buy orders in the window",
+    ),
+    (
+        "imbalances",
+        "sell_volume",
+        "Fiat amount of the sell orders in the window",
+    ),
+    (
+        "imbalances",
+        "buy_share",
+        "Share of the window fiat volume that was bought, from 0 to 1",
+    ),
+    (
+        "anomalies",
+        "detected_at",
+        "UTC time the collector flagged the order",
+    ),
+    (
+        "anomalies",
+        "mean_price",
+        "Mean fiat price of the previous orders of the pair",
+    ),
+    (
+        "anomalies",
+        "std_dev",
+        "Standard deviation of the fiat price of the previous orders of the pair",
+    ),
+    (
+        "anomalies",
+        "z_score",
+        "Number of standard deviations between the order price and the mean price",
+    ),
+    (
+        "reference_prices",
+        "fetched_at",
+        "UTC time the reference price was fetched",
+    ),
+    ("reference_prices", "crypto_symbol", "Symbol of the crypto"),
+    (
+        "reference_prices",
+        "fiat_symbol",
+        "Symbol of the fiat currency",
+    ),
+    (
+        "reference_prices",
+        "price",
+        "Market price of one crypto unit in fiat, from CoinGecko",
+    ),
+    (
+        "orders",
+        "base_fiat_amount",
+        "Fiat amount converted to the base currency with the rate of the order day",
+    ),
+    (
+        "orders",
+        "base_fiat_symbol",
+        "Symbol of the currency base_fiat_amount is in",
+    ),
+    (
+        "orders",
+        "annotations",
+        "JSON object of the fields added by the order script",
+    ),
+    ("fx_rates", "day", "Day the ECB rate applies to"),
+    (
+        "fx_rates",
+        "base",
+        "Currency the fiat amounts are converted to",
+    ),
+    (
+        "fx_rates",
+        "fiat_symbol",
+        "Symbol of the converted fiat currency",
+    ),
+    ("fx_rates", "rate", "Units of base per unit of fiat_symbol"),
+    ("orders", "source", "Exchange the order was collected from"),
+    ("fetch_runs", "source", "Exchange the poll cycle fetched"),
+    (
+        "gaps",
+        "source",
+        "Exchange whose window was only new orders",
+    ),
+    ("markets", "name", "Name of the Nash market, like eth_usdc"),
+    ("markets", "a_unit", "Asset traded on the market"),
+    ("markets", "b_unit", "Asset the market is quoted in"),
+    (
+        "markets",
+        "a_unit_precision",
+        "Number of decimals of the traded amounts",
+    ),
+    (
+        "markets",
+        "b_unit_precision",
+        "Number of decimals of the quoted prices",
+    ),
+    ("markets", "min_tick_size", "Smallest price increment"),
+    ("markets", "min_trade_size", "Smallest tradable amount"),
+    ("markets", "status", "Trading status of the market"),
+    (
+        "markets",
+        "updated_at",
+        "UTC time the market was last seen listed",
+    ),
+    ("tickers", "fetched_at", "UTC time the ticker was fetched"),
+    ("tickers", "market", "Name of the Nash market"),
+    ("tickers", "last_price", "Price of the latest trade"),
+    ("tickers", "best_bid", "Highest buy price in the order book"),
+    ("tickers", "best_ask", "Lowest sell price in the order book"),
+    (
+        "tickers",
+        "volume_24h",
+        "Amount traded over the last 24 hours",
+    ),
+    (
+        "tickers",
+        "price_change_24h_pct",
+        "Price change over the last 24 hours, in percent",
+    ),
+    (
+        "orders",
+        "extra",
+        "JSON object of the order fields returned by the API but not known to the collector",
+    ),
+    (
+        "orders",
+        "api_order_id",
+        "Id of the order on the API side, if it exposes one",
+    ),
+    (
+        "orders",
+        "api_created_at",
+        "When the order was completed according to the API, if it says so",
+    ),
+    (
+        "orders",
+        "fetched_at",
+        "When the API returned the order, from the Date header of its response or the local clock",
+    ),
+    (
+        "orders",
+        "hash",
+        "SHA-256 of the source, content, API id and timestamp of the order and of how many identical orders were stored before it, unique so that an order is only stored once",
+    ),
+    ("my_orders", "id", "Nash id of the order of the account"),
+    (
+        "my_orders",
+        "completed_at",
+        "UTC time Nash completed the order",
+    ),
+    ("my_orders", "type", "Side of the order, buy or sell crypto"),
+    (
+        "my_orders",
+        "blockchain",
+        "Blockchain the crypto was transferred on",
+    ),
+    (
+        "my_orders",
+        "crypto_amount",
+        "Amount of crypto bought or sold",
+    ),
+    ("my_orders", "crypto_symbol", "Symbol of the crypto"),
+    (
+        "my_orders",
+        "fiat_amount",
+        "Amount of fiat paid or received",
+    ),
+    (
+        "my_orders",
+        "fiat_price",
+        "Price of one crypto unit in fiat",
+    ),
+    ("my_orders", "fiat_symbol", "Symbol of the fiat currency"),
+    (
+        "my_orders",
+        "fetched_at",
+        "UTC time the collector stored the order",
+    ),
+    (
+        "pair_summary_snapshot",
+        "avg_price",
+        "Volume weighted average price of one crypto unit in fiat",
+    ),
+    (
+        "pair_summary_snapshot",
+        "largest_fiat_amount",
+        "Fiat amount of the largest order",
+    ),
+    (
+        "pair_summary_snapshot",
+        "first_order_at",
+        "UTC time the collector first saw an order of the pair",
+    ),
+    (
+        "pair_summary_snapshot",
+        "last_order_at",
+        "UTC time the collector last saw an order of the pair",
+    ),
+    (
+        "pair_summary_snapshot",
+        "refreshed_at",
+        "UTC time the snapshot was taken from the pair_summary view",
+    ),
+    (
+        "pair_stats",
+        "orders",
+        "Orders of the pair ever inserted, the pruned ones included",
+    ),
+    ("pair_stats", "buys", "Buy orders of the pair ever inserted"),
+    (
+        "pair_stats",
+        "sells",
+        "Sell orders of the pair ever inserted",
+    ),
+    (
+        "pair_stats",
+        "crypto_volume",
+        "Crypto amount of every order of the pair",
+    ),
+    (
+        "pair_stats",
+        "fiat_volume",
+        "Fiat amount of every order of the pair",
+    ),
+    (
+        "pair_stats",
+        "min_price",
+        "Lowest fiat price of an order of the pair",
+    ),
+    (
+        "pair_stats",
+        "max_price",
+        "Highest fiat price of an order of the pair",
+    ),
+    (
+        "pair_stats",
+        "first_seen_at",
+        "UTC time the collector first saw an order of the pair",
+    ),
+    (
+        "pair_stats",
+        "last_seen_at",
+        "UTC time the collector last saw an order of the pair",
+    ),
+    ("orders_hourly", "hour", "Start of the UTC hour"),
+    ("orders_hourly", "orders", "Orders of the pair in the hour"),
+    (
+        "orders_hourly",
+        "crypto_volume",
+        "Crypto amount of the orders of the pair in the hour",
+    ),
+    (
+        "orders_hourly",
+        "fiat_volume",
+        "Fiat amount of the orders of the pair in the hour",
+    ),
+    (
+        "orders_hourly",
+        "vwap",
+        "Fiat price weighted by the crypto amounts, null without crypto volume",
+    ),
+    ("orders_hourly", "high", "Highest fiat price of the hour"),
+    ("orders_hourly", "low", "Lowest fiat price of the hour"),
+    ("orders_daily", "day", "UTC day"),
+    ("orders_daily", "orders", "Orders of the pair in the day"),
+    (
+        "orders_daily",
+        "crypto_volume",
+        "Crypto amount of the orders of the pair in the day",
+    ),
+    (
+        "orders_daily",
+        "fiat_volume",
+        "Fiat amount of the orders of the pair in the day",
+    ),
+    (
+        "orders_daily",
+        "vwap",
+        "Fiat price weighted by the crypto amounts, null without crypto volume",
+    ),
+    ("orders_daily", "high", "Highest fiat price of the day"),
+    ("orders_daily", "low", "Lowest fiat price of the day"),
+];
+
+/// Creates the directories the database file goes in, if any is missing
+pub fn create_parent_dir(persist_path: &str) -> anyhow::Result<()> {
+    if persist_path == MEMORY_PATH {
+        return Ok(());
+    }
+
+    if let Some(parent) = Path::new(persist_path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    Ok(())
+}
+
+pub fn init(persist_path: &str, session_gap: TimeDelta) -> anyhow::Result<()> {
+    let mut conn = get_connection(persist_path)?;
+
+    for migration in migrate(&mut conn)? {
+        info!("Applied migration {} {}", migration.version, migration.name);
+    }
+
+    let untagged: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM orders WHERE session_id IS NULL;",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if untagged > 0 {
+        tag_sessions(&conn, session_gap)?;
+    }
+
+    for (table, column, doc) in COLUMN_DOCS {
+        conn.execute_batch(&format!(
+            "COMMENT ON COLUMN {table}.{column} IS {};",
+            quote(doc)
+        ))?;
+    }
+
+    drop(conn);
+    refresh_pair_summary(persist_path)?;
+
+    Ok(())
+}
+
+/// Groups every stored order into sessions, where orders on the same pair and
+/// side that are at most `session_gap` apart belong to the same session
+fn tag_sessions(conn: &Connection, session_gap: TimeDelta) -> anyhow::Result<()> {
+    conn.execute(
+        r"UPDATE orders
+        SET session_id = sessions.session_id
+        FROM (
+            SELECT
+                id,
+                SUM(starts_session) OVER (
+                    ORDER BY crypto_symbol, fiat_symbol, type, created_at, id
+                    ROWS UNBOUNDED PRECEDING
+                ) AS session_id
+            FROM (
+                SELECT
+                    rowid AS id,
+                    crypto_symbol,
+                    fiat_symbol,
+                    type,
+                    created_at,
+                    CASE
+                        WHEN created_at - LAG(created_at) OVER pair_side <= to_seconds(?) THEN 0
+                        ELSE 1
+                    END AS starts_session
+                FROM orders
+                WINDOW pair_side AS (PARTITION BY crypto_symbol, fiat_symbol, type ORDER BY created_at, rowid)
+            )
+        ) AS sessions
+        WHERE orders.rowid = sessions.id;",
+        params![session_gap.as_seconds_f64()],
+    )?;
+
+    Ok(())
+}
+
+/// Replaces the rows of `pair_summary_snapshot` with the current content of
+/// the `pair_summary` view, returning the number of pairs
+pub fn refresh_pair_summary(persist_path: &str) -> anyhow::Result<usize> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+
+    transaction.execute("DELETE FROM pair_summary_snapshot;", [])?;
+    let pairs = transaction.execute(
+        "INSERT INTO pair_summary_snapshot SELECT *, ? FROM pair_summary;",
+        params![Utc::now()],
+    )?;
+
+    transaction.commit()?;
+
+    Ok(pairs)
+}
+
+/// Recomputes the `orders_hourly` and `orders_daily` rows from the UTC day of
+/// `since`, or from the latest rolled up day, returning the number of hours
+/// rolled up
+pub fn rollup(persist_path: &str, since: Option<DateTime<Utc>>) -> anyhow::Result<usize> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+    let hours = rollup_with(&transaction, since)?;
+
+    transaction.commit()?;
+
+    Ok(hours)
+}
+
+fn rollup_with(conn: &Connection, since: Option<DateTime<Utc>>) -> anyhow::Result<usize> {
+    let latest: Option<NaiveDate> =
+        conn.query_row("SELECT MAX(day) FROM orders_daily;", [], |row| row.get(0))?;
+
+    // The latest day is always recomputed, its last rollup having missed the
+    // orders inserted since
+    let since = match (since, latest) {
+        (_, None) => conn.query_row(
+            "SELECT CAST(MIN(created_at) AS DATE) FROM orders;",
+            [],
+            |row| row.get(0),
+        )?,
+        (Some(since), Some(latest)) => Some(since.date_naive().min(latest)),
+        (None, latest) => latest,
+    };
+
+    let Some(since) = since else {
+        return Ok(0);
+    };
+
+    conn.execute("DELETE FROM orders_hourly WHERE hour >= ?;", params![since])?;
+    let hours = conn.execute(
+        r"INSERT INTO orders_hourly
+        SELECT
+            date_trunc('hour', created_at),
+            crypto_symbol,
+            fiat_symbol,
+            COUNT(*),
+            SUM(crypto_amount),
+            SUM(fiat_amount),
+            SUM(fiat_amount) / NULLIF(SUM(crypto_amount), 0),
+            MAX(fiat_price),
+            MIN(fiat_price),
+        FROM orders
+        WHERE created_at >= ?
+        GROUP BY ALL;",
+        params![since],
+    )?;
+
+    conn.execute("DELETE FROM orders_daily WHERE day >= ?;", params![since])?;
+    conn.execute(
+        r"INSERT INTO orders_daily
+        SELECT
+            CAST(hour AS DATE),
+            crypto_symbol,
+            fiat_symbol,
+            SUM(orders),
+            SUM(crypto_volume),
+            SUM(fiat_volume),
+            SUM(fiat_volume) / NULLIF(SUM(crypto_volume), 0),
+            MAX(high),
+            MIN(low),
+        FROM orders_hourly
+        WHERE hour >= ?
+        GROUP BY ALL;",
+        params![since],
+    )?;
+
+    Ok(hours)
+}
+
+/// Creates or refreshes the `information` view, listing every column with its documentation
+pub fn create_information_view(persist_path: &str) -> anyhow::Result<()> {
     let conn = get_connection(persist_path)?;
 
     conn.execute_batch(
-        r"CREATE TABLE IF NOT EXISTS orders
-            (
-                created_at TIMESTAMP NOT NULL,
-                type VARCHAR NOT NULL,
-                blockchain VARCHAR NOT NULL,
-                crypto_amount DOUBLE NOT NULL,
-                crypto_symbol VARCHAR NOT NULL,
-                fiat_amount DOUBLE NOT NULL,
-                fiat_price DOUBLE NOT NULL,
-                fiat_symbol VARCHAR NOT NULL,
-            );",
+        r"CREATE OR REPLACE VIEW information AS
+        SELECT
+            table_name,
+            column_name,
+            data_type,
+            is_nullable,
+            comment AS description
+        FROM duckdb_columns()
+        WHERE NOT internal AND table_name <> 'information'
+        ORDER BY table_name, column_index;
+        COMMENT ON VIEW information IS 'Tables and views of the database with their column documentation';",
     )?;
 
     Ok(())
 }
 
-pub fn get_latest_orders(persist_path: &str) -> anyhow::Result<Vec<Order>> {
-    let conn = get_connection(persist_path)?;
+/// Returns the `CREATE` statements of the user tables and views
+pub fn get_schema_sql(persist_path: &str) -> anyhow::Result<Vec<String>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT sql FROM duckdb_tables() WHERE NOT internal
+        UNION ALL
+        SELECT sql FROM duckdb_views() WHERE NOT internal;",
+    )?;
+
+    let statements = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(statements)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnInfo {
+    pub table: String,
+    pub column: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub description: Option<String>,
+}
+
+pub fn get_columns(persist_path: &str) -> anyhow::Result<Vec<ColumnInfo>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT table_name, column_name, data_type, is_nullable, comment
+        FROM duckdb_columns()
+        WHERE NOT internal
+        ORDER BY table_name, column_index;",
+    )?;
+
+    let columns = statement
+        .query_map([], |row| {
+            Ok(ColumnInfo {
+                table: row.get(0)?,
+                column: row.get(1)?,
+                data_type: row.get(2)?,
+                nullable: row.get(3)?,
+                description: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(columns)
+}
+
+/// Latest orders of `source` within the dedup `window`, newest first, with
+/// when they were stored
+pub fn get_latest_orders(
+    persist_path: &str,
+    source: &str,
+    window: &DedupWindow,
+) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+    let conn = get_read_only_connection(persist_path)?;
     let mut statement = conn.prepare(
         r"SELECT
         type,
@@ -33,35 +683,375 @@ pub fn get_latest_orders(persist_path: &str) -> anyhow::Result<Vec<Order>> {
         crypto_symbol,
         fiat_amount,
         fiat_price,
-        fiat_symbol
+        fiat_symbol,
+        created_at
     FROM orders
+    WHERE source = ? AND created_at >= COALESCE(?, created_at)
     ORDER BY created_at DESC
-    LIMIT 10;",
+    LIMIT ?;",
     )?;
 
     let orders = statement
-        .query_map([], |row| {
-            Ok(Order {
-                ty: row.get(0)?,
-                blockchain: row.get(1)?,
-                crypto_amount: row.get(2)?,
-                crypto_symbol: row.get(3)?,
-                fiat_amount: row.get(4)?,
-                fiat_price: row.get(5)?,
-                fiat_symbol: row.get(6)?,
-            })
+        .query_map(
+            params![
+                source,
+                window.since(Utc::now()),
+                window
+                    .count
+                    .map_or(i64::MAX, |count| i64::try_from(count).unwrap_or(i64::MAX)),
+            ],
+            |row| Ok((row.get(7)?, order_from_row(row)?)),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(orders)
+}
+
+pub fn get_orders_since(
+    persist_path: &str,
+    since: DateTime<Utc>,
+) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        type,
+        blockchain,
+        crypto_amount,
+        crypto_symbol,
+        fiat_amount,
+        fiat_price,
+        fiat_symbol,
+        created_at
+    FROM orders
+    WHERE created_at >= ?
+    ORDER BY created_at;",
+    )?;
+
+    let orders = statement
+        .query_map(params![since], |row| {
+            Ok((row.get(7)?, order_from_row(row)?))
         })?
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok(orders)
 }
 
-pub fn insert_order(order: &Order, persist_path: &str) -> anyhow::Result<()> {
+/// Conditions on the orders followed by the `tail` command
+#[derive(Debug, Clone, Default)]
+pub struct OrderFilter {
+    pub pair: Option<Pair>,
+    pub ty: Option<OrderType>,
+    pub min_fiat_amount: Option<f64>,
+}
+
+impl OrderFilter {
+    fn to_sql(&self) -> String {
+        let mut conditions = vec!["TRUE".to_string()];
+
+        if let Some(pair) = &self.pair {
+            conditions.push(format!(
+                "crypto_symbol = {} AND fiat_symbol = {}",
+                quote(&pair.crypto_symbol),
+                quote(&pair.fiat_symbol)
+            ));
+        }
+
+        if let Some(ty) = &self.ty {
+            conditions.push(format!("type = {}", quote(&ty.to_string())));
+        }
+
+        if let Some(min_fiat_amount) = self.min_fiat_amount {
+            conditions.push(format!("fiat_amount >= {min_fiat_amount}"));
+        }
+
+        conditions.join(" AND ")
+    }
+
+    /// Same conditions as [`OrderFilter::to_sql`], for the orders not stored yet
+    pub fn matches(&self, order: &Order) -> bool {
+        self.pair.as_ref().is_none_or(|pair| {
+            pair.crypto_symbol == order.crypto_symbol && pair.fiat_symbol == order.fiat_symbol
+        }) && self.ty.as_ref().is_none_or(|ty| *ty == order.ty)
+            && self
+                .min_fiat_amount
+                .is_none_or(|min_fiat_amount| order.fiat_amount >= min_fiat_amount)
+    }
+}
+
+/// Returns the latest `limit` orders inserted after `after` matching `filter`, oldest first
+#[cfg(feature = "grpc")]
+pub fn get_orders_after(
+    persist_path: &str,
+    after: DateTime<Utc>,
+    filter: &OrderFilter,
+    limit: usize,
+) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(&format!(
+        r"SELECT * FROM (
+            SELECT
+                type,
+                blockchain,
+                crypto_amount,
+                crypto_symbol,
+                fiat_amount,
+                fiat_price,
+                fiat_symbol,
+                created_at
+            FROM orders
+            WHERE created_at > ? AND {}
+            ORDER BY created_at DESC
+            LIMIT ?
+        )
+        ORDER BY created_at;",
+        filter.to_sql()
+    ))?;
+
+    let orders = statement
+        .query_map(
+            params![after, i64::try_from(limit).unwrap_or(i64::MAX)],
+            |row| Ok((row.get(7)?, order_from_row(row)?)),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(orders)
+}
+
+/// Position of an order in the pages of [`get_orders_page`], unique even when
+/// orders were created at the same instant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderCursor {
+    pub created_at: DateTime<Utc>,
+    pub hash: String,
+}
+
+impl Display for OrderCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.created_at.timestamp_micros(), self.hash)
+    }
+}
+
+impl FromStr for OrderCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (created_at, hash) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid cursor {s}"))?;
+        let created_at = created_at
+            .parse()
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .ok_or_else(|| anyhow!("Invalid cursor {s}"))?;
+
+        Ok(OrderCursor {
+            created_at,
+            hash: hash.to_string(),
+        })
+    }
+}
+
+/// Returns `limit` orders matching `filter` in creation order, or the reverse
+/// when `descending`, starting after the `after` cursor, each with its own cursor
+pub fn get_orders_page(
+    persist_path: &str,
+    filter: &OrderFilter,
+    after: Option<&OrderCursor>,
+    limit: usize,
+    descending: bool,
+) -> anyhow::Result<Vec<(OrderCursor, Order)>> {
+    let (after_op, direction) = if descending {
+        ("<", "DESC")
+    } else {
+        (">", "ASC")
+    };
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(&format!(
+        r"SELECT
+            type,
+            blockchain,
+            crypto_amount,
+            crypto_symbol,
+            fiat_amount,
+            fiat_price,
+            fiat_symbol,
+            created_at,
+            hash
+        FROM orders
+        WHERE {}
+            AND (
+                ? IS NULL
+                OR created_at {after_op} ?
+                OR (created_at = ? AND hash {after_op} ?)
+            )
+        ORDER BY created_at {direction}, hash {direction}
+        LIMIT ?;",
+        filter.to_sql()
+    ))?;
+
+    let after_at = after.map(|cursor| cursor.created_at);
+    let orders = statement
+        .query_map(
+            params![
+                after_at,
+                after_at,
+                after_at,
+                after.map(|cursor| &cursor.hash),
+                i64::try_from(limit).unwrap_or(i64::MAX),
+            ],
+            |row| {
+                let cursor = OrderCursor {
+                    created_at: row.get(7)?,
+                    hash: row.get(8)?,
+                };
+
+                Ok((cursor, order_from_row(row)?))
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(orders)
+}
+
+/// An order to insert, with what it was received along with
+#[derive(Debug, Clone)]
+pub struct NewOrder {
+    pub order: Order,
+    pub source: &'static str,
+    pub annotations: Option<String>,
+    /// When the collector received the order
+    pub created_at: DateTime<Utc>,
+    pub fetched_at: DateTime<Utc>,
+    /// How many orders the API returned along with it, an identical order
+    /// stored among as many latest ones being this order fetched again
+    pub window_size: usize,
+}
+
+/// Inserts `order` unless it is already stored, fetched again after a restart
+/// or by another collector, returns whether it was inserted
+pub fn insert_order(
+    order: &NewOrder,
+    persist_path: &str,
+    session_gap: TimeDelta,
+) -> anyhow::Result<bool> {
     let conn = get_connection(persist_path)?;
-    let now = Utc::now();
 
-    conn.execute(
-        "INSERT INTO orders 
+    insert_order_with(&conn, order, session_gap)
+}
+
+/// Inserts `orders` in a single transaction, in order for the sessions to be
+/// joined as if inserted one by one, returns how many were inserted
+pub fn insert_orders(
+    orders: &[NewOrder],
+    persist_path: &str,
+    session_gap: TimeDelta,
+) -> anyhow::Result<usize> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+    let mut inserted = 0;
+
+    for order in orders {
+        if insert_order_with(&transaction, order, session_gap)? {
+            inserted += 1;
+        }
+    }
+
+    transaction.commit()?;
+
+    Ok(inserted)
+}
+
+fn insert_order_with(
+    conn: &Connection,
+    new_order: &NewOrder,
+    session_gap: TimeDelta,
+) -> anyhow::Result<bool> {
+    let order = &new_order.order;
+    let extra = (!order.extra.is_empty())
+        .then(|| serde_json::to_string(&order.extra))
+        .transpose()?;
+
+    // Joins the session of the latest order on the same source, pair and side if
+    // it is recent enough, or starts a new one
+    let session_id: i64 = conn.query_row(
+        r"SELECT COALESCE(
+            (
+                SELECT session_id
+                FROM orders
+                WHERE source = ?
+                    AND crypto_symbol = ?
+                    AND fiat_symbol = ?
+                    AND type = ?
+                    AND created_at >= ?
+                ORDER BY created_at DESC
+                LIMIT 1
+            ),
+            (SELECT COALESCE(MAX(session_id), 0) + 1 FROM orders)
+        );",
+        params![
+            new_order.source,
+            order.crypto_symbol,
+            order.fiat_symbol,
+            order.ty.to_string(),
+            new_order.created_at - session_gap,
+        ],
+        |row| row.get(0),
+    )?;
+
+    // Without an id or a timestamp from the API, identical orders are told
+    // apart by how many were stored before, the one within the latest fetch
+    // window being this order fetched again rather than a repeated one
+    let occurrence: i64 = if order.api_order_id.is_some() || order.api_created_at.is_some() {
+        0
+    } else {
+        conn.query_row(
+            r"WITH copies AS (
+                SELECT created_at
+                FROM orders
+                WHERE source = ?
+                    AND type = ?
+                    AND blockchain = ?
+                    AND crypto_amount = ?
+                    AND crypto_symbol = ?
+                    AND fiat_amount = ?
+                    AND fiat_price = ?
+                    AND fiat_symbol = ?
+                    AND api_order_id IS NULL
+                    AND api_created_at IS NULL
+            ),
+            latest AS (
+                SELECT created_at
+                FROM orders
+                WHERE source = ? AND created_at < ?
+                ORDER BY created_at DESC
+                LIMIT ?
+            )
+            SELECT COUNT(*) - CAST(
+                EXISTS (SELECT 1 FROM copies WHERE created_at >= (SELECT MIN(created_at) FROM latest))
+                AS BIGINT
+            )
+            FROM copies;",
+            params![
+                new_order.source,
+                order.ty.to_string(),
+                order.blockchain,
+                order.crypto_amount,
+                order.crypto_symbol,
+                order.fiat_amount,
+                order.fiat_price,
+                order.fiat_symbol,
+                new_order.source,
+                new_order.created_at,
+                i64::try_from(new_order.window_size).unwrap_or(i64::MAX),
+            ],
+            |row| row.get(0),
+        )?
+    };
+
+    // The hash is computed by the database, as the migrations did, so that the
+    // stored orders and the new ones are hashed alike
+    let inserted = conn.execute(
+        r"INSERT OR IGNORE INTO orders
         (
             created_at,
             type,
@@ -70,11 +1060,277 @@ pub fn insert_order(order: &Order, persist_path: &str) -> anyhow::Result<()> {
             crypto_symbol,
             fiat_amount,
             fiat_price,
-            fiat_symbol
-        ) 
+            fiat_symbol,
+            session_id,
+            annotations,
+            source,
+            extra,
+            api_order_id,
+            api_created_at,
+            fetched_at,
+            hash
+        )
+        SELECT
+            * EXCLUDE (occurrence),
+            sha256(concat_ws(
+                '|',
+                source,
+                type,
+                blockchain,
+                crypto_amount,
+                crypto_symbol,
+                fiat_amount,
+                fiat_price,
+                fiat_symbol,
+                COALESCE(api_order_id, ''),
+                COALESCE(CAST(CAST(api_created_at AS TIMESTAMP) AS VARCHAR), ''),
+                occurrence
+            ))
+        FROM (VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)) AS o(
+            created_at,
+            type,
+            blockchain,
+            crypto_amount,
+            crypto_symbol,
+            fiat_amount,
+            fiat_price,
+            fiat_symbol,
+            session_id,
+            annotations,
+            source,
+            extra,
+            api_order_id,
+            api_created_at,
+            fetched_at,
+            occurrence
+        );",
+        params![
+            new_order.created_at,
+            order.ty.to_string(),
+            order.blockchain,
+            order.crypto_amount,
+            order.crypto_symbol,
+            order.fiat_amount,
+            order.fiat_price,
+            order.fiat_symbol,
+            session_id,
+            new_order.annotations,
+            new_order.source,
+            extra,
+            order.api_order_id,
+            order.api_created_at,
+            new_order.fetched_at,
+            occurrence,
+        ],
+    )?;
+
+    if inserted > 0 {
+        add_to_pair_stats(
+            conn,
+            r"SELECT
+                CAST(? AS VARCHAR) AS crypto_symbol,
+                CAST(? AS VARCHAR) AS fiat_symbol,
+                CAST(? AS VARCHAR) AS type,
+                CAST(? AS DOUBLE) AS crypto_amount,
+                CAST(? AS DOUBLE) AS fiat_amount,
+                CAST(? AS DOUBLE) AS fiat_price,
+                CAST(? AS TIMESTAMP) AS created_at",
+            params![
+                order.crypto_symbol,
+                order.fiat_symbol,
+                order.ty.to_string(),
+                order.crypto_amount,
+                order.fiat_amount,
+                order.fiat_price,
+                new_order.created_at,
+            ],
+        )?;
+    }
+
+    Ok(inserted > 0)
+}
+
+/// Adds the rows of `orders`, a query with the columns of the orders table,
+/// to the statistics of their pair
+fn add_to_pair_stats(
+    conn: &Connection,
+    orders: &str,
+    params: impl duckdb::Params,
+) -> anyhow::Result<()> {
+    conn.execute(
+        &format!(
+            r"INSERT INTO pair_stats
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                COUNT(*),
+                COUNT(*) FILTER (WHERE type = 'buy'),
+                COUNT(*) FILTER (WHERE type = 'sell'),
+                SUM(crypto_amount),
+                SUM(fiat_amount),
+                MIN(fiat_price),
+                MAX(fiat_price),
+                MIN(created_at),
+                MAX(created_at),
+            FROM ({orders})
+            GROUP BY ALL
+            ON CONFLICT (crypto_symbol, fiat_symbol) DO UPDATE SET
+                orders = orders + EXCLUDED.orders,
+                buys = buys + EXCLUDED.buys,
+                sells = sells + EXCLUDED.sells,
+                crypto_volume = crypto_volume + EXCLUDED.crypto_volume,
+                fiat_volume = fiat_volume + EXCLUDED.fiat_volume,
+                min_price = LEAST(min_price, EXCLUDED.min_price),
+                max_price = GREATEST(max_price, EXCLUDED.max_price),
+                first_seen_at = LEAST(first_seen_at, EXCLUDED.first_seen_at),
+                last_seen_at = GREATEST(last_seen_at, EXCLUDED.last_seen_at);"
+        ),
+        params,
+    )?;
+
+    Ok(())
+}
+
+/// Outcome of a single poll cycle
+#[derive(Debug, Clone, Default)]
+pub struct FetchRun {
+    pub source: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub http_status: Option<u16>,
+    pub latency: Option<Duration>,
+    pub orders_returned: Option<usize>,
+    pub orders_inserted: usize,
+    pub error: Option<String>,
+}
+
+pub fn insert_fetch_run(run: &FetchRun, persist_path: &str) -> anyhow::Result<()> {
+    let conn = get_connection(persist_path)?;
+
+    conn.execute(
+        "INSERT INTO fetch_runs
+        (
+            started_at,
+            http_status,
+            latency_ms,
+            orders_returned,
+            orders_inserted,
+            error,
+            source
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![
+            run.started_at,
+            run.http_status,
+            run.latency.map(|latency| latency.as_millis() as i64),
+            run.orders_returned.map(|orders| orders as i64),
+            run.orders_inserted as i64,
+            run.error,
+            run.source,
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn insert_gap(gap: &Gap, source: &str, persist_path: &str) -> anyhow::Result<()> {
+    let conn = get_connection(persist_path)?;
+
+    conn.execute(
+        "INSERT INTO gaps
+        (
+            detected_at,
+            since_previous_fetch_ms,
+            window_size,
+            estimated_missed,
+            source
+        )
+        VALUES (?, ?, ?, ?, ?)",
+        params![
+            Utc::now(),
+            gap.since_previous_fetch.as_millis() as i64,
+            gap.window_size as i64,
+            gap.estimated_missed as i64,
+            source,
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn insert_burst(burst: &Burst, source: &str, persist_path: &str) -> anyhow::Result<()> {
+    let conn = get_connection(persist_path)?;
+
+    conn.execute(
+        "INSERT INTO bursts (started_at, ended_at, fetches, gaps, source) VALUES (?, ?, ?, ?, ?)",
+        params![
+            burst.started_at,
+            burst.ended_at,
+            burst.fetches as i64,
+            burst.gaps as i64,
+            source,
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn insert_imbalance(
+    imbalance: &Imbalance,
+    window: Duration,
+    persist_path: &str,
+) -> anyhow::Result<()> {
+    let conn = get_connection(persist_path)?;
+
+    conn.execute(
+        "INSERT INTO imbalances
+        (
+            computed_at,
+            crypto_symbol,
+            fiat_symbol,
+            window_s,
+            orders,
+            buy_volume,
+            sell_volume,
+            buy_share
+        )
         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
         params![
-            now,
+            Utc::now(),
+            imbalance.pair.crypto_symbol,
+            imbalance.pair.fiat_symbol,
+            window.as_secs() as i64,
+            imbalance.orders as i64,
+            imbalance.buy_volume,
+            imbalance.sell_volume,
+            imbalance.buy_share(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn insert_anomaly(anomaly: &Anomaly, persist_path: &str) -> anyhow::Result<()> {
+    let conn = get_connection(persist_path)?;
+    let order = &anomaly.order;
+
+    conn.execute(
+        "INSERT INTO anomalies
+        (
+            detected_at,
+            type,
+            blockchain,
+            crypto_amount,
+            crypto_symbol,
+            fiat_amount,
+            fiat_price,
+            fiat_symbol,
+            mean_price,
+            std_dev,
+            z_score
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            Utc::now(),
             order.ty.to_string(),
             order.blockchain,
             order.crypto_amount,
@@ -82,14 +1338,1975 @@ pub fn insert_order(order: &Order, persist_path: &str) -> anyhow::Result<()> {
             order.fiat_amount,
             order.fiat_price,
             order.fiat_symbol,
+            anomaly.mean_price,
+            anomaly.std_dev,
+            anomaly.z_score,
         ],
     )?;
 
     Ok(())
 }
 
-pub fn get_connection(persist_path: &str) -> anyhow::Result<Connection> {
-    let connection = Connection::open(persist_path)?;
+pub fn insert_reference_prices(prices: &[(Pair, f64)], persist_path: &str) -> anyhow::Result<()> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+    let now = Utc::now();
+
+    for (pair, price) in prices {
+        transaction.execute(
+            "INSERT INTO reference_prices (fetched_at, crypto_symbol, fiat_symbol, price)
+            VALUES (?, ?, ?, ?)",
+            params![now, pair.crypto_symbol, pair.fiat_symbol, price],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Inserts or refreshes the listed markets
+pub fn upsert_markets(markets: &[Market], persist_path: &str) -> anyhow::Result<()> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+    let now = Utc::now();
+
+    for market in markets {
+        transaction.execute(
+            "INSERT OR REPLACE INTO markets
+            (
+                name,
+                a_unit,
+                b_unit,
+                a_unit_precision,
+                b_unit_precision,
+                min_tick_size,
+                min_trade_size,
+                status,
+                updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                market.name,
+                market.a_unit,
+                market.b_unit,
+                market.a_unit_precision,
+                market.b_unit_precision,
+                market.min_tick_size,
+                market.min_trade_size,
+                market.status,
+                now,
+            ],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+pub fn insert_tickers(tickers: &[Ticker], persist_path: &str) -> anyhow::Result<()> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+    let now = Utc::now();
+
+    for ticker in tickers {
+        transaction.execute(
+            "INSERT INTO tickers
+            (
+                fetched_at,
+                market,
+                last_price,
+                best_bid,
+                best_ask,
+                volume_24h,
+                price_change_24h_pct
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                now,
+                ticker.market_name,
+                ticker.last_price.as_ref().map(|price| price.amount),
+                ticker.best_bid_price.as_ref().map(|price| price.amount),
+                ticker.best_ask_price.as_ref().map(|price| price.amount),
+                ticker.volume_24h.as_ref().map(|volume| volume.amount),
+                ticker
+                    .price_change_24h_pct
+                    .as_deref()
+                    .and_then(|change| change.parse::<f64>().ok()),
+            ],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Inserts the orders of the account not stored yet, returning their number
+pub fn insert_my_orders(orders: &[MyOrder], persist_path: &str) -> anyhow::Result<usize> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+    let now = Utc::now();
+    let mut inserted = 0;
+
+    for my_order in orders {
+        let order = &my_order.order;
+
+        inserted += transaction.execute(
+            "INSERT OR IGNORE INTO my_orders
+            (
+                id,
+                completed_at,
+                type,
+                blockchain,
+                crypto_amount,
+                crypto_symbol,
+                fiat_amount,
+                fiat_price,
+                fiat_symbol,
+                fetched_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                my_order.id,
+                my_order.completed_at,
+                order.ty.to_string(),
+                order.blockchain,
+                order.crypto_amount,
+                order.crypto_symbol,
+                order.fiat_amount,
+                order.fiat_price,
+                order.fiat_symbol,
+                now,
+            ],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(inserted)
+}
+
+/// Returns the orders of the account sorted by time, from `my_orders` or from
+/// a CSV file with the same column names when `csv` is set
+pub fn get_my_trades(persist_path: &str, csv: Option<&str>) -> anyhow::Result<Vec<Trade>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let table = match csv {
+        Some(csv) => format!(
+            r"read_csv({}, header = true, columns = {{
+                'completed_at': 'TIMESTAMP',
+                'type': 'VARCHAR',
+                'crypto_symbol': 'VARCHAR',
+                'crypto_amount': 'DOUBLE',
+                'fiat_symbol': 'VARCHAR',
+                'fiat_amount': 'DOUBLE'
+            }})",
+            quote(csv)
+        ),
+        None => "my_orders".to_string(),
+    };
+    let mut statement = conn.prepare(&format!(
+        r"SELECT
+            completed_at,
+            lower(type),
+            crypto_symbol,
+            fiat_symbol,
+            crypto_amount,
+            fiat_amount
+        FROM {table}
+        ORDER BY completed_at;"
+    ))?;
+
+    let trades = statement
+        .query_map([], |row| {
+            Ok(Trade {
+                at: row.get(0)?,
+                ty: row.get(1)?,
+                crypto_symbol: row.get(2)?,
+                fiat_symbol: row.get(3)?,
+                crypto_amount: row.get(4)?,
+                fiat_amount: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(trades)
+}
+
+/// Returns the first day of the orders not converted to `base` yet, and their
+/// fiat currencies other than `base`
+pub fn get_unnormalized_fiats(
+    persist_path: &str,
+    base: &str,
+) -> anyhow::Result<(Option<NaiveDate>, Vec<String>)> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    let since = conn.query_row(
+        r"SELECT MIN(CAST(created_at AS DATE))
+        FROM orders
+        WHERE base_fiat_symbol IS DISTINCT FROM ? OR base_fiat_amount IS NULL;",
+        params![base],
+        |row| row.get(0),
+    )?;
+
+    let fiats = conn
+        .prepare(
+            r"SELECT DISTINCT fiat_symbol
+            FROM orders
+            WHERE fiat_symbol != ?
+            ORDER BY fiat_symbol;",
+        )?
+        .query_map(params![base], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((since, fiats))
+}
+
+pub fn insert_fx_rates(
+    persist_path: &str,
+    base: &str,
+    rates: &[(NaiveDate, String, f64)],
+) -> anyhow::Result<()> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+
+    for (day, fiat_symbol, rate) in rates {
+        transaction.execute(
+            "INSERT OR REPLACE INTO fx_rates (day, base, fiat_symbol, rate) VALUES (?, ?, ?, ?)",
+            params![day, base, fiat_symbol, rate],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Converts the fiat amount of the orders not converted to `base` yet, with
+/// the latest rate known on their day, returning the number of converted orders
+pub fn normalize_orders(persist_path: &str, base: &str) -> anyhow::Result<usize> {
+    let conn = get_connection(persist_path)?;
+
+    let normalized = conn.execute(
+        r"UPDATE orders
+        SET
+            base_fiat_amount = CASE
+                WHEN fiat_symbol = $1 THEN fiat_amount
+                ELSE fiat_amount * (
+                    SELECT rate
+                    FROM fx_rates
+                    WHERE fx_rates.base = $1
+                        AND fx_rates.fiat_symbol = orders.fiat_symbol
+                        AND fx_rates.day <= CAST(orders.created_at AS DATE)
+                    ORDER BY fx_rates.day DESC
+                    LIMIT 1
+                )
+            END,
+            base_fiat_symbol = $1
+        WHERE base_fiat_symbol IS DISTINCT FROM $1 OR base_fiat_amount IS NULL;",
+        params![base],
+    )?;
+
+    Ok(normalized)
+}
+
+/// Premium of the order prices of a pair to the reference prices over a period
+#[derive(Debug, Clone, Serialize)]
+pub struct PremiumSummary {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub orders: u64,
+    pub latest: f64,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Matches every order with the latest reference price fetched at most an hour before it
+pub fn get_premium_summary(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<PremiumSummary>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        o.crypto_symbol,
+        o.fiat_symbol,
+        COUNT(*),
+        arg_max(o.fiat_price / r.price - 1, o.created_at),
+        AVG(o.fiat_price / r.price - 1),
+        MIN(o.fiat_price / r.price - 1),
+        MAX(o.fiat_price / r.price - 1)
+    FROM orders o
+    ASOF JOIN reference_prices r
+        ON o.crypto_symbol = r.crypto_symbol
+        AND o.fiat_symbol = r.fiat_symbol
+        AND o.created_at >= r.fetched_at
+    WHERE o.created_at >= ? AND o.created_at < ?
+        AND r.fetched_at >= o.created_at - INTERVAL 1 HOUR
+    GROUP BY o.crypto_symbol, o.fiat_symbol
+    ORDER BY o.crypto_symbol, o.fiat_symbol;",
+    )?;
+
+    let summaries = statement
+        .query_map(params![from, to], |row| {
+            Ok(PremiumSummary {
+                crypto_symbol: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                orders: row.get(2)?,
+                latest: row.get(3)?,
+                avg: row.get(4)?,
+                min: row.get(5)?,
+                max: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(summaries)
+}
+
+/// Buy share of a pair over a period, from the stored imbalances
+#[derive(Debug, Clone, Serialize)]
+pub struct ImbalanceSummary {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub latest: f64,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    pub latest_at: DateTime<Utc>,
+}
+
+pub fn get_imbalance_summary(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<ImbalanceSummary>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        crypto_symbol,
+        fiat_symbol,
+        arg_max(buy_share, computed_at),
+        AVG(buy_share),
+        MIN(buy_share),
+        MAX(buy_share),
+        MAX(computed_at)
+    FROM imbalances
+    WHERE computed_at >= ? AND computed_at < ?
+    GROUP BY crypto_symbol, fiat_symbol
+    ORDER BY crypto_symbol, fiat_symbol;",
+    )?;
+
+    let summaries = statement
+        .query_map(params![from, to], |row| {
+            Ok(ImbalanceSummary {
+                crypto_symbol: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                latest: row.get(2)?,
+                avg: row.get(3)?,
+                min: row.get(4)?,
+                max: row.get(5)?,
+                latest_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(summaries)
+}
+
+/// Realized volatility of the order prices of a pair over a period and over
+/// its rolling windows
+#[derive(Debug, Clone, Serialize)]
+pub struct VolatilitySummary {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    /// Number of log returns between consecutive order prices
+    pub returns: u64,
+    /// Square root of the sum of the squared log returns over the period
+    pub realized: f64,
+    /// Realized volatility of the latest rolling window with returns
+    pub latest: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+/// Returns the realized volatility of every pair, or of `pair` only, the
+/// rolling windows being aligned on multiples of `rolling`
+pub fn get_volatility_summary(
+    persist_path: &str,
+    pair: Option<&Pair>,
+    rolling: TimeDelta,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<VolatilitySummary>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let pair_condition = pair
+        .map(|pair| {
+            format!(
+                "AND crypto_symbol = {} AND fiat_symbol = {}",
+                quote(&pair.crypto_symbol),
+                quote(&pair.fiat_symbol)
+            )
+        })
+        .unwrap_or_default();
+
+    let mut statement = conn.prepare(&format!(
+        r"WITH returns AS (
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                created_at,
+                ln(fiat_price / LAG(fiat_price) OVER (
+                    PARTITION BY crypto_symbol, fiat_symbol ORDER BY created_at
+                )) AS log_return
+            FROM orders
+            WHERE created_at >= ? AND created_at < ? AND fiat_price > 0 {pair_condition}
+        ),
+        windows AS (
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                time_bucket(to_seconds(?), created_at) AS start,
+                sqrt(SUM(log_return * log_return)) AS realized
+            FROM returns
+            WHERE log_return IS NOT NULL
+            GROUP BY ALL
+        )
+        SELECT
+            r.crypto_symbol,
+            r.fiat_symbol,
+            r.returns,
+            r.realized,
+            w.latest,
+            w.avg,
+            w.max
+        FROM (
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                CAST(COUNT(*) AS UBIGINT) AS returns,
+                sqrt(SUM(log_return * log_return)) AS realized
+            FROM returns
+            WHERE log_return IS NOT NULL
+            GROUP BY ALL
+        ) r
+        JOIN (
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                arg_max(realized, start) AS latest,
+                AVG(realized) AS avg,
+                MAX(realized) AS max
+            FROM windows
+            GROUP BY ALL
+        ) w USING (crypto_symbol, fiat_symbol)
+        ORDER BY r.realized DESC;"
+    ))?;
+
+    let summaries = statement
+        .query_map(params![from, to, rolling.num_seconds()], |row| {
+            Ok(VolatilitySummary {
+                crypto_symbol: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                returns: row.get(2)?,
+                realized: row.get(3)?,
+                latest: row.get(4)?,
+                avg: row.get(5)?,
+                max: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(summaries)
+}
+
+/// Activity of a single pair over a period
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct PairSummary {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub orders: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub crypto_volume: f64,
+    pub fiat_volume: f64,
+    pub avg_price: f64,
+    pub largest_fiat_amount: f64,
+    /// Fiat volume in the base currency, when the orders were normalized
+    pub base_fiat_volume: Option<f64>,
+}
+
+pub fn get_summary(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<PairSummary>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        crypto_symbol,
+        fiat_symbol,
+        COUNT(*),
+        COUNT(*) FILTER (WHERE type = 'buy'),
+        COUNT(*) FILTER (WHERE type = 'sell'),
+        SUM(crypto_amount),
+        SUM(fiat_amount),
+        SUM(fiat_amount) / SUM(crypto_amount),
+        MAX(fiat_amount),
+        SUM(base_fiat_amount)
+    FROM orders
+    WHERE created_at >= ? AND created_at < ?
+    GROUP BY crypto_symbol, fiat_symbol
+    ORDER BY SUM(fiat_amount) DESC;",
+    )?;
+
+    let summaries = statement
+        .query_map(params![from, to], |row| {
+            Ok(PairSummary {
+                crypto_symbol: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                orders: row.get(2)?,
+                buys: row.get(3)?,
+                sells: row.get(4)?,
+                crypto_volume: row.get(5)?,
+                fiat_volume: row.get(6)?,
+                avg_price: row.get(7)?,
+                largest_fiat_amount: row.get(8)?,
+                base_fiat_volume: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(summaries)
+}
+
+/// Returns the orders of a pair created between `from` and `to`, oldest first
+pub fn get_pair_orders(
+    persist_path: &str,
+    pair: &Pair,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        type,
+        blockchain,
+        crypto_amount,
+        crypto_symbol,
+        fiat_amount,
+        fiat_price,
+        fiat_symbol,
+        created_at
+    FROM orders
+    WHERE crypto_symbol = ? AND fiat_symbol = ? AND created_at >= ? AND created_at < ?
+    ORDER BY created_at;",
+    )?;
+
+    let orders = statement
+        .query_map(
+            params![pair.crypto_symbol, pair.fiat_symbol, from, to],
+            |row| Ok((row.get(7)?, order_from_row(row)?)),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(orders)
+}
+
+/// Returns the candles of a pair between `from` and `to`, the timeframes without
+/// orders since the first one being flat at the previous close, for the
+/// indicators to count them
+pub fn get_candles(
+    persist_path: &str,
+    pair: &Pair,
+    timeframe: TimeDelta,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<Candle>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"WITH candles AS (
+            SELECT
+                time_bucket(to_seconds(?), created_at) AS start,
+                arg_min(fiat_price, created_at) AS open,
+                MAX(fiat_price) AS high,
+                MIN(fiat_price) AS low,
+                arg_max(fiat_price, created_at) AS close,
+                SUM(fiat_amount) AS volume
+            FROM orders
+            WHERE crypto_symbol = ? AND fiat_symbol = ? AND created_at >= ? AND created_at < ?
+            GROUP BY start
+        ),
+        buckets AS (
+            SELECT UNNEST(generate_series(
+                MIN(start),
+                time_bucket(to_seconds(?), CAST(? AS TIMESTAMP) - INTERVAL 1 MICROSECOND),
+                to_seconds(?)
+            )) AS start
+            FROM candles
+        ),
+        filled AS (
+            SELECT
+                start,
+                open,
+                high,
+                low,
+                close,
+                volume,
+                last_value(close IGNORE NULLS) OVER (ORDER BY start) AS previous_close
+            FROM buckets
+            LEFT JOIN candles USING (start)
+        )
+        SELECT
+            start,
+            COALESCE(open, previous_close),
+            COALESCE(high, previous_close),
+            COALESCE(low, previous_close),
+            COALESCE(close, previous_close),
+            COALESCE(volume, 0)
+        FROM filled
+        ORDER BY start;",
+    )?;
+
+    let candles = statement
+        .query_map(
+            params![
+                timeframe.num_seconds(),
+                pair.crypto_symbol,
+                pair.fiat_symbol,
+                from,
+                to,
+                timeframe.num_seconds(),
+                to,
+                timeframe.num_seconds(),
+            ],
+            |row| {
+                Ok(Candle {
+                    start: row.get(0)?,
+                    open: row.get(1)?,
+                    high: row.get(2)?,
+                    low: row.get(3)?,
+                    close: row.get(4)?,
+                    volume: row.get(5)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(candles)
+}
+
+/// Volume executed by the orders of a pair whose price is within `[lower, upper)`,
+/// the highest level including its upper bound
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PriceLevel {
+    pub lower: f64,
+    pub upper: f64,
+    pub orders: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub crypto_volume: f64,
+    pub fiat_volume: f64,
+}
+
+/// Volume of a pair over a period by price level, lowest level first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VolumeProfile {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub orders: u64,
+    pub fiat_volume: f64,
+    /// Middle of the level with the most fiat volume, if there were orders
+    pub point_of_control: Option<f64>,
+    /// Only the levels with orders
+    pub levels: Vec<PriceLevel>,
+}
+
+/// Splits the price range of the orders of `pair` between `from` and `to`
+/// in `levels` levels of the same width
+pub fn get_volume_profile(
+    persist_path: &str,
+    pair: &Pair,
+    levels: usize,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<VolumeProfile> {
+    let conn = get_read_only_connection(persist_path)?;
+    let levels = levels.max(1);
+
+    let (low, high) = conn.query_row(
+        r"SELECT MIN(fiat_price), MAX(fiat_price)
+        FROM orders
+        WHERE crypto_symbol = ? AND fiat_symbol = ? AND created_at >= ? AND created_at < ?;",
+        params![pair.crypto_symbol, pair.fiat_symbol, from, to],
+        |row| Ok((row.get::<_, Option<f64>>(0)?, row.get::<_, Option<f64>>(1)?)),
+    )?;
+
+    let mut profile = VolumeProfile {
+        crypto_symbol: pair.crypto_symbol.clone(),
+        fiat_symbol: pair.fiat_symbol.clone(),
+        orders: 0,
+        fiat_volume: 0.0,
+        point_of_control: None,
+        levels: Vec::new(),
+    };
+
+    let (Some(low), Some(high)) = (low, high) else {
+        return Ok(profile);
+    };
+
+    // A single price puts every order in the first level
+    let width = ((high - low) / levels as f64).max(f64::EPSILON);
+
+    let mut statement = conn.prepare(
+        r"SELECT
+        LEAST(CAST(floor((fiat_price - ?) / ?) AS BIGINT), ?) AS level,
+        CAST(COUNT(*) AS UBIGINT),
+        CAST(COUNT(*) FILTER (WHERE type = 'buy') AS UBIGINT),
+        CAST(COUNT(*) FILTER (WHERE type = 'sell') AS UBIGINT),
+        SUM(crypto_amount),
+        SUM(fiat_amount)
+    FROM orders
+    WHERE crypto_symbol = ? AND fiat_symbol = ? AND created_at >= ? AND created_at < ?
+    GROUP BY level
+    ORDER BY level;",
+    )?;
+
+    profile.levels = statement
+        .query_map(
+            params![
+                low,
+                width,
+                levels as i64 - 1,
+                pair.crypto_symbol,
+                pair.fiat_symbol,
+                from,
+                to
+            ],
+            |row| {
+                let level = row.get::<_, i64>(0)? as f64;
+
+                Ok(PriceLevel {
+                    lower: low + width * level,
+                    upper: low + width * (level + 1.0),
+                    orders: row.get(1)?,
+                    buys: row.get(2)?,
+                    sells: row.get(3)?,
+                    crypto_volume: row.get(4)?,
+                    fiat_volume: row.get(5)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    profile.orders = profile.levels.iter().map(|level| level.orders).sum();
+    profile.fiat_volume = profile.levels.iter().map(|level| level.fiat_volume).sum();
+    profile.point_of_control = profile
+        .levels
+        .iter()
+        .max_by(|a, b| a.fiat_volume.total_cmp(&b.fiat_volume))
+        .map(|level| (level.lower + level.upper) / 2.0);
+
+    Ok(profile)
+}
+
+/// Orders of an hour, the hour being the UTC one
+#[derive(Debug, Clone)]
+pub struct HourlyActivity {
+    pub hour: DateTime<Utc>,
+    pub orders: u64,
+    pub fiat_volume: f64,
+}
+
+/// Returns the activity of every hour with orders of every pair, or of `pair`
+/// only, oldest first
+pub fn get_hourly_activity(
+    persist_path: &str,
+    pair: Option<&Pair>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<HourlyActivity>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let pair_condition = pair
+        .map(|pair| {
+            format!(
+                "AND crypto_symbol = {} AND fiat_symbol = {}",
+                quote(&pair.crypto_symbol),
+                quote(&pair.fiat_symbol)
+            )
+        })
+        .unwrap_or_default();
+
+    let mut statement = conn.prepare(&format!(
+        r"SELECT
+            date_trunc('hour', created_at) AS hour,
+            CAST(COUNT(*) AS UBIGINT),
+            SUM(fiat_amount)
+        FROM orders
+        WHERE created_at >= ? AND created_at < ? {pair_condition}
+        GROUP BY hour
+        ORDER BY hour;"
+    ))?;
+
+    let activity = statement
+        .query_map(params![from, to], |row| {
+            Ok(HourlyActivity {
+                hour: row.get(0)?,
+                orders: row.get(1)?,
+                fiat_volume: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(activity)
+}
+
+/// Returns the orders with the largest fiat amounts created between `from` and `to`
+pub fn get_largest_orders(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: usize,
+) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        type,
+        blockchain,
+        crypto_amount,
+        crypto_symbol,
+        fiat_amount,
+        fiat_price,
+        fiat_symbol,
+        created_at
+    FROM orders
+    WHERE created_at >= ? AND created_at < ?
+    ORDER BY fiat_amount DESC
+    LIMIT ?;",
+    )?;
+
+    let orders = statement
+        .query_map(params![from, to, limit as i64], |row| {
+            Ok((row.get(7)?, order_from_row(row)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(orders)
+}
+
+/// Activity on one blockchain over a period
+#[derive(Debug, Clone)]
+pub struct BlockchainActivity {
+    pub blockchain: String,
+    pub orders: u64,
+    pub pairs: u64,
+    /// Fiat volume in the base currency, when the orders were normalized
+    pub base_fiat_volume: Option<f64>,
+}
+
+/// Returns the blockchains with the most orders between `from` and `to`
+pub fn get_blockchain_activity(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: usize,
+) -> anyhow::Result<Vec<BlockchainActivity>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        blockchain,
+        COUNT(*),
+        COUNT(DISTINCT (crypto_symbol, fiat_symbol)),
+        SUM(base_fiat_amount)
+    FROM orders
+    WHERE created_at >= ? AND created_at < ?
+    GROUP BY blockchain
+    ORDER BY COUNT(*) DESC
+    LIMIT ?;",
+    )?;
+
+    let activity = statement
+        .query_map(params![from, to, limit as i64], |row| {
+            Ok(BlockchainActivity {
+                blockchain: row.get(0)?,
+                orders: row.get(1)?,
+                pairs: row.get(2)?,
+                base_fiat_volume: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(activity)
+}
+
+/// Fiat size distribution of the orders of a pair
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeDistribution {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub orders: u64,
+    pub min: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+    pub buckets: Vec<SizeBucket>,
+}
+
+/// Orders whose fiat amount is within `[lower, upper)`
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub orders: u64,
+}
+
+/// Returns the fiat size distribution of every pair, or of `pair` only, with power of ten buckets
+pub fn get_size_distributions(
+    persist_path: &str,
+    pair: Option<&Pair>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<SizeDistribution>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let pair_condition = pair
+        .map(|pair| {
+            format!(
+                "AND crypto_symbol = {} AND fiat_symbol = {}",
+                quote(&pair.crypto_symbol),
+                quote(&pair.fiat_symbol)
+            )
+        })
+        .unwrap_or_default();
+
+    let mut statement = conn.prepare(&format!(
+        r"SELECT
+            crypto_symbol,
+            fiat_symbol,
+            COUNT(*),
+            MIN(fiat_amount),
+            quantile_cont(fiat_amount, 0.5),
+            quantile_cont(fiat_amount, 0.9),
+            quantile_cont(fiat_amount, 0.99),
+            MAX(fiat_amount)
+        FROM orders
+        WHERE created_at >= ? AND created_at < ? {pair_condition}
+        GROUP BY crypto_symbol, fiat_symbol
+        ORDER BY COUNT(*) DESC;"
+    ))?;
+
+    let mut distributions = statement
+        .query_map(params![from, to], |row| {
+            Ok(SizeDistribution {
+                crypto_symbol: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                orders: row.get(2)?,
+                min: row.get(3)?,
+                p50: row.get(4)?,
+                p90: row.get(5)?,
+                p99: row.get(6)?,
+                max: row.get(7)?,
+                buckets: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = conn.prepare(&format!(
+        r"SELECT
+            crypto_symbol,
+            fiat_symbol,
+            power(10, floor(log10(fiat_amount))) AS lower,
+            COUNT(*)
+        FROM orders
+        WHERE created_at >= ? AND created_at < ? AND fiat_amount > 0 {pair_condition}
+        GROUP BY ALL
+        ORDER BY lower;"
+    ))?;
+
+    let buckets = statement.query_map(params![from, to], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(2)?,
+            row.get::<_, u64>(3)?,
+        ))
+    })?;
+
+    for bucket in buckets {
+        let (crypto_symbol, fiat_symbol, lower, orders) = bucket?;
+
+        if let Some(distribution) = distributions.iter_mut().find(|distribution| {
+            distribution.crypto_symbol == crypto_symbol && distribution.fiat_symbol == fiat_symbol
+        }) {
+            distribution.buckets.push(SizeBucket {
+                lower,
+                upper: lower * 10.0,
+                orders,
+            });
+        }
+    }
+
+    Ok(distributions)
+}
+
+/// Activity of a fiat currency over one day
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyVolume {
+    pub day: NaiveDate,
+    pub fiat_symbol: String,
+    pub orders: u64,
+    pub buy_fiat_volume: f64,
+    pub sell_fiat_volume: f64,
+    /// Fiat volume in the base currency, when the orders were normalized
+    pub base_fiat_volume: Option<f64>,
+}
+
+pub fn get_daily_volumes(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<DailyVolume>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        CAST(created_at AS DATE) AS day,
+        fiat_symbol,
+        COUNT(*),
+        COALESCE(SUM(fiat_amount) FILTER (WHERE type = 'buy'), 0),
+        COALESCE(SUM(fiat_amount) FILTER (WHERE type = 'sell'), 0),
+        SUM(base_fiat_amount)
+    FROM orders
+    WHERE created_at >= ? AND created_at < ?
+    GROUP BY day, fiat_symbol
+    ORDER BY day, fiat_symbol;",
+    )?;
+
+    let volumes = statement
+        .query_map(params![from, to], |row| {
+            Ok(DailyVolume {
+                day: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                orders: row.get(2)?,
+                buy_fiat_volume: row.get(3)?,
+                sell_fiat_volume: row.get(4)?,
+                base_fiat_volume: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(volumes)
+}
+
+/// Sizes of the sessions of one pair and side over a period
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub ty: OrderType,
+    pub sessions: u64,
+    pub avg_orders: f64,
+    pub max_orders: u64,
+    pub avg_fiat_amount: f64,
+    pub max_fiat_amount: f64,
+}
+
+pub fn get_session_summary(
+    persist_path: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<SessionSummary>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT
+        crypto_symbol,
+        fiat_symbol,
+        type,
+        COUNT(*),
+        AVG(orders),
+        MAX(orders),
+        AVG(fiat_amount),
+        MAX(fiat_amount)
+    FROM (
+        SELECT
+            crypto_symbol,
+            fiat_symbol,
+            type,
+            session_id,
+            COUNT(*) AS orders,
+            SUM(fiat_amount) AS fiat_amount
+        FROM orders
+        WHERE created_at >= ? AND created_at < ? AND session_id IS NOT NULL
+        GROUP BY crypto_symbol, fiat_symbol, type, session_id
+    )
+    GROUP BY crypto_symbol, fiat_symbol, type
+    ORDER BY crypto_symbol, fiat_symbol, type;",
+    )?;
+
+    let summaries = statement
+        .query_map(params![from, to], |row| {
+            Ok(SessionSummary {
+                crypto_symbol: row.get(0)?,
+                fiat_symbol: row.get(1)?,
+                ty: row.get(2)?,
+                sessions: row.get(3)?,
+                avg_orders: row.get(4)?,
+                max_orders: row.get(5)?,
+                avg_fiat_amount: row.get(6)?,
+                max_fiat_amount: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(summaries)
+}
+
+/// Maps the first columns of a row, selected in the `orders` table order, to an [`Order`]
+fn order_from_row(row: &Row<'_>) -> duckdb::Result<Order> {
+    Ok(Order {
+        ty: row.get(0)?,
+        blockchain: row.get(1)?,
+        crypto_amount: row.get(2)?,
+        crypto_symbol: row.get(3)?,
+        fiat_amount: row.get(4)?,
+        fiat_price: row.get(5)?,
+        fiat_symbol: row.get(6)?,
+        api_order_id: None,
+        api_created_at: None,
+        extra: Map::new(),
+    })
+}
+
+/// Rows removed by a prune, per table
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pruned {
+    pub orders: usize,
+    pub fetch_runs: usize,
+    pub gaps: usize,
+    pub imbalances: usize,
+    pub anomalies: usize,
+    pub reference_prices: usize,
+    pub tickers: usize,
+    pub bursts: usize,
+}
+
+impl Display for Pruned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} orders, {} fetch runs, {} gaps, {} imbalances, {} anomalies, {} reference prices, {} tickers and {} bursts removed",
+            self.orders,
+            self.fetch_runs,
+            self.gaps,
+            self.imbalances,
+            self.anomalies,
+            self.reference_prices,
+            self.tickers,
+            self.bursts
+        )
+    }
+}
+
+/// Orders written by [`archive`]
+#[derive(Debug)]
+pub struct Archived {
+    pub orders: usize,
+    pub files: Vec<PathBuf>,
+}
+
+/// Writes the orders created before `before` to `directory` as Parquet files
+/// partitioned by day, a day archived again replacing its file rather than
+/// adding another one, so `before` is expected at the start of a day
+pub fn archive(
+    persist_path: &str,
+    before: DateTime<Utc>,
+    directory: &str,
+) -> anyhow::Result<Archived> {
+    let conn = get_connection(persist_path)?;
+
+    let (orders, files) = conn.query_row(
+        &format!(
+            r"COPY (
+                SELECT *, CAST(created_at AS DATE) AS day
+                FROM orders
+                WHERE created_at < {}
+            ) TO {} (
+                FORMAT PARQUET,
+                PARTITION_BY (day),
+                OVERWRITE_OR_IGNORE,
+                FILENAME_PATTERN 'orders_{{i}}',
+                RETURN_FILES
+            );",
+            quote(&before.naive_utc().to_string()),
+            quote(directory)
+        ),
+        [],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Value>(1)?)),
+    )?;
+
+    let files = match files {
+        Value::List(files) => files
+            .into_iter()
+            .filter_map(|file| match file {
+                Value::Text(file) => Some(PathBuf::from(file)),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(Archived {
+        orders: usize::try_from(orders)?,
+        files,
+    })
+}
+
+/// Deletes the rows created before `before`
+pub fn prune(persist_path: &str, before: DateTime<Utc>) -> anyhow::Result<Pruned> {
+    let mut conn = get_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+
+    let pruned = Pruned {
+        orders: transaction.execute("DELETE FROM orders WHERE created_at < ?", params![before])?,
+        fetch_runs: transaction.execute(
+            "DELETE FROM fetch_runs WHERE started_at < ?",
+            params![before],
+        )?,
+        gaps: transaction.execute("DELETE FROM gaps WHERE detected_at < ?", params![before])?,
+        imbalances: transaction.execute(
+            "DELETE FROM imbalances WHERE computed_at < ?",
+            params![before],
+        )?,
+        anomalies: transaction.execute(
+            "DELETE FROM anomalies WHERE detected_at < ?",
+            params![before],
+        )?,
+        reference_prices: transaction.execute(
+            "DELETE FROM reference_prices WHERE fetched_at < ?",
+            params![before],
+        )?,
+        tickers: transaction
+            .execute("DELETE FROM tickers WHERE fetched_at < ?", params![before])?,
+        bursts: transaction.execute("DELETE FROM bursts WHERE ended_at < ?", params![before])?,
+    };
+
+    transaction.commit()?;
+
+    Ok(pruned)
+}
+
+/// Returns the number of rows of every user table
+fn table_row_counts(conn: &Connection) -> anyhow::Result<Vec<(String, i64)>> {
+    let tables = conn
+        .prepare("SELECT table_name FROM duckdb_tables() WHERE NOT internal ORDER BY table_name;")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    tables
+        .into_iter()
+        .map(|table| {
+            let count =
+                conn.query_row(&format!("SELECT COUNT(*) FROM \"{table}\";"), [], |row| {
+                    row.get(0)
+                })?;
+
+            Ok((table, count))
+        })
+        .collect()
+}
+
+/// Orders of another database merged by [`merge`]
+#[derive(Debug)]
+pub struct Merged {
+    pub inserted: usize,
+    /// Orders already stored, matched by content whatever their insert time
+    pub skipped: usize,
+}
+
+/// Inserts the orders of the database at `other` that aren't stored yet, then
+/// tags the sessions again since the session ids of both databases overlap
+pub fn merge(persist_path: &str, other: &str, session_gap: TimeDelta) -> anyhow::Result<Merged> {
+    let mut conn = get_connection(persist_path)?;
+
+    conn.execute_batch(&format!("ATTACH {} AS merged (READ_ONLY);", quote(other)))?;
+
+    let version = current_version(&conn)?;
+    let other_version: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM merged.schema_migrations;",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if other_version != version {
+        bail!(
+            "{other} is at schema version {other_version} and {persist_path} at {version}, migrate both first"
+        );
+    }
+
+    let transaction = conn.transaction()?;
+    let total: i64 =
+        transaction.query_row("SELECT COUNT(*) FROM merged.orders;", [], |row| row.get(0))?;
+
+    // The copies of an order recorded by both collectors have different local
+    // insert times, and may have been hashed with different occurrences, so
+    // the orders are matched by content, the copies beyond the stored ones
+    // being the repeated orders only the other database has
+    transaction.execute_batch(
+        r"CREATE OR REPLACE TEMP TABLE merging AS
+        WITH numbered AS (
+            SELECT
+                *,
+                ROW_NUMBER() OVER (
+                    PARTITION BY
+                        source,
+                        type,
+                        blockchain,
+                        crypto_amount,
+                        crypto_symbol,
+                        fiat_amount,
+                        fiat_price,
+                        fiat_symbol,
+                        api_order_id,
+                        api_created_at
+                    ORDER BY created_at
+                ) - 1 AS occurrence
+            FROM merged.orders
+        ),
+        stored AS (
+            SELECT
+                source,
+                type,
+                blockchain,
+                crypto_amount,
+                crypto_symbol,
+                fiat_amount,
+                fiat_price,
+                fiat_symbol,
+                api_order_id,
+                api_created_at,
+                COUNT(*) AS copies
+            FROM orders
+            GROUP BY ALL
+        )
+        SELECT numbered.* EXCLUDE (occurrence) REPLACE (
+            CAST(NULL AS BIGINT) AS session_id,
+            sha256(concat_ws(
+                '|',
+                numbered.source,
+                numbered.type,
+                numbered.blockchain,
+                numbered.crypto_amount,
+                numbered.crypto_symbol,
+                numbered.fiat_amount,
+                numbered.fiat_price,
+                numbered.fiat_symbol,
+                COALESCE(numbered.api_order_id, ''),
+                COALESCE(CAST(CAST(numbered.api_created_at AS TIMESTAMP) AS VARCHAR), ''),
+                numbered.occurrence
+            )) AS hash
+        )
+        FROM numbered
+        LEFT JOIN stored
+            ON numbered.source = stored.source
+            AND numbered.type = stored.type
+            AND numbered.blockchain IS NOT DISTINCT FROM stored.blockchain
+            AND numbered.crypto_amount = stored.crypto_amount
+            AND numbered.crypto_symbol = stored.crypto_symbol
+            AND numbered.fiat_amount = stored.fiat_amount
+            AND numbered.fiat_price = stored.fiat_price
+            AND numbered.fiat_symbol = stored.fiat_symbol
+            AND numbered.api_order_id IS NOT DISTINCT FROM stored.api_order_id
+            AND numbered.api_created_at IS NOT DISTINCT FROM stored.api_created_at
+        WHERE numbered.occurrence >= COALESCE(stored.copies, 0);",
+    )?;
+
+    // Counted before the insert, which doesn't tell which orders were new
+    add_to_pair_stats(&transaction, "SELECT * FROM merging", [])?;
+    let oldest: Option<DateTime<Utc>> =
+        transaction.query_row("SELECT MIN(created_at) FROM merging;", [], |row| row.get(0))?;
+
+    let inserted = transaction.execute(
+        "INSERT OR IGNORE INTO orders BY NAME SELECT * FROM merging ORDER BY created_at;",
+        [],
+    )?;
+    transaction.execute_batch("DROP TABLE merging;")?;
+
+    if inserted > 0 {
+        tag_sessions(&transaction, session_gap)?;
+        rollup_with(&transaction, oldest)?;
+    }
+
+    transaction.commit()?;
+    conn.execute_batch("DETACH merged;")?;
+
+    Ok(Merged {
+        inserted,
+        skipped: usize::try_from(total)?.saturating_sub(inserted),
+    })
+}
+
+/// Outcome of [`maintain`]
+#[derive(Debug)]
+pub struct Maintenance {
+    /// Size of the database file and its WAL, unknown in memory
+    pub size_before: Option<u64>,
+    pub size_after: Option<u64>,
+    pub indexes_rebuilt: Vec<String>,
+    /// Rows read back from each table
+    pub row_counts: Vec<(String, i64)>,
+    pub duplicate_hashes: i64,
+}
+
+impl Display for Maintenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(before), Some(after)) = (self.size_before, self.size_after) {
+            write!(f, "{before} bytes to {after} bytes, ")?;
+        }
+
+        write!(
+            f,
+            "{} indexes rebuilt, {} rows in {} tables verified",
+            self.indexes_rebuilt.len(),
+            self.row_counts.iter().map(|(_, count)| count).sum::<i64>(),
+            self.row_counts.len()
+        )?;
+
+        if self.duplicate_hashes > 0 {
+            write!(f, ", {} duplicate order hashes", self.duplicate_hashes)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads every row of every table back, which checks the block checksums,
+/// rebuilds the indexes, then reclaims the space of the deleted rows
+pub fn maintain(persist_path: &str) -> anyhow::Result<Maintenance> {
+    let size_before = database_size(persist_path);
+    let mut conn = get_connection(persist_path)?;
+
+    let tables = table_row_counts(&conn)?;
+    let row_counts = tables
+        .into_iter()
+        .map(|(table, _)| {
+            let count = conn.query_row(
+                &format!("SELECT COUNT(*), bit_xor(hash(t)) FROM \"{table}\" AS t;"),
+                [],
+                |row| row.get(0),
+            )?;
+
+            Ok((table, count))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let duplicate_hashes = conn.query_row(
+        r"SELECT COALESCE(SUM(copies - 1), 0)
+        FROM (SELECT COUNT(*) AS copies FROM orders GROUP BY hash HAVING COUNT(*) > 1);",
+        [],
+        |row| row.get(0),
+    )?;
+
+    let indexes = conn
+        .prepare("SELECT index_name, sql FROM duckdb_indexes() WHERE sql IS NOT NULL;")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // A unique index can't be rebuilt over duplicates, it's left as is then
+    let transaction = conn.transaction()?;
+
+    for (index, sql) in &indexes {
+        if index == "orders_hash" && duplicate_hashes > 0 {
+            continue;
+        }
+
+        transaction.execute_batch(&format!("DROP INDEX \"{index}\"; {sql}"))?;
+    }
+
+    transaction.commit()?;
+
+    conn.execute_batch("VACUUM ANALYZE; CHECKPOINT;")?;
+    drop(conn);
+
+    Ok(Maintenance {
+        size_before,
+        size_after: database_size(persist_path),
+        indexes_rebuilt: indexes
+            .into_iter()
+            .map(|(index, _)| index)
+            .filter(|index| index != "orders_hash" || duplicate_hashes == 0)
+            .collect(),
+        row_counts,
+        duplicate_hashes,
+    })
+}
+
+/// Size of the database file and its WAL, `None` in memory
+pub fn database_size(persist_path: &str) -> Option<u64> {
+    if persist_path == MEMORY_PATH {
+        return None;
+    }
+
+    let size = |path: &str| fs::metadata(path).map(|metadata| metadata.len()).ok();
+
+    Some(size(persist_path)? + size(&format!("{persist_path}.wal")).unwrap_or_default())
+}
+
+/// Exports a consistent snapshot of the database to `directory` as Parquet
+/// files, returning the number of rows of every table
+pub fn backup(
+    persist_path: &str,
+    directory: &str,
+    compression: &str,
+) -> anyhow::Result<Vec<(String, i64)>> {
+    let mut conn = get_read_only_connection(persist_path)?;
+    let transaction = conn.transaction()?;
+
+    let counts = table_row_counts(&transaction)?;
+
+    transaction.execute_batch(&format!(
+        "EXPORT DATABASE {} (FORMAT PARQUET, COMPRESSION {compression});",
+        quote(directory)
+    ))?;
+
+    transaction.commit()?;
+
+    Ok(counts)
+}
+
+/// Loads a backup in memory and checks it holds the expected number of rows
+pub fn verify_backup(directory: &str, expected: &[(String, i64)]) -> anyhow::Result<()> {
+    let conn = Connection::open_in_memory()?;
+
+    conn.execute_batch(&format!("IMPORT DATABASE {};", quote(directory)))?;
+
+    let counts = table_row_counts(&conn)?;
+
+    if counts != expected {
+        bail!("Backup holds {counts:?} rows instead of {expected:?}");
+    }
+
+    Ok(())
+}
+
+/// Imports a backup into the database, which must not hold any orders yet
+pub fn restore(persist_path: &str, directory: &str) -> anyhow::Result<Vec<(String, i64)>> {
+    let conn = get_connection(persist_path)?;
+
+    let has_orders = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM duckdb_tables() WHERE table_name = 'orders';",
+            [],
+            |row| row.get::<_, bool>(0),
+        )
+        .unwrap_or(false)
+        && conn.query_row("SELECT COUNT(*) > 0 FROM orders;", [], |row| {
+            row.get::<_, bool>(0)
+        })?;
+
+    if has_orders {
+        bail!("{persist_path} already holds orders, restore into a new database");
+    }
+
+    // Tables created by a previous start of the collector would clash with the imported ones
+    for (table, _) in table_row_counts(&conn)? {
+        conn.execute_batch(&format!("DROP TABLE \"{table}\";"))?;
+    }
+
+    let views = conn
+        .prepare("SELECT view_name FROM duckdb_views() WHERE NOT internal;")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for view in views {
+        conn.execute_batch(&format!("DROP VIEW \"{view}\";"))?;
+    }
+
+    conn.execute_batch(&format!("IMPORT DATABASE {};", quote(directory)))?;
+
+    table_row_counts(&conn)
+}
+
+/// Result of an arbitrary query, every value cast to text
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// Runs a user-provided query on a read-only connection, casting every column to text
+pub fn run_query(persist_path: &str, sql: &str) -> anyhow::Result<QueryResult> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(&format!(
+        "SELECT COLUMNS(*)::VARCHAR FROM ({})",
+        sql.trim().trim_end_matches(';')
+    ))?;
+
+    let mut rows = statement.query([])?;
+    let columns = rows
+        .as_ref()
+        .map(|statement| statement.column_names())
+        .unwrap_or_default();
+    let mut result = QueryResult {
+        columns,
+        rows: Vec::new(),
+    };
+
+    while let Some(row) = rows.next()? {
+        result.rows.push(
+            (0..result.columns.len())
+                .map(|i| row.get(i))
+                .collect::<Result<_, _>>()?,
+        );
+    }
+
+    Ok(result)
+}
+
+/// Runs a user-provided query on a read-only connection, converting every row to a JSON object
+pub fn run_json_query(persist_path: &str, sql: &str) -> anyhow::Result<Vec<serde_json::Value>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(&format!(
+        "SELECT to_json(q)::VARCHAR FROM ({}) q",
+        sql.trim().trim_end_matches(';')
+    ))?;
+
+    let rows = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|json| Ok(serde_json::from_str(&json?)?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(rows)
+}
+
+/// Returns the number of orders with the same content as an earlier one
+pub fn count_suspected_duplicates(persist_path: &str) -> anyhow::Result<i64> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    let suspected = conn.query_row(
+        "SELECT COUNT(*) FROM suspected_duplicates WHERE created_at > first_created_at;",
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(suspected)
+}
+
+/// Returns the number of order rows stored more than once with the same timestamp
+pub fn count_duplicate_orders(persist_path: &str) -> anyhow::Result<i64> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    let duplicates = conn.query_row(
+        r"SELECT COALESCE(SUM(copies - 1), 0)
+        FROM (
+            SELECT COUNT(*) AS copies
+            FROM orders
+            GROUP BY ALL
+            HAVING COUNT(*) > 1
+        );",
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(duplicates)
+}
+
+/// Returns the longest periods without any inserted order, longest first
+pub fn get_longest_silences(
+    persist_path: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+    let conn = get_read_only_connection(persist_path)?;
+    let mut statement = conn.prepare(
+        r"SELECT previous_created_at, created_at
+        FROM (
+            SELECT
+                LAG(created_at) OVER (ORDER BY created_at) AS previous_created_at,
+                created_at
+            FROM orders
+        )
+        WHERE previous_created_at IS NOT NULL
+        ORDER BY created_at - previous_created_at DESC
+        LIMIT ?;",
+    )?;
+
+    let silences = statement
+        .query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(silences)
+}
+
+/// Returns the number of rows of every user table
+pub fn get_row_counts(persist_path: &str) -> anyhow::Result<Vec<(String, i64)>> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    table_row_counts(&conn)
+}
+
+/// Opens the database and runs a trivial query, to tell whether it is usable
+pub fn check_connection(persist_path: &str) -> anyhow::Result<()> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    conn.query_row("SELECT 1;", [], |row| row.get::<_, i32>(0))?;
+
+    Ok(())
+}
+
+/// Returns the start of the latest successful poll cycle and the latest order insertion
+pub fn get_last_activity(
+    persist_path: &str,
+) -> anyhow::Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    let activity = conn.query_row(
+        r"SELECT
+            (SELECT MAX(started_at) FROM fetch_runs WHERE error IS NULL),
+            (SELECT MAX(created_at) FROM orders);",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    Ok(activity)
+}
+
+/// Summary of every stored fetch run, the uptime being unknown from the database
+pub fn get_status(persist_path: &str) -> anyhow::Result<StatusSummary> {
+    let conn = get_read_only_connection(persist_path)?;
+
+    let (cycles, orders_inserted, last_latency_ms): (u64, u64, Option<u64>) = conn.query_row(
+        r"SELECT
+            COUNT(*),
+            CAST(COALESCE(SUM(orders_inserted), 0) AS UBIGINT),
+            arg_max(latency_ms, started_at) FILTER (WHERE latency_ms IS NOT NULL)
+        FROM fetch_runs;",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let mut stmt = conn.prepare(
+        r"SELECT source, COUNT(*)
+        FROM fetch_runs
+        WHERE error IS NOT NULL
+        GROUP BY source
+        ORDER BY source;",
+    )?;
+    let errors = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(StatusSummary {
+        uptime: None,
+        cycles,
+        orders_inserted,
+        errors,
+        last_latency: last_latency_ms.map(Duration::from_millis),
+        db_size: database_size(persist_path),
+    })
+}
+
+/// Instance of the database shared by the connections of the process, so that
+/// the fetch loop, the HTTP handlers and the jobs don't open the file several
+/// times at once, closed once none uses it for other processes to open it
+struct SharedInstance {
+    persist_path: String,
+    read_only: bool,
+    connection: Connection,
+    users: usize,
+}
+
+struct Shared {
+    instance: Option<SharedInstance>,
+    /// Connections waiting for a read-only instance to close to write
+    waiting_writers: usize,
+}
+
+static SHARED: Mutex<Shared> = Mutex::new(Shared {
+    instance: None,
+    waiting_writers: 0,
+});
+static RELEASED: Condvar = Condvar::new();
+
+/// Connection cloned from the instance of the database shared by the process
+pub struct SharedConnection {
+    connection: Connection,
+    /// Persist path of the shared instance, `None` when not shared
+    shared: Option<String>,
+}
+
+impl Deref for SharedConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl DerefMut for SharedConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.connection
+    }
+}
+
+impl Drop for SharedConnection {
+    fn drop(&mut self) {
+        let Some(persist_path) = &self.shared else {
+            return;
+        };
+        let Ok(mut shared) = SHARED.lock() else {
+            return;
+        };
+
+        if let Some(instance) = &mut shared.instance
+            && instance.persist_path == *persist_path
+        {
+            instance.users -= 1;
+
+            if instance.users == 0 {
+                shared.instance = None;
+                RELEASED.notify_all();
+            }
+        }
+    }
+}
+
+/// Clones a connection from the shared instance, opening it with `open` if
+/// the process has none
+fn connect_shared(
+    persist_path: &str,
+    read_only: bool,
+    open: impl Fn() -> duckdb::Result<Connection>,
+) -> anyhow::Result<SharedConnection> {
+    let mut shared = SHARED
+        .lock()
+        .map_err(|_| anyhow!("Shared database lock poisoned"))?;
+    let started_at = Instant::now();
+
+    loop {
+        let waiting_writers = shared.waiting_writers;
+
+        match &mut shared.instance {
+            // A read-only instance is only joined by readers while no writer
+            // waits for it to close
+            Some(instance)
+                if instance.persist_path == persist_path
+                    && (!instance.read_only || (read_only && waiting_writers == 0)) =>
+            {
+                let connection = instance.connection.try_clone()?;
+                instance.users += 1;
+
+                return Ok(SharedConnection {
+                    connection,
+                    shared: Some(persist_path.to_string()),
+                });
+            }
+            Some(instance) if instance.persist_path == persist_path => {
+                // The same thread may hold it, which is reported rather than
+                // waited for forever
+                let Some(timeout) = LOCK_TIMEOUT.checked_sub(started_at.elapsed()) else {
+                    bail!("{persist_path} stayed open read-only by this process");
+                };
+                let writer = usize::from(!read_only);
+
+                shared.waiting_writers += writer;
+                shared = RELEASED
+                    .wait_timeout(shared, timeout)
+                    .map_err(|_| anyhow!("Shared database lock poisoned"))?
+                    .0;
+                shared.waiting_writers -= writer;
+            }
+            // Another database, like a backup being verified, isn't shared
+            Some(_) => {
+                return Ok(SharedConnection {
+                    connection: open_retrying(persist_path, &open)?,
+                    shared: None,
+                });
+            }
+            None => {
+                let instance = open_retrying(persist_path, &open)?;
+                let connection = instance.try_clone()?;
+
+                shared.instance = Some(SharedInstance {
+                    persist_path: persist_path.to_string(),
+                    read_only,
+                    connection: instance,
+                    users: 1,
+                });
+
+                return Ok(SharedConnection {
+                    connection,
+                    shared: Some(persist_path.to_string()),
+                });
+            }
+        }
+    }
+}
+
+pub fn get_connection(persist_path: &str) -> anyhow::Result<SharedConnection> {
+    // Each in-memory connection would get its own empty database, so they are
+    // all cloned from a single one living as long as the process
+    if persist_path == MEMORY_PATH {
+        static MEMORY: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+        let memory = match MEMORY.get() {
+            Some(memory) => memory,
+            None => {
+                let connection = Connection::open_in_memory()?;
+                MEMORY.get_or_init(|| Mutex::new(connection))
+            }
+        };
+
+        let connection = memory
+            .lock()
+            .map_err(|_| anyhow!("In-memory database lock poisoned"))?
+            .try_clone()?;
+
+        return Ok(SharedConnection {
+            connection,
+            shared: None,
+        });
+    }
+
+    connect_shared(persist_path, false, || Connection::open(persist_path))
+}
+
+/// Opens the database so that nothing can be written through the connection,
+/// unless the process already has it open to write
+pub fn get_read_only_connection(persist_path: &str) -> anyhow::Result<SharedConnection> {
+    // The in-memory database can't be reopened read-only
+    if persist_path == MEMORY_PATH {
+        return get_connection(persist_path);
+    }
+
+    connect_shared(persist_path, true, || {
+        let config = duckdb::Config::default().access_mode(duckdb::AccessMode::ReadOnly)?;
+
+        Connection::open_with_flags(persist_path, config)
+    })
+}
+
+/// Opens the database with `open`, retrying while another process holds the
+/// lock on the file, like the collector and the commands reading alongside it
+fn open_retrying(
+    persist_path: &str,
+    open: impl Fn() -> duckdb::Result<Connection>,
+) -> anyhow::Result<Connection> {
+    let started_at = Instant::now();
 
-    Ok(connection)
+    loop {
+        match open() {
+            Err(err)
+                if err.to_string().contains("Could not set lock on file")
+                    && started_at.elapsed() < LOCK_TIMEOUT =>
+            {
+                thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            result => {
+                return result.with_context(|| format!("Failed to open {persist_path}"));
+            }
+        }
+    }
 }
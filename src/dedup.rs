@@ -0,0 +1,254 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::fetch::Order;
+
+/// How many of the orders already seen are remembered, by count, by time since
+/// they were last fetched or both, for a fetch not to return them as new again
+#[derive(Debug, Clone, Copy)]
+pub struct DedupWindow {
+    pub count: Option<usize>,
+    pub age: Option<Duration>,
+}
+
+impl DedupWindow {
+    /// Oldest instant remembered, if limited by age
+    pub fn since(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.age
+            .and_then(|age| TimeDelta::from_std(age).ok())
+            .and_then(|age| now.checked_sub_signed(age))
+    }
+}
+
+impl FromStr for DedupWindow {
+    type Err = anyhow::Error;
+
+    /// Parses a count, a duration or both separated by a comma, like `500,24h`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut window = DedupWindow {
+            count: None,
+            age: None,
+        };
+
+        for part in s.split(',').map(str::trim) {
+            if let Ok(count) = part.parse() {
+                window.count = Some(count);
+            } else {
+                window.age = Some(
+                    humantime::parse_duration(part)
+                        .map_err(|err| anyhow!("Invalid dedup window {s}: {err}"))?,
+                );
+            }
+        }
+
+        Ok(window)
+    }
+}
+
+impl Display for DedupWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.count, self.age) {
+            (Some(count), Some(age)) => write!(f, "{count},{}", humantime::format_duration(age)),
+            (Some(count), None) => write!(f, "{count}"),
+            (None, Some(age)) => write!(f, "{}", humantime::format_duration(age)),
+            (None, None) => write!(f, "unbounded"),
+        }
+    }
+}
+
+/// Orders seen within the dedup window
+#[derive(Debug)]
+pub struct SeenOrders {
+    window: DedupWindow,
+    orders: HashSet<Order>,
+    /// When each order was last fetched, the least recently first
+    by_age: VecDeque<(DateTime<Utc>, Order)>,
+}
+
+impl SeenOrders {
+    /// Remembers `stored`, newest first as loaded from the database
+    pub fn new(window: DedupWindow, stored: Vec<(DateTime<Utc>, Order)>) -> Self {
+        let mut seen = Self {
+            window,
+            orders: HashSet::new(),
+            by_age: VecDeque::new(),
+        };
+
+        for (at, order) in stored.into_iter().rev() {
+            seen.insert(order, at);
+        }
+
+        seen.evict(Utc::now(), 0);
+        seen
+    }
+
+    /// Returns the orders of `fetched` not seen yet, and remembers all of them
+    /// as fetched at `at`
+    pub fn observe(&mut self, fetched: &HashSet<Order>, at: DateTime<Utc>) -> Vec<Order> {
+        let new_orders = fetched
+            .iter()
+            .filter(|order| !self.orders.contains(*order))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // The orders still in the API window move to the back, so that they
+        // can't be evicted and then fetched as new
+        self.by_age.retain(|(_, order)| !fetched.contains(order));
+
+        for order in fetched {
+            self.orders.insert(order.clone());
+            self.by_age.push_back((at, order.clone()));
+        }
+
+        self.evict(at, fetched.len());
+
+        new_orders
+    }
+
+    fn insert(&mut self, order: Order, at: DateTime<Utc>) {
+        if self.orders.insert(order.clone()) {
+            self.by_age.push_back((at, order));
+        }
+    }
+
+    /// Forgets the orders out of the window, the `keep` latest ones excepted
+    fn evict(&mut self, now: DateTime<Utc>, keep: usize) {
+        let since = self.window.since(now);
+        let count = self.window.count.map(|count| count.max(keep));
+
+        while let Some((at, _)) = self.by_age.front()
+            && self.by_age.len() > keep
+            && (since.is_some_and(|since| *at < since)
+                || count.is_some_and(|count| self.by_age.len() > count))
+        {
+            if let Some((_, order)) = self.by_age.pop_front() {
+                self.orders.remove(&order);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Map;
+
+    use super::*;
+    use crate::fetch::OrderType;
+
+    /// Order told apart from the others by its crypto symbol
+    fn order(symbol: &str) -> Order {
+        Order {
+            ty: OrderType::Buy,
+            blockchain: "ETH".to_string(),
+            crypto_amount: 1.0,
+            crypto_symbol: symbol.to_string(),
+            fiat_amount: 100.0,
+            fiat_price: 100.0,
+            fiat_symbol: "USD".to_string(),
+            api_order_id: None,
+            api_created_at: None,
+            extra: Map::new(),
+        }
+    }
+
+    fn fetched(symbols: &[&str]) -> HashSet<Order> {
+        symbols.iter().map(|symbol| order(symbol)).collect()
+    }
+
+    fn symbols(orders: Vec<Order>) -> Vec<String> {
+        let mut symbols = orders
+            .into_iter()
+            .map(|order| order.crypto_symbol)
+            .collect::<Vec<_>>();
+        symbols.sort();
+        symbols
+    }
+
+    #[test]
+    fn parses_a_count_an_age_or_both() {
+        let window = "500".parse::<DedupWindow>().unwrap();
+        assert_eq!(window.count, Some(500));
+        assert_eq!(window.age, None);
+
+        let window = "24h".parse::<DedupWindow>().unwrap();
+        assert_eq!(window.count, None);
+        assert_eq!(window.age, Some(Duration::from_secs(24 * 60 * 60)));
+
+        let window = "500, 24h".parse::<DedupWindow>().unwrap();
+        assert_eq!(window.count, Some(500));
+        assert_eq!(window.age, Some(Duration::from_secs(24 * 60 * 60)));
+
+        assert!("500,forever".parse::<DedupWindow>().is_err());
+    }
+
+    #[test]
+    fn only_returns_the_orders_not_seen_yet() {
+        let mut seen = SeenOrders::new("500".parse().unwrap(), Vec::new());
+        let at = DateTime::UNIX_EPOCH;
+
+        assert_eq!(symbols(seen.observe(&fetched(&["a", "b"]), at)), ["a", "b"]);
+        assert_eq!(symbols(seen.observe(&fetched(&["a", "b", "c"]), at)), ["c"]);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_fetched_beyond_the_count() {
+        let mut seen = SeenOrders::new("2".parse().unwrap(), Vec::new());
+        let at = DateTime::UNIX_EPOCH;
+
+        seen.observe(&fetched(&["a"]), at);
+        seen.observe(&fetched(&["b"]), at);
+        // Fetched again, a moves behind b which gets evicted instead
+        seen.observe(&fetched(&["a", "c"]), at);
+
+        assert_eq!(
+            symbols(seen.observe(&fetched(&["a"]), at)),
+            Vec::<String>::new()
+        );
+        assert_eq!(symbols(seen.observe(&fetched(&["b"]), at)), ["b"]);
+    }
+
+    #[test]
+    fn evicts_the_orders_older_than_the_age() {
+        let mut seen = SeenOrders::new("1h".parse().unwrap(), Vec::new());
+        let at = DateTime::UNIX_EPOCH;
+
+        seen.observe(&fetched(&["a"]), at);
+        seen.observe(&fetched(&["b"]), at + TimeDelta::minutes(30));
+        seen.observe(&fetched(&["c"]), at + TimeDelta::minutes(90));
+
+        assert_eq!(
+            symbols(seen.observe(&fetched(&["a", "b"]), at + TimeDelta::minutes(90))),
+            ["a"]
+        );
+    }
+
+    #[test]
+    fn keeps_the_whole_fetch_beyond_the_window() {
+        let mut seen = SeenOrders::new("1,1h".parse().unwrap(), Vec::new());
+        let at = DateTime::UNIX_EPOCH;
+
+        seen.observe(&fetched(&["a", "b", "c"]), at);
+
+        // Still returned by the API past the window, none of them is new again
+        assert_eq!(
+            symbols(seen.observe(&fetched(&["a", "b", "c"]), at + TimeDelta::hours(2))),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn remembers_the_stored_orders() {
+        let now = Utc::now();
+        let stored = vec![(now, order("b")), (now - TimeDelta::hours(2), order("a"))];
+        let mut seen = SeenOrders::new("1h".parse().unwrap(), stored);
+
+        assert_eq!(symbols(seen.observe(&fetched(&["a", "b"]), now)), ["a"]);
+    }
+}
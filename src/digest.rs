@@ -0,0 +1,128 @@
+use std::fs;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox,
+    transport::smtp::authentication::Credentials,
+};
+use minijinja::{Environment, context};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    args::Args,
+    db::{get_largest_orders, get_summary},
+    scheduler::spawn_job,
+};
+
+const DEFAULT_TEMPLATE: &str = include_str!("../templates/digest.txt");
+
+/// Number of orders listed in the largest orders section
+const LARGEST_ORDERS: usize = 5;
+
+/// Schedules the email digest of the activity, when an SMTP server is configured
+pub fn spawn_digest(args: &Args) -> anyhow::Result<()> {
+    let Some(host) = &args.smtp_host else {
+        return Ok(());
+    };
+
+    let mut transport =
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?.port(args.smtp_port);
+
+    if let Some(username) = &args.smtp_username {
+        transport = transport.credentials(Credentials::new(
+            username.clone(),
+            args.smtp_password.clone().unwrap_or_default(),
+        ));
+    }
+
+    let transport = transport.build();
+    let from = args
+        .digest_from
+        .as_deref()
+        .context("A digest sender address is required")?
+        .parse::<Mailbox>()?;
+    let to = args
+        .digest_to
+        .iter()
+        .map(|address| address.parse::<Mailbox>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let template = match &args.digest_template {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read digest template {}", path.display()))?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+
+    // Fails early on a broken template rather than at the first digest
+    Environment::new().template_from_str(&template)?;
+
+    let persist_path = args.persist_path.clone();
+    let timezone = args.timezone;
+
+    spawn_job(
+        "email digest".to_string(),
+        args.digest_schedule.clone(),
+        move |from_time, to_time| {
+            let transport = transport.clone();
+            let persist_path = persist_path.clone();
+            let template = template.clone();
+            let from = from.clone();
+            let to = to.clone();
+
+            async move {
+                let body = spawn_blocking(move || {
+                    render(&persist_path, &template, from_time, to_time, timezone)
+                })
+                .await??;
+
+                let mut message = Message::builder().from(from).subject(format!(
+                    "Nash activity {}",
+                    from_time.with_timezone(&timezone).format("%Y-%m-%d %H:%M")
+                ));
+
+                for recipient in &to {
+                    message = message.to(recipient.clone());
+                }
+
+                transport.send(message.body(body)?).await?;
+
+                Ok(format!("sent to {} recipients", to.len()))
+            }
+        },
+    );
+
+    Ok(())
+}
+
+fn render(
+    persist_path: &str,
+    template: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    timezone: Tz,
+) -> anyhow::Result<String> {
+    let pairs = get_summary(persist_path, from, to)?;
+    let largest_orders = get_largest_orders(persist_path, from, to, LARGEST_ORDERS)?
+        .into_iter()
+        .map(|(created_at, order)| {
+            context! {
+                created_at => created_at.with_timezone(&timezone).format("%Y-%m-%d %H:%M").to_string(),
+                order => order.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let body = Environment::new().render_str(
+        template,
+        context! {
+            from => from.with_timezone(&timezone).format("%Y-%m-%d %H:%M").to_string(),
+            to => to.with_timezone(&timezone).format("%Y-%m-%d %H:%M").to_string(),
+            pairs,
+            largest_orders,
+        },
+    )?;
+
+    Ok(body)
+}
@@ -0,0 +1,143 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{db::get_read_only_connection, fetch::Order, scheduler::Schedule};
+
+/// A named, periodic export of the orders of one or all pairs
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportSubscription {
+    pub name: String,
+    /// Pair as `CRYPTO/FIAT`, all pairs are exported when not set
+    pub pair: Option<Pair>,
+    pub format: ExportFormat,
+    /// Directory the export files are written to
+    pub destination: PathBuf,
+    pub schedule: Schedule,
+}
+
+impl ExportSubscription {
+    /// Exports the orders created between `from` and `to` and returns the written file
+    pub fn run(
+        &self,
+        persist_path: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(&self.destination)?;
+
+        let file = self.destination.join(format!(
+            "{}-{}.{}",
+            self.name,
+            from.format("%Y%m%dT%H%M"),
+            self.format.extension()
+        ));
+
+        let mut filter = format!(
+            "created_at >= {} AND created_at < {}",
+            quote(&from.naive_utc().to_string()),
+            quote(&to.naive_utc().to_string())
+        );
+
+        if let Some(pair) = &self.pair {
+            filter.push_str(&format!(
+                " AND crypto_symbol = {} AND fiat_symbol = {}",
+                quote(&pair.crypto_symbol),
+                quote(&pair.fiat_symbol)
+            ));
+        }
+
+        let conn = get_read_only_connection(persist_path)?;
+
+        conn.execute_batch(&format!(
+            "COPY (SELECT * FROM orders WHERE {filter} ORDER BY created_at) TO {} (FORMAT {});",
+            quote(&file.to_string_lossy()),
+            self.format.duckdb_format()
+        ))?;
+
+        Ok(file)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pair {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+}
+
+impl Pair {
+    pub fn of(order: &Order) -> Self {
+        Pair {
+            crypto_symbol: order.crypto_symbol.clone(),
+            fiat_symbol: order.fiat_symbol.clone(),
+        }
+    }
+}
+
+impl FromStr for Pair {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (crypto_symbol, fiat_symbol) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Pair {s} should be formatted as CRYPTO/FIAT"))?;
+
+        if crypto_symbol.is_empty() || fiat_symbol.is_empty() {
+            bail!("Pair {s} should be formatted as CRYPTO/FIAT");
+        }
+
+        Ok(Pair {
+            crypto_symbol: crypto_symbol.to_string(),
+            fiat_symbol: fiat_symbol.to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for Pair {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Pair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.crypto_symbol, self.fiat_symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Parquet,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    fn duckdb_format(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV, HEADER",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Parquet => "PARQUET",
+        }
+    }
+}
+
+/// Quotes a string as a SQL literal, for statements like `COPY` that don't take parameters
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
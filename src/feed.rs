@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, stream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::{db::OrderFilter, fetch::Order};
+
+/// New orders kept for the live subscribers lagging behind
+const FEED_CAPACITY: usize = 1024;
+
+/// An order as processed by the collector
+#[derive(Debug, Clone)]
+pub struct FeedOrder {
+    pub received_at: DateTime<Utc>,
+    pub order: Order,
+}
+
+/// Broadcasts the new orders to the live endpoints, the ones sent while
+/// nobody listens being dropped
+pub type OrderFeed = broadcast::Sender<FeedOrder>;
+
+pub fn order_feed() -> OrderFeed {
+    broadcast::channel(FEED_CAPACITY).0
+}
+
+/// Follows the new orders matching `filter`, skipping the ones missed by a
+/// subscriber too slow to keep up
+pub fn subscribe(feed: &OrderFeed, filter: OrderFilter) -> impl Stream<Item = FeedOrder> + use<> {
+    stream::unfold(
+        (feed.subscribe(), filter),
+        |(mut receiver, filter)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(feed_order) if filter.matches(&feed_order.order) => {
+                        return Some((feed_order, (receiver, filter)));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Live subscriber lagging behind, {skipped} orders skipped");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    )
+}
@@ -1,67 +1,136 @@
-use std::{collections::HashSet, error::Error, fmt::Display, hash::Hash, str::FromStr};
+use std::{
+    collections::HashSet, error::Error, fmt::Display, hash::Hash, str::FromStr, time::Duration,
+};
 
 use anyhow::anyhow;
 use approx::AbsDiffEq;
+use chrono::{DateTime, TimeDelta, Utc};
 use duckdb::types::{FromSql, FromSqlError};
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
 
-pub async fn fetch(client: &reqwest::Client) -> anyhow::Result<HashSet<Order>> {
-    let order_response = client
-        .get("https://app.nash.io/api/cash/latest_completed_orders")
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<OrdersResponse>()
-        .await
-        .map_err(|err| anyhow!("Failed to deserialize: {err}"))?;
+use crate::numbers::{NumberFormat, OrderStyle};
 
-    let current_orders = LatestOrders::try_from(order_response)?;
+#[derive(Debug)]
+pub struct Fetched {
+    pub status: u16,
+    pub orders: HashSet<Order>,
+    /// The response didn't change since the previous fetch, `orders` being left empty
+    pub not_modified: bool,
+    pub timing: Timing,
+    /// How long the API asked to wait before the next fetch, its request
+    /// quota being exhausted
+    pub retry_after: Option<Duration>,
+}
 
-    Ok(current_orders.into_set())
+impl Fetched {
+    pub fn not_modified(status: u16, timing: Timing) -> Self {
+        Self {
+            status,
+            orders: HashSet::new(),
+            not_modified: true,
+            timing,
+            retry_after: None,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-enum OrdersResponse {
-    Ok(LatestOrders),
-    Err(OrdersError),
+/// Number of characters of an unparsable response kept in the error
+const SNIPPET_LENGTH: usize = 200;
+
+/// Why a fetch failed
+#[derive(Debug)]
+pub enum FetchError {
+    /// The request failed or the API answered with an error status
+    Http(reqwest::Error),
+    /// The API asked to slow down, for `retry_after` when it said how long
+    RateLimited { retry_after: Option<Duration> },
+    /// The API answered with an error message
+    Api(String),
+    /// The response isn't in the expected shape
+    Deserialize {
+        snippet: String,
+        source: serde_json::Error,
+    },
 }
 
-impl TryFrom<OrdersResponse> for LatestOrders {
-    type Error = OrdersError;
+impl FetchError {
+    pub fn deserialize(body: &[u8], source: serde_json::Error) -> Self {
+        let body = String::from_utf8_lossy(body);
+        let mut snippet = body.chars().take(SNIPPET_LENGTH).collect::<String>();
 
-    fn try_from(value: OrdersResponse) -> Result<Self, Self::Error> {
-        match value {
-            OrdersResponse::Ok(latest_orders) => Ok(latest_orders),
-            OrdersResponse::Err(orders_error) => Err(orders_error),
+        if body.chars().count() > SNIPPET_LENGTH {
+            snippet.push('…');
         }
+
+        FetchError::Deserialize { snippet, source }
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct LatestOrders {
-    latest_orders: Vec<Order>,
+    /// Class of the error, as reported in the metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FetchError::Http(_) => "http",
+            FetchError::RateLimited { .. } => "rate_limited",
+            FetchError::Api(_) => "api",
+            FetchError::Deserialize { .. } => "deserialize",
+        }
+    }
+
+    /// HTTP status of the response, if one was received
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            FetchError::Http(err) => err.status().map(|status| status.as_u16()),
+            FetchError::RateLimited { .. } => Some(429),
+            FetchError::Api(_) | FetchError::Deserialize { .. } => None,
+        }
+    }
 }
 
-impl LatestOrders {
-    fn into_set(self) -> HashSet<Order> {
-        HashSet::from_iter(self.latest_orders)
+impl Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::Http(err) => write!(f, "{err}"),
+            FetchError::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(
+                f,
+                "Rate limited, retry after {}",
+                humantime::format_duration(*retry_after)
+            ),
+            FetchError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            FetchError::Api(message) => write!(f, "API error: {message}"),
+            FetchError::Deserialize { snippet, source } => {
+                write!(f, "Failed to deserialize '{snippet}': {source}")
+            }
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct OrdersError {
-    message: String,
+impl Error for FetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FetchError::Http(err) => Some(err),
+            FetchError::Deserialize { source, .. } => Some(source),
+            FetchError::RateLimited { .. } | FetchError::Api(_) => None,
+        }
+    }
 }
 
-impl Display for OrdersError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+impl From<reqwest::Error> for FetchError {
+    fn from(err: reqwest::Error) -> Self {
+        FetchError::Http(err)
     }
 }
 
-impl Error for OrdersError {}
+#[derive(Debug, Clone, Copy)]
+pub struct Timing {
+    /// Time between sending the request and receiving the response headers
+    pub latency: Duration,
+    /// Server clock minus local clock, if the server sent a `Date` header
+    pub skew: Option<TimeDelta>,
+    /// When the server answered, from its `Date` header
+    pub server_date: Option<DateTime<Utc>>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -77,6 +146,15 @@ pub struct Order {
     #[serde(deserialize_with = "from_str_to_f64")]
     pub fiat_price: f64,
     pub fiat_symbol: String,
+    /// Id of the order on the API side, if it exposes one
+    #[serde(default, rename = "id", deserialize_with = "from_id")]
+    pub api_order_id: Option<String>,
+    /// When the order was completed according to the API, if it says so
+    #[serde(default, rename = "createdAt", deserialize_with = "from_timestamp")]
+    pub api_created_at: Option<DateTime<Utc>>,
+    /// Fields the API added since, kept as is and ignored by the comparisons
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl PartialEq for Order {
@@ -97,19 +175,14 @@ impl PartialEq for Order {
 
 impl Eq for Order {}
 
+/// The compact sentence of the default [`NumberFormat`], see
+/// [`NumberFormat::order`] for the other locales and styles
 impl Display for Order {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{} {} {} for {} {} at {} {} on {}",
-            self.ty,
-            self.crypto_amount,
-            self.crypto_symbol,
-            self.fiat_amount,
-            self.fiat_symbol,
-            self.fiat_price,
-            self.fiat_symbol,
-            self.blockchain
+            "{}",
+            NumberFormat::default().order(self, OrderStyle::Compact)
         )
     }
 }
@@ -163,10 +236,44 @@ impl FromSql for OrderType {
     }
 }
 
-fn from_str_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+pub fn from_str_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
     s.parse::<f64>().map_err(serde::de::Error::custom)
 }
+
+/// Accepts ids sent as strings as well as numbers
+fn from_id<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(id) => Some(id),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    })
+}
+
+/// Accepts RFC 3339 dates and Unix timestamps in seconds or milliseconds, an
+/// unexpected format leaving the timestamp unknown rather than failing the order
+fn from_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(date) => DateTime::parse_from_rfc3339(&date)
+            .ok()
+            .map(|date| date.with_timezone(&Utc)),
+        Value::Number(timestamp) => timestamp.as_i64().and_then(|timestamp| {
+            // Past 10^11 seconds is year 5138, so it must be milliseconds
+            if timestamp.abs() >= 100_000_000_000 {
+                DateTime::from_timestamp_millis(timestamp)
+            } else {
+                DateTime::from_timestamp(timestamp, 0)
+            }
+        }),
+        _ => None,
+    })
+}
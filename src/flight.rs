@@ -0,0 +1,210 @@
+use std::net::SocketAddr;
+
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+    encode::FlightDataEncoderBuilder,
+    error::FlightError,
+    flight_service_server::{FlightService, FlightServiceServer},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{
+    StreamExt, TryStreamExt,
+    stream::{self, BoxStream},
+};
+use serde::Deserialize;
+use tonic::{Request, Response, Status, Streaming, transport::Server};
+use tracing::{error, info};
+
+use crate::{
+    batches::{BATCH_ROWS, OrderRange, SCHEMA},
+    db::OrderFilter,
+    export::Pair,
+    fetch::OrderType,
+    store::Store,
+};
+
+/// Orders requested by a ticket, a JSON object whose fields are all optional,
+/// the empty ticket requesting every stored order
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrdersTicket {
+    pair: Option<Pair>,
+    #[serde(rename = "type")]
+    ty: Option<OrderType>,
+    min_fiat_amount: Option<f64>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+impl OrdersTicket {
+    fn parse(ticket: &[u8]) -> Result<Self, Status> {
+        if ticket.is_empty() {
+            return Ok(Self::default());
+        }
+
+        serde_json::from_slice(ticket)
+            .map_err(|err| Status::invalid_argument(format!("Invalid ticket: {err}")))
+    }
+
+    fn range(self) -> OrderRange {
+        OrderRange::new(
+            OrderFilter {
+                pair: self.pair,
+                ty: self.ty,
+                min_fiat_amount: self.min_fiat_amount,
+            },
+            self.from,
+            self.to,
+        )
+    }
+}
+
+/// Arrow Flight server streaming the stored orders as record batches
+struct FlightOrders {
+    store: Store,
+}
+
+pub fn spawn_flight(addr: SocketAddr, store: Store) {
+    let service = FlightServiceServer::new(FlightOrders { store });
+
+    info!("Arrow Flight server listening on {addr}");
+
+    tokio::spawn(async move {
+        if let Err(err) = Server::builder().add_service(service).serve(addr).await {
+            error!("Arrow Flight server failed: {err}");
+        }
+    });
+}
+
+#[tonic::async_trait]
+impl FlightService for FlightOrders {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    /// A single flight, every stored order
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let info = flight_info(FlightDescriptor::new_path(vec!["orders".to_string()]), &[])?;
+
+        Ok(Response::new(stream::iter([Ok(info)]).boxed()))
+    }
+
+    /// The command of the descriptor is used as the ticket
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        let ticket = descriptor.cmd.clone();
+        OrdersTicket::parse(&ticket)?;
+
+        Ok(Response::new(flight_info(descriptor, &ticket)?))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        let schema = SchemaAsIpc::new(&SCHEMA, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(internal)?;
+
+        Ok(Response::new(schema))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let range = OrdersTicket::parse(&request.into_inner().ticket)?.range();
+
+        let batches = stream::try_unfold(
+            (self.store.clone(), range),
+            |(store, mut range)| async move {
+                let page = store
+                    .get_orders_page(range.filter.clone(), range.after.clone(), BATCH_ROWS, false)
+                    .await?;
+
+                Ok::<_, anyhow::Error>(range.batch(page)?.map(|batch| (batch, (store, range))))
+            },
+        )
+        .map_err(|err| {
+            error!("Arrow Flight request failed: {err}");
+
+            FlightError::ExternalError(err.into())
+        });
+
+        let data = FlightDataEncoderBuilder::new()
+            .with_schema(SCHEMA.clone())
+            .build(batches)
+            .map_err(Status::from);
+
+        Ok(Response::new(data.boxed()))
+    }
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("No handshake is needed"))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("Flights are served at once"))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("The orders are read only"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("No action is supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("The orders are read only"))
+    }
+}
+
+/// A flight served by this server, its ticket being redeemed at the same address
+fn flight_info(descriptor: FlightDescriptor, ticket: &[u8]) -> Result<FlightInfo, Status> {
+    Ok(FlightInfo::new()
+        .try_with_schema(&SCHEMA)
+        .map_err(internal)?
+        .with_descriptor(descriptor)
+        .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(ticket.to_vec()))))
+}
+
+fn internal(err: impl std::fmt::Display) -> Status {
+    error!("Arrow Flight request failed: {err}");
+
+    Status::internal(err.to_string())
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::Deserialize;
+use tokio::task::spawn_blocking;
+use tracing::{error, info};
+
+use crate::{
+    args::Args,
+    db::{get_unnormalized_fiats, insert_fx_rates, normalize_orders},
+    scheduler::{Schedule, spawn_job},
+};
+
+/// Daily rates of a time series, as units of `base` per unit of each currency
+#[derive(Debug, Deserialize)]
+struct TimeSeries {
+    rates: HashMap<NaiveDate, HashMap<String, f64>>,
+}
+
+/// Fetches the daily rates of the stored fiat currencies at startup then every
+/// hour, and converts the fiat amounts of the orders into the base currency
+pub fn spawn_fx(args: &Args, client: reqwest::Client) {
+    let Some(base) = args.fx_base.clone().map(|base| base.to_uppercase()) else {
+        return;
+    };
+
+    let persist_path = args.persist_path.clone();
+    let url = args.fx_url.trim_end_matches('/').to_string();
+    let job = move |_: DateTime<Utc>, _: DateTime<Utc>| {
+        let persist_path = persist_path.clone();
+        let base = base.clone();
+        let client = client.clone();
+        let url = url.clone();
+
+        async move {
+            let normalized = update(&persist_path, &base, &client, &url).await?;
+
+            anyhow::Ok(format!("{normalized} orders converted to {base}"))
+        }
+    };
+
+    let startup = job.clone();
+
+    tokio::spawn(async move {
+        let now = Utc::now();
+
+        match startup(now, now).await {
+            Ok(outcome) => info!("FX normalization: {outcome}"),
+            Err(err) => error!("FX normalization failed: {err}"),
+        }
+    });
+
+    spawn_job("FX normalization".to_string(), Schedule::Hourly, job);
+}
+
+async fn update(
+    persist_path: &str,
+    base: &str,
+    client: &reqwest::Client,
+    url: &str,
+) -> anyhow::Result<usize> {
+    let (since, fiats) = {
+        let persist_path = persist_path.to_string();
+        let base = base.to_string();
+
+        spawn_blocking(move || get_unnormalized_fiats(&persist_path, &base)).await??
+    };
+
+    if let Some(since) = since
+        && !fiats.is_empty()
+    {
+        // Days without ECB rates use the previous ones, so the series starts a week earlier
+        let start = since - Days::new(7);
+        let series = client
+            .get(format!("{url}/{start}.."))
+            .query(&[("from", base), ("to", &fiats.join(","))])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TimeSeries>()
+            .await?;
+
+        // The API quotes each currency per unit of base, the table stores the opposite
+        let rates = series
+            .rates
+            .into_iter()
+            .flat_map(|(day, rates)| {
+                rates
+                    .into_iter()
+                    .filter(|(_, rate)| *rate > 0.0)
+                    .map(move |(fiat, rate)| (day, fiat, 1.0 / rate))
+            })
+            .collect::<Vec<_>>();
+
+        let persist_path = persist_path.to_string();
+        let base = base.to_string();
+
+        spawn_blocking(move || insert_fx_rates(&persist_path, &base, &rates)).await??;
+    }
+
+    let persist_path = persist_path.to_string();
+    let base = base.to_string();
+
+    spawn_blocking(move || normalize_orders(&persist_path, &base)).await?
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+/// Weight given to the latest sample of the order rate
+const SMOOTHING: f64 = 0.1;
+
+/// A fetch whose whole window was made of new orders, meaning older ones may
+/// have scrolled out of the API window since the previous fetch
+#[derive(Debug, Clone, Copy)]
+pub struct Gap {
+    pub since_previous_fetch: Duration,
+    pub window_size: usize,
+    pub estimated_missed: u64,
+}
+
+/// Period of rapid polling that followed a gap, to miss as few orders as
+/// possible while the market is busy
+#[derive(Debug, Clone, Copy)]
+pub struct Burst {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Fetches made at the burst interval
+    pub fetches: u64,
+    /// Gaps detected during the burst, the one that started it included
+    pub gaps: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct GapTracker {
+    previous_fetch: Option<Instant>,
+    /// Smoothed number of new orders per second, from the fetches without gap
+    order_rate: Option<f64>,
+}
+
+impl GapTracker {
+    pub fn observe(&mut self, new_orders: usize, window_size: usize) -> Option<Gap> {
+        let now = Instant::now();
+        let previous_fetch = self.previous_fetch.replace(now)?;
+        let elapsed = now - previous_fetch;
+
+        if window_size == 0 || new_orders < window_size {
+            // Only complete windows tell the actual rate, a full window is a lower bound
+            let rate = new_orders as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+
+            self.order_rate = Some(match self.order_rate {
+                Some(order_rate) => order_rate + SMOOTHING * (rate - order_rate),
+                None => rate,
+            });
+
+            return None;
+        }
+
+        let expected = self.order_rate.unwrap_or_default() * elapsed.as_secs_f64();
+
+        Some(Gap {
+            since_previous_fetch: elapsed,
+            window_size,
+            estimated_missed: (expected - window_size as f64).max(0.0).round() as u64,
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use async_graphql::{
+    Context, EmptyMutation, Object, Schema, SimpleObject, Subscription,
+    connection::{Connection, Edge},
+};
+use async_graphql_axum::{GraphQL, GraphQLSubscription};
+use axum::Router;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    db::{OrderCursor, OrderFilter, PairSummary},
+    export::Pair,
+    feed::{self, OrderFeed},
+    fetch::Order,
+    indicators::{Candle, Timeframe},
+    store::Store,
+};
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Queries at `/graphql`, subscriptions over WebSocket at `/graphql/ws`
+pub fn router(store: Store, feed: OrderFeed) -> Router {
+    let schema = Schema::build(Query, EmptyMutation, Subscription)
+        .data(store)
+        .data(feed)
+        .finish();
+
+    Router::new()
+        .route_service("/graphql", GraphQL::new(schema.clone()))
+        .route_service("/graphql/ws", GraphQLSubscription::new(schema))
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Order")]
+struct OrderNode {
+    created_at: DateTime<Utc>,
+    #[graphql(name = "type")]
+    ty: String,
+    blockchain: String,
+    crypto_amount: f64,
+    crypto_symbol: String,
+    fiat_amount: f64,
+    fiat_price: f64,
+    fiat_symbol: String,
+}
+
+impl OrderNode {
+    fn new(created_at: DateTime<Utc>, order: &Order) -> Self {
+        Self {
+            created_at,
+            ty: order.ty.to_string(),
+            blockchain: order.blockchain.clone(),
+            crypto_amount: order.crypto_amount,
+            crypto_symbol: order.crypto_symbol.clone(),
+            fiat_amount: order.fiat_amount,
+            fiat_price: order.fiat_price,
+            fiat_symbol: order.fiat_symbol.clone(),
+        }
+    }
+}
+
+struct Query;
+
+#[Object]
+impl Query {
+    /// Stored orders in creation order, `first` of them after the `after` cursor
+    async fn orders(
+        &self,
+        ctx: &Context<'_>,
+        pair: Option<String>,
+        #[graphql(name = "type")] ty: Option<String>,
+        min_fiat_amount: Option<f64>,
+        after: Option<String>,
+        first: Option<usize>,
+    ) -> async_graphql::Result<Connection<String, OrderNode>> {
+        let filter = order_filter(pair, ty, min_fiat_amount)?;
+        let after = after
+            .map(|after| after.parse::<OrderCursor>())
+            .transpose()?;
+        let first = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+        // One more order tells whether there is a next page
+        let mut orders = ctx
+            .data::<Store>()?
+            .get_orders_page(filter, after.clone(), first + 1, false)
+            .await?;
+        let has_next_page = orders.len() > first;
+        orders.truncate(first);
+
+        let mut connection = Connection::new(after.is_some(), has_next_page);
+
+        connection
+            .edges
+            .extend(orders.into_iter().map(|(cursor, order)| {
+                Edge::new(
+                    cursor.to_string(),
+                    OrderNode::new(cursor.created_at, &order),
+                )
+            }));
+
+        Ok(connection)
+    }
+
+    /// Candles of `pair` between `from` and `to`, now by default
+    async fn candles(
+        &self,
+        ctx: &Context<'_>,
+        pair: String,
+        #[graphql(desc = "1m, 5m, 15m, 1h, 4h or 1d")] timeframe: String,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+    ) -> async_graphql::Result<Vec<Candle>> {
+        let pair = pair.parse::<Pair>()?;
+        let timeframe = Timeframe::from_str(&timeframe, true)?;
+
+        Ok(ctx
+            .data::<Store>()?
+            .get_candles(
+                pair,
+                timeframe.duration(),
+                from,
+                to.unwrap_or_else(Utc::now),
+            )
+            .await?)
+    }
+
+    /// Activity per pair between `from` and `to`, now by default
+    async fn stats(
+        &self,
+        ctx: &Context<'_>,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+    ) -> async_graphql::Result<Vec<PairSummary>> {
+        Ok(ctx
+            .data::<Store>()?
+            .get_summary(from, to.unwrap_or_else(Utc::now))
+            .await?)
+    }
+}
+
+struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    /// New orders matching the filter, as the collector receives them
+    async fn new_orders(
+        &self,
+        ctx: &Context<'_>,
+        pair: Option<String>,
+        #[graphql(name = "type")] ty: Option<String>,
+        min_fiat_amount: Option<f64>,
+    ) -> async_graphql::Result<impl Stream<Item = OrderNode> + use<>> {
+        let filter = order_filter(pair, ty, min_fiat_amount)?;
+
+        Ok(feed::subscribe(ctx.data::<OrderFeed>()?, filter)
+            .map(|feed_order| OrderNode::new(feed_order.received_at, &feed_order.order)))
+    }
+}
+
+fn order_filter(
+    pair: Option<String>,
+    ty: Option<String>,
+    min_fiat_amount: Option<f64>,
+) -> anyhow::Result<OrderFilter> {
+    Ok(OrderFilter {
+        pair: pair.map(|pair| pair.parse()).transpose()?,
+        ty: ty.map(|ty| ty.parse()).transpose()?,
+        min_fiat_amount,
+    })
+}
@@ -0,0 +1,158 @@
+use std::{net::SocketAddr, pin::Pin};
+
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
+use tonic::{Request, Response, Status, transport::Server};
+use tracing::{error, info};
+
+use crate::{
+    db::{OrderFilter, PairSummary},
+    feed::{self, OrderFeed},
+    fetch::Order,
+    store::Store,
+};
+
+mod proto {
+    tonic::include_proto!("nash_stats");
+}
+
+use proto::nash_stats_server::{NashStats, NashStatsServer};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 10_000;
+
+struct GrpcService {
+    store: Store,
+    feed: OrderFeed,
+}
+
+pub fn spawn_grpc(addr: SocketAddr, store: Store, feed: OrderFeed) {
+    let service = NashStatsServer::new(GrpcService { store, feed });
+
+    info!("gRPC server listening on {addr}");
+
+    tokio::spawn(async move {
+        if let Err(err) = Server::builder().add_service(service).serve(addr).await {
+            error!("gRPC server failed: {err}");
+        }
+    });
+}
+
+#[tonic::async_trait]
+impl NashStats for GrpcService {
+    type WatchOrdersStream = Pin<Box<dyn Stream<Item = Result<proto::Order, Status>> + Send>>;
+
+    async fn list_orders(
+        &self,
+        request: Request<proto::ListOrdersRequest>,
+    ) -> Result<Response<proto::ListOrdersResponse>, Status> {
+        let request = request.into_inner();
+        let filter = order_filter(request.filter)?;
+        let after = request
+            .after
+            .map(timestamp)
+            .transpose()?
+            .unwrap_or(DateTime::UNIX_EPOCH);
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+        let orders = self
+            .store
+            .get_orders_after(after, filter, limit as usize)
+            .await
+            .map_err(internal)?;
+
+        Ok(Response::new(proto::ListOrdersResponse {
+            orders: orders
+                .iter()
+                .map(|(created_at, order)| proto_order(*created_at, order))
+                .collect(),
+        }))
+    }
+
+    async fn get_stats(
+        &self,
+        request: Request<proto::GetStatsRequest>,
+    ) -> Result<Response<proto::GetStatsResponse>, Status> {
+        let request = request.into_inner();
+        let from = timestamp(request.from)?;
+        let to = request.to.map(timestamp).transpose()?.unwrap_or(Utc::now());
+
+        let pairs = self.store.get_summary(from, to).await.map_err(internal)?;
+
+        Ok(Response::new(proto::GetStatsResponse {
+            pairs: pairs.into_iter().map(proto_pair_stats).collect(),
+        }))
+    }
+
+    async fn watch_orders(
+        &self,
+        request: Request<proto::WatchOrdersRequest>,
+    ) -> Result<Response<Self::WatchOrdersStream>, Status> {
+        let filter = order_filter(request.into_inner().filter)?;
+        let orders = feed::subscribe(&self.feed, filter)
+            .map(|feed_order| Ok(proto_order(feed_order.received_at, &feed_order.order)));
+
+        Ok(Response::new(Box::pin(orders)))
+    }
+}
+
+fn order_filter(filter: Option<proto::OrderFilter>) -> Result<OrderFilter, Status> {
+    let filter = filter.unwrap_or_default();
+
+    Ok(OrderFilter {
+        pair: filter
+            .pair
+            .map(|pair| pair.parse())
+            .transpose()
+            .map_err(invalid)?,
+        ty: filter
+            .r#type
+            .map(|ty| ty.parse())
+            .transpose()
+            .map_err(invalid)?,
+        min_fiat_amount: filter.min_fiat_amount,
+    })
+}
+
+fn timestamp(millis: i64) -> Result<DateTime<Utc>, Status> {
+    DateTime::from_timestamp_millis(millis)
+        .ok_or_else(|| Status::invalid_argument(format!("Timestamp {millis} out of range")))
+}
+
+fn proto_order(created_at: DateTime<Utc>, order: &Order) -> proto::Order {
+    proto::Order {
+        created_at: created_at.timestamp_millis(),
+        r#type: order.ty.to_string(),
+        blockchain: order.blockchain.clone(),
+        crypto_amount: order.crypto_amount,
+        crypto_symbol: order.crypto_symbol.clone(),
+        fiat_amount: order.fiat_amount,
+        fiat_price: order.fiat_price,
+        fiat_symbol: order.fiat_symbol.clone(),
+    }
+}
+
+fn proto_pair_stats(pair: PairSummary) -> proto::PairStats {
+    proto::PairStats {
+        crypto_symbol: pair.crypto_symbol,
+        fiat_symbol: pair.fiat_symbol,
+        orders: pair.orders,
+        buys: pair.buys,
+        sells: pair.sells,
+        crypto_volume: pair.crypto_volume,
+        fiat_volume: pair.fiat_volume,
+        avg_price: pair.avg_price,
+        largest_fiat_amount: pair.largest_fiat_amount,
+        base_fiat_volume: pair.base_fiat_volume,
+    }
+}
+
+fn invalid(err: anyhow::Error) -> Status {
+    Status::invalid_argument(err.to_string())
+}
+
+fn internal(err: anyhow::Error) -> Status {
+    error!("gRPC request failed: {err}");
+
+    Status::internal(err.to_string())
+}
@@ -0,0 +1,41 @@
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Collector state reported by the health checks
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    pub healthy: bool,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_insert: Option<DateTime<Utc>>,
+}
+
+pub type SharedHealth = Arc<RwLock<Health>>;
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            healthy: true,
+            last_success: None,
+            last_insert: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    #[serde(flatten)]
+    pub health: Health,
+    /// No fetch succeeded for longer than `--max-fetch-silence`
+    pub fetch_stale: bool,
+    pub db_accessible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_error: Option<String>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.health.healthy && !self.fetch_stale && self.db_accessible
+    }
+}
@@ -0,0 +1,523 @@
+use std::{fmt::Write, net::SocketAddr, time::Duration};
+
+use axum::{
+    Json, Router,
+    extract::{OriginalUri, Query, State},
+    http::{StatusCode, Uri, header::CONTENT_TYPE},
+    response::IntoResponse,
+    routing::get,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{net::TcpListener, task::spawn_blocking};
+use tracing::{error, info, warn};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+#[cfg(feature = "graphql")]
+use crate::graphql;
+use crate::{
+    atom,
+    auth::{HttpAccess, Scope},
+    dashboard,
+    db::{OrderCursor, OrderFilter, PairSummary, PriceLevel, VolumeProfile, check_connection},
+    export::Pair,
+    feed::OrderFeed,
+    fetch::Order,
+    health::{HealthReport, SharedHealth},
+    indicators::{Candle, Timeframe},
+    store::Store,
+    ws,
+};
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Orders of the Atom feed, only the notable ones being worth following
+const FEED_ENTRIES: usize = 50;
+const DEFAULT_FEED_MIN_FIAT_AMOUNT: f64 = 10_000.0;
+
+const DEFAULT_PROFILE_LEVELS: usize = 20;
+const MAX_PROFILE_LEVELS: usize = 500;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "nash-stats"),
+    paths(healthz, orders, feed, stats, candles, profile),
+    components(schemas(
+        OrdersPage,
+        OrderItem,
+        SortOrder,
+        PairSummary,
+        Candle,
+        VolumeProfile,
+        PriceLevel
+    ))
+)]
+struct ApiDoc;
+
+#[derive(Clone)]
+struct AppState {
+    persist_path: String,
+    health: SharedHealth,
+    store: Store,
+    /// Time without a successful fetch after which the collector is unhealthy
+    max_fetch_silence: Duration,
+    started_at: DateTime<Utc>,
+}
+
+pub async fn spawn_server(
+    addr: SocketAddr,
+    persist_path: &str,
+    health: SharedHealth,
+    store: Store,
+    access: HttpAccess,
+    feed: OrderFeed,
+    max_fetch_silence: Duration,
+) -> anyhow::Result<()> {
+    if access.is_open() && !addr.ip().is_loopback() {
+        warn!("HTTP server exposed on {addr} without any API token");
+    }
+
+    let state = AppState {
+        persist_path: persist_path.to_string(),
+        health,
+        store: store.clone(),
+        max_fetch_silence,
+        started_at: Utc::now(),
+    };
+
+    let app = Router::new()
+        .merge(
+            access.protect(
+                Router::new()
+                    .route("/orders", get(orders))
+                    .route("/feed.atom", get(feed)),
+                Scope::Orders,
+            ),
+        )
+        .merge(
+            access.protect(
+                Router::new()
+                    .route("/stats", get(stats))
+                    .route("/candles", get(candles))
+                    .route("/profile", get(profile)),
+                Scope::Stats,
+            ),
+        )
+        .merge(access.protect(
+            Router::new().route("/openapi.json", get(openapi)),
+            Scope::Docs,
+        ))
+        .route("/healthz", get(healthz))
+        .with_state(state)
+        .merge(dashboard::router())
+        .merge(
+            access.protect(
+                Router::new()
+                    .route("/ws", get(ws::ws))
+                    .with_state(feed.clone()),
+                Scope::Orders,
+            ),
+        );
+
+    #[cfg(feature = "graphql")]
+    let app = app.merge(access.protect(graphql::router(store, feed), Scope::Graphql));
+
+    let app = match access.cors()? {
+        Some(cors) => app.layer(cors),
+        None => app,
+    };
+
+    let listener = TcpListener::bind(addr).await?;
+
+    info!("HTTP server listening on {addr}");
+
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            error!("HTTP server failed: {err}");
+        }
+    });
+
+    Ok(())
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Health of the collector and of its database
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    responses(
+        (status = 200, description = "The collector is healthy"),
+        (status = 503, description = "The collector or its database is unhealthy, or no fetch succeeded for longer than --max-fetch-silence")
+    )
+)]
+async fn healthz(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let health = state
+        .health
+        .read()
+        .map(|health| health.clone())
+        .unwrap_or_default();
+
+    // Without any successful fetch yet, the silence counts from the start
+    let fetch_stale = TimeDelta::from_std(state.max_fetch_silence).is_ok_and(|max_silence| {
+        Utc::now() - health.last_success.unwrap_or(state.started_at) > max_silence
+    });
+
+    let db_check = spawn_blocking(move || check_connection(&state.persist_path))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+
+    let report = HealthReport {
+        health,
+        fetch_stale,
+        db_accessible: db_check.is_ok(),
+        db_error: db_check.err().map(|err| err.to_string()),
+    };
+
+    let status = if report.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct OrdersQuery {
+    /// Pair of the orders, like `BTC/USD`
+    pair: Option<String>,
+    /// `buy` or `sell`
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    ty: Option<String>,
+    min_fiat_amount: Option<f64>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    /// Orders per page, 100 by default and 1000 at most
+    limit: Option<usize>,
+    /// By creation time, `asc` by default
+    sort: Option<SortOrder>,
+}
+
+/// A page of orders
+#[derive(Debug, Serialize, ToSchema)]
+struct OrdersPage {
+    orders: Vec<OrderItem>,
+    /// Cursor of the next page, unset on the last one
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrderItem {
+    created_at: DateTime<Utc>,
+    #[serde(rename = "type")]
+    #[schema(rename = "type")]
+    ty: String,
+    blockchain: String,
+    crypto_amount: f64,
+    crypto_symbol: String,
+    fiat_amount: f64,
+    fiat_price: f64,
+    fiat_symbol: String,
+}
+
+impl OrderItem {
+    pub fn new(created_at: DateTime<Utc>, order: &Order) -> Self {
+        Self {
+            created_at,
+            ty: order.ty.to_string(),
+            blockchain: order.blockchain.clone(),
+            crypto_amount: order.crypto_amount,
+            crypto_symbol: order.crypto_symbol.clone(),
+            fiat_amount: order.fiat_amount,
+            fiat_price: order.fiat_price,
+            fiat_symbol: order.fiat_symbol.clone(),
+        }
+    }
+}
+
+/// Stored orders matching the filters, a page at a time
+#[utoipa::path(
+    get,
+    path = "/orders",
+    params(OrdersQuery),
+    responses(
+        (status = 200, description = "A page of orders", body = OrdersPage),
+        (status = 400, description = "Invalid filter or cursor", body = String)
+    )
+)]
+async fn orders(
+    State(state): State<AppState>,
+    Query(query): Query<OrdersQuery>,
+) -> Result<Json<OrdersPage>, (StatusCode, String)> {
+    let bad_request = |err: anyhow::Error| (StatusCode::BAD_REQUEST, err.to_string());
+
+    let filter = OrderFilter {
+        pair: query
+            .pair
+            .map(|pair| pair.parse())
+            .transpose()
+            .map_err(bad_request)?,
+        ty: query
+            .ty
+            .map(|ty| ty.parse())
+            .transpose()
+            .map_err(bad_request)?,
+        min_fiat_amount: query.min_fiat_amount,
+    };
+    let cursor = query
+        .cursor
+        .map(|cursor| cursor.parse::<OrderCursor>())
+        .transpose()
+        .map_err(bad_request)?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let descending = matches!(query.sort.unwrap_or_default(), SortOrder::Desc);
+
+    // One more order tells whether there is a next page
+    let mut orders = state
+        .store
+        .get_orders_page(filter, cursor, limit + 1, descending)
+        .await
+        .map_err(internal_error)?;
+    let has_next_page = orders.len() > limit;
+    orders.truncate(limit);
+
+    Ok(Json(OrdersPage {
+        next_cursor: has_next_page
+            .then(|| orders.last().map(|(cursor, _)| cursor.to_string()))
+            .flatten(),
+        orders: orders
+            .iter()
+            .map(|(cursor, order)| OrderItem::new(cursor.created_at, order))
+            .collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeedQuery {
+    /// Pair of the orders, like `BTC/USD`
+    pair: Option<String>,
+    /// `buy` or `sell`
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    ty: Option<String>,
+    /// 10000 by default
+    min_fiat_amount: Option<f64>,
+}
+
+/// Atom feed of the latest notable orders, for feed readers
+#[utoipa::path(
+    get,
+    path = "/feed.atom",
+    params(FeedQuery),
+    responses(
+        (status = 200, description = "Atom feed, newest orders first", content_type = "application/atom+xml", body = String),
+        (status = 400, description = "Invalid filter", body = String)
+    )
+)]
+async fn feed(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<FeedQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let bad_request = |err: anyhow::Error| (StatusCode::BAD_REQUEST, err.to_string());
+
+    let filter = OrderFilter {
+        pair: query
+            .pair
+            .map(|pair| pair.parse())
+            .transpose()
+            .map_err(bad_request)?,
+        ty: query
+            .ty
+            .map(|ty| ty.parse())
+            .transpose()
+            .map_err(bad_request)?,
+        min_fiat_amount: Some(
+            query
+                .min_fiat_amount
+                .unwrap_or(DEFAULT_FEED_MIN_FIAT_AMOUNT),
+        ),
+    };
+
+    let mut title = format!(
+        "Nash orders of {} or more",
+        filter.min_fiat_amount.unwrap_or_default()
+    );
+
+    if let Some(pair) = &filter.pair {
+        write!(title, " on {pair}").ok();
+    }
+
+    if let Some(ty) = &filter.ty {
+        write!(title, ", {ty}s only").ok();
+    }
+
+    let orders = state
+        .store
+        .get_orders_page(filter, None, FEED_ENTRIES, true)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        [(CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        atom::render(&title, &feed_url(&uri), &orders),
+    ))
+}
+
+/// URL of the feed as requested, without the token a feed reader may pass
+fn feed_url(uri: &Uri) -> String {
+    let params = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("access_token="))
+        .collect::<Vec<_>>();
+
+    if params.is_empty() {
+        uri.path().to_string()
+    } else {
+        format!("{}?{}", uri.path(), params.join("&"))
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StatsQuery {
+    /// 24 hours ago by default
+    from: Option<DateTime<Utc>>,
+    /// Now by default
+    to: Option<DateTime<Utc>>,
+}
+
+/// Activity per pair between `from` and `to`
+#[utoipa::path(
+    get,
+    path = "/stats",
+    params(StatsQuery),
+    responses((status = 200, description = "Activity per pair, by fiat volume", body = Vec<PairSummary>))
+)]
+async fn stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Vec<PairSummary>>, (StatusCode, String)> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - TimeDelta::days(1));
+
+    state
+        .store
+        .get_summary(from, to)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CandlesQuery {
+    /// Pair of the candles, like `BTC/USD`
+    pair: String,
+    /// `1m`, `5m`, `15m`, `1h`, `4h` or `1d`
+    #[param(value_type = String)]
+    timeframe: Timeframe,
+    /// 24 hours ago by default
+    from: Option<DateTime<Utc>>,
+    /// Now by default
+    to: Option<DateTime<Utc>>,
+}
+
+/// Candles of a pair between `from` and `to`
+#[utoipa::path(
+    get,
+    path = "/candles",
+    params(CandlesQuery),
+    responses(
+        (status = 200, description = "Candles, oldest first", body = Vec<Candle>),
+        (status = 400, description = "Invalid pair", body = String)
+    )
+)]
+async fn candles(
+    State(state): State<AppState>,
+    Query(query): Query<CandlesQuery>,
+) -> Result<Json<Vec<Candle>>, (StatusCode, String)> {
+    let pair = query
+        .pair
+        .parse::<Pair>()
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - TimeDelta::days(1));
+
+    state
+        .store
+        .get_candles(pair, query.timeframe.duration(), from, to)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ProfileQuery {
+    /// Pair of the profile, like `BTC/USD`
+    pair: String,
+    /// Number of price levels, 20 by default and 500 at most
+    levels: Option<usize>,
+    /// 24 hours ago by default
+    from: Option<DateTime<Utc>>,
+    /// Now by default
+    to: Option<DateTime<Utc>>,
+}
+
+/// Volume of a pair by price level between `from` and `to`
+#[utoipa::path(
+    get,
+    path = "/profile",
+    params(ProfileQuery),
+    responses(
+        (status = 200, description = "Volume profile, lowest price level first", body = VolumeProfile),
+        (status = 400, description = "Invalid pair", body = String)
+    )
+)]
+async fn profile(
+    State(state): State<AppState>,
+    Query(query): Query<ProfileQuery>,
+) -> Result<Json<VolumeProfile>, (StatusCode, String)> {
+    let pair = query
+        .pair
+        .parse::<Pair>()
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let levels = query
+        .levels
+        .unwrap_or(DEFAULT_PROFILE_LEVELS)
+        .clamp(1, MAX_PROFILE_LEVELS);
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - TimeDelta::days(1));
+
+    state
+        .store
+        .get_volume_profile(pair, levels, from, to)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+fn internal_error(err: anyhow::Error) -> (StatusCode, String) {
+    error!("HTTP request failed: {err}");
+
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
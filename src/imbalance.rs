@@ -0,0 +1,153 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    args::Args,
+    export::Pair,
+    fetch::{Order, OrderType},
+};
+
+/// Buy and sell fiat volumes of a pair over the rolling window
+#[derive(Debug, Clone)]
+pub struct Imbalance {
+    pub pair: Pair,
+    pub orders: usize,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+}
+
+impl Imbalance {
+    /// Share of the fiat volume that was bought, from 0 to 1
+    pub fn buy_share(&self) -> f64 {
+        let volume = self.buy_volume + self.sell_volume;
+
+        if volume > 0.0 {
+            self.buy_volume / volume
+        } else {
+            0.5
+        }
+    }
+}
+
+/// Keeps the orders of the rolling window of every pair, to raise a single
+/// alert while a pair stays one-sided
+#[derive(Debug)]
+pub struct ImbalanceTracker {
+    window: Duration,
+    alert_threshold: Option<f64>,
+    min_orders: usize,
+    orders: HashMap<Pair, VecDeque<(DateTime<Utc>, OrderType, f64)>>,
+    alerting: HashSet<Pair>,
+}
+
+impl ImbalanceTracker {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            window: args.imbalance_window,
+            alert_threshold: args.imbalance_alert_threshold,
+            min_orders: args.imbalance_min_orders,
+            orders: HashMap::new(),
+            alerting: HashSet::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Adds the new orders and drops the ones out of the window, returning the
+    /// imbalance of every pair that changed
+    pub fn observe(&mut self, new_orders: &[&Order], at: DateTime<Utc>) -> Vec<Imbalance> {
+        let mut changed = HashSet::new();
+
+        for order in new_orders {
+            let pair = Pair::of(order);
+
+            self.orders.entry(pair.clone()).or_default().push_back((
+                at,
+                order.ty.clone(),
+                order.fiat_amount,
+            ));
+            changed.insert(pair);
+        }
+
+        let start = TimeDelta::from_std(self.window)
+            .ok()
+            .and_then(|window| at.checked_sub_signed(window))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        for (pair, orders) in &mut self.orders {
+            while orders
+                .front()
+                .is_some_and(|(seen_at, _, _)| *seen_at < start)
+            {
+                orders.pop_front();
+                changed.insert(pair.clone());
+            }
+        }
+
+        self.orders.retain(|_, orders| !orders.is_empty());
+
+        let mut imbalances = changed
+            .into_iter()
+            .map(|pair| {
+                let orders = self.orders.get(&pair);
+                let volume = |ty: OrderType| {
+                    orders
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, order_ty, _)| *order_ty == ty)
+                        .map(|(_, _, fiat_amount)| fiat_amount)
+                        .sum()
+                };
+
+                Imbalance {
+                    orders: orders.map(VecDeque::len).unwrap_or_default(),
+                    buy_volume: volume(OrderType::Buy),
+                    sell_volume: volume(OrderType::Sell),
+                    pair,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        imbalances.sort_by(|a, b| a.pair.cmp(&b.pair));
+
+        imbalances
+    }
+
+    /// Returns an alert message when the pair becomes one-sided beyond the threshold
+    pub fn alert(&mut self, imbalance: &Imbalance) -> Option<String> {
+        let threshold = self.alert_threshold?;
+        let buy_share = imbalance.buy_share();
+        let one_sided = imbalance.orders >= self.min_orders
+            && (buy_share >= threshold || 1.0 - buy_share >= threshold);
+
+        if !one_sided {
+            self.alerting.remove(&imbalance.pair);
+
+            return None;
+        }
+
+        if !self.alerting.insert(imbalance.pair.clone()) {
+            return None;
+        }
+
+        let (share, side) = if buy_share >= 0.5 {
+            (buy_share, "buys")
+        } else {
+            (1.0 - buy_share, "sells")
+        };
+
+        Some(format!(
+            "{}: {:.0}% of the fiat volume over the last {} is {side} ({} orders)",
+            imbalance.pair,
+            share * 100.0,
+            humantime::format_duration(self.window),
+            imbalance.orders
+        ))
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Number of candles of the simple and exponential moving averages
+pub const MA_PERIOD: usize = 20;
+
+pub const RSI_PERIOD: usize = 14;
+
+pub const MACD_FAST_PERIOD: usize = 12;
+pub const MACD_SLOW_PERIOD: usize = 26;
+pub const MACD_SIGNAL_PERIOD: usize = 9;
+
+/// Number of candles before the requested period needed for every indicator to settle
+pub const WARM_UP: usize = MACD_SLOW_PERIOD + MACD_SIGNAL_PERIOD;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, ValueEnum)]
+pub enum Timeframe {
+    #[serde(rename = "1m")]
+    #[value(name = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    #[value(name = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    #[value(name = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    #[value(name = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    #[value(name = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    #[value(name = "1d")]
+    OneDay,
+}
+
+impl Timeframe {
+    pub fn duration(&self) -> TimeDelta {
+        match self {
+            Timeframe::OneMinute => TimeDelta::minutes(1),
+            Timeframe::FiveMinutes => TimeDelta::minutes(5),
+            Timeframe::FifteenMinutes => TimeDelta::minutes(15),
+            Timeframe::OneHour => TimeDelta::hours(1),
+            Timeframe::FourHours => TimeDelta::hours(4),
+            Timeframe::OneDay => TimeDelta::days(1),
+        }
+    }
+}
+
+impl Display for Timeframe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timeframe::OneMinute => write!(f, "1m"),
+            Timeframe::FiveMinutes => write!(f, "5m"),
+            Timeframe::FifteenMinutes => write!(f, "15m"),
+            Timeframe::OneHour => write!(f, "1h"),
+            Timeframe::FourHours => write!(f, "4h"),
+            Timeframe::OneDay => write!(f, "1d"),
+        }
+    }
+}
+
+/// Prices of the orders of a pair within one timeframe
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct Candle {
+    pub start: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// Indicators at the close of a candle, unset until enough candles are known
+#[derive(Debug, Clone, Serialize)]
+pub struct IndicatorPoint {
+    pub start: DateTime<Utc>,
+    pub close: f64,
+    pub sma: Option<f64>,
+    pub ema: Option<f64>,
+    pub rsi: Option<f64>,
+    pub macd: Option<f64>,
+    pub macd_signal: Option<f64>,
+    pub macd_histogram: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Indicator {
+    Close,
+    Sma,
+    Ema,
+    Rsi,
+    Macd,
+    MacdSignal,
+    MacdHistogram,
+}
+
+impl IndicatorPoint {
+    pub fn get(&self, indicator: Indicator) -> Option<f64> {
+        match indicator {
+            Indicator::Close => Some(self.close),
+            Indicator::Sma => self.sma,
+            Indicator::Ema => self.ema,
+            Indicator::Rsi => self.rsi,
+            Indicator::Macd => self.macd,
+            Indicator::MacdSignal => self.macd_signal,
+            Indicator::MacdHistogram => self.macd_histogram,
+        }
+    }
+}
+
+/// Computes every indicator over the close prices of consecutive candles
+pub fn compute(candles: &[Candle]) -> Vec<IndicatorPoint> {
+    let closes = candles
+        .iter()
+        .map(|candle| candle.close)
+        .collect::<Vec<_>>();
+    let sma = sma(&closes, MA_PERIOD);
+    let ema = ema(&closes, MA_PERIOD);
+    let rsi = rsi(&closes, RSI_PERIOD);
+    let fast = ema(&closes, MACD_FAST_PERIOD);
+    let slow = ema(&closes, MACD_SLOW_PERIOD);
+    let macd = fast
+        .iter()
+        .zip(&slow)
+        .map(|(fast, slow)| Some((*fast)? - (*slow)?))
+        .collect::<Vec<_>>();
+    let signal = ema_of_options(&macd, MACD_SIGNAL_PERIOD);
+
+    candles
+        .iter()
+        .enumerate()
+        .map(|(i, candle)| IndicatorPoint {
+            start: candle.start,
+            close: candle.close,
+            sma: sma[i],
+            ema: ema[i],
+            rsi: rsi[i],
+            macd: macd[i],
+            macd_signal: signal[i],
+            macd_histogram: macd[i].zip(signal[i]).map(|(macd, signal)| macd - signal),
+        })
+        .collect()
+}
+
+fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    (0..values.len())
+        .map(|i| {
+            (i + 1 >= period)
+                .then(|| values[i + 1 - period..=i].iter().sum::<f64>() / period as f64)
+        })
+        .collect()
+}
+
+/// Exponential moving average seeded with the simple average of the first `period` values
+fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    ema_of_options(
+        &values.iter().copied().map(Some).collect::<Vec<_>>(),
+        period,
+    )
+}
+
+fn ema_of_options(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    let k = 2.0 / (period as f64 + 1.0);
+    let mut seed = Vec::with_capacity(period);
+    let mut previous = None;
+
+    values
+        .iter()
+        .map(|value| {
+            let value = (*value)?;
+
+            previous = match previous {
+                Some(previous) => Some(previous + k * (value - previous)),
+                None => {
+                    seed.push(value);
+                    (seed.len() == period).then(|| seed.iter().sum::<f64>() / period as f64)
+                }
+            };
+
+            previous
+        })
+        .collect()
+}
+
+/// Relative strength index with Wilder's smoothing
+fn rsi(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut average_gain = 0.0;
+    let mut average_loss = 0.0;
+
+    (0..values.len())
+        .map(|i| {
+            if i == 0 {
+                return None;
+            }
+
+            let change = values[i] - values[i - 1];
+            let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+
+            if i <= period {
+                average_gain += gain / period as f64;
+                average_loss += loss / period as f64;
+            } else {
+                average_gain = (average_gain * (period - 1) as f64 + gain) / period as f64;
+                average_loss = (average_loss * (period - 1) as f64 + loss) / period as f64;
+            }
+
+            (i >= period).then(|| {
+                if average_loss == 0.0 {
+                    100.0
+                } else {
+                    100.0 - 100.0 / (1.0 + average_gain / average_loss)
+                }
+            })
+        })
+        .collect()
+}
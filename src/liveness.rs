@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+
+/// Tracks the fetch failures, to raise a single alert per outage
+#[derive(Debug)]
+pub struct Liveness {
+    max_consecutive_failures: u32,
+    max_silence: Duration,
+    consecutive_failures: u32,
+    last_success: Instant,
+    healthy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LivenessChange {
+    Down(String),
+    Recovered(String),
+}
+
+impl Liveness {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            max_consecutive_failures: args.max_consecutive_failures,
+            max_silence: args.max_fetch_silence,
+            consecutive_failures: 0,
+            last_success: Instant::now(),
+            healthy: true,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+
+    pub fn record_success(&mut self) -> Option<LivenessChange> {
+        let down_for = self.last_success.elapsed();
+
+        self.consecutive_failures = 0;
+        self.last_success = Instant::now();
+
+        if self.healthy {
+            return None;
+        }
+
+        self.healthy = true;
+
+        Some(LivenessChange::Recovered(format!(
+            "Fetching orders recovered after {}",
+            humantime::format_duration(Duration::from_secs(down_for.as_secs()))
+        )))
+    }
+
+    pub fn record_failure(&mut self, err: &anyhow::Error) -> Option<LivenessChange> {
+        self.consecutive_failures += 1;
+
+        if !self.healthy {
+            return None;
+        }
+
+        let silence = self.last_success.elapsed();
+
+        if self.consecutive_failures >= self.max_consecutive_failures || silence >= self.max_silence
+        {
+            self.healthy = false;
+
+            return Some(LivenessChange::Down(format!(
+                "Fetching orders failed {} times in a row, no successful fetch for {}: {err}",
+                self.consecutive_failures,
+                humantime::format_duration(Duration::from_secs(silence.as_secs()))
+            )));
+        }
+
+        None
+    }
+
+    /// Raises the alert once no fetch succeeded for too long, even without a
+    /// failure, while waiting out a rate limit or a schedule
+    pub fn check_silence(&mut self) -> Option<LivenessChange> {
+        let silence = self.last_success.elapsed();
+
+        if !self.healthy || silence < self.max_silence {
+            return None;
+        }
+
+        self.healthy = false;
+
+        Some(LivenessChange::Down(format!(
+            "No successful fetch for {}",
+            humantime::format_duration(Duration::from_secs(silence.as_secs()))
+        )))
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, bail};
+use tracing::warn;
+
+/// Lock file next to the database, preventing the collector and the writing
+/// commands from writing to it at the same time, released when dropped
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    pub fn acquire(persist_path: &str, force: bool) -> anyhow::Result<Self> {
+        let path = lock_path(persist_path);
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+
+                    return Ok(Self { path });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    let owner = fs::read_to_string(&path).unwrap_or_default();
+                    let owner = owner.trim();
+
+                    if !is_running(owner) {
+                        warn!("Removing the stale lock of process {owner} on {persist_path}");
+                    } else if force {
+                        warn!("Breaking the lock of process {owner} on {persist_path}");
+                    } else {
+                        bail!(
+                            "{persist_path} is locked by process {owner}, stop it or use --force if it isn't the one holding the lock"
+                        );
+                    }
+
+                    fs::remove_file(&path)
+                        .with_context(|| format!("Failed to remove {}", path.display()))?;
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to create {}", path.display()));
+                }
+            }
+        }
+    }
+
+    /// Whether a collector or a command writing to the database holds its lock
+    pub fn is_held(persist_path: &str) -> bool {
+        fs::read_to_string(lock_path(persist_path)).is_ok_and(|owner| is_running(owner.trim()))
+    }
+}
+
+/// Whether the process of the lock file still runs, a lock left by a process
+/// which crashed or by a previous container, where the PID may be ours, being
+/// stale
+fn is_running(owner: &str) -> bool {
+    // The owner may not have written its PID yet
+    let Ok(pid) = owner.parse::<libc::pid_t>() else {
+        return owner.is_empty();
+    };
+
+    if pid <= 0 || pid.unsigned_abs() == std::process::id() {
+        return false;
+    }
+
+    // Signal 0 only checks that the process exists, which it also does when
+    // not allowed to signal it
+    // SAFETY: kill doesn't access memory
+    let signaled = unsafe { libc::kill(pid, 0) } == 0;
+
+    signaled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+fn lock_path(persist_path: &str) -> PathBuf {
+    PathBuf::from(format!("{persist_path}.lock"))
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Failed to remove {}: {err}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,215 @@
+#[cfg(feature = "otlp")]
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+#[cfg(feature = "otlp")]
+use opentelemetry::trace::TracerProvider;
+#[cfg(feature = "otlp")]
+use opentelemetry_otlp::{SpanExporter, WithExportConfig, WithHttpConfig};
+#[cfg(feature = "otlp")]
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+use tracing::{Subscriber, level_filters::LevelFilter};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry,
+    fmt::{MakeWriter, layer},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    reload,
+    util::SubscriberInitExt,
+};
+
+use crate::args::Args;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogFormat {
+    Compact,
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogRotation {
+    Never,
+    Minutely,
+    Hourly,
+    Daily,
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(value: LogRotation) -> Self {
+        match value {
+            LogRotation::Never => Rotation::NEVER,
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+        }
+    }
+}
+
+/// Flushes the file logs and the exported spans when dropped
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+    console_filter: reload::Handle<EnvFilter, Registry>,
+    #[cfg(feature = "otlp")]
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl LogGuard {
+    /// Changes the level of the console logs, `RUST_LOG` still overriding it
+    pub fn set_level(&self, level: LevelFilter) -> anyhow::Result<()> {
+        self.console_filter.reload(console_filter(level))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "otlp")]
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if let Some(tracer_provider) = &self.tracer_provider
+            && let Err(err) = tracer_provider.shutdown()
+        {
+            eprintln!("Failed to export the last spans: {err}");
+        }
+    }
+}
+
+/// Sets up the console and file logs, and the span export, the returned guard
+/// must be kept alive for them to be flushed
+pub fn init(args: &Args) -> anyhow::Result<LogGuard> {
+    let (filter, filter_handle) = reload::Layer::new(console_filter(args.log_level));
+    let mut layers = vec![
+        format_layer(args.log_format, std::io::stdout)
+            .with_filter(filter)
+            .boxed(),
+    ];
+
+    let guard = match &args.log_file {
+        Some(path) if !args.no_log_file => {
+            let directory = path.parent().unwrap_or_else(|| Path::new("."));
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| anyhow!("Invalid log file {}", path.display()))?;
+
+            // Create a rolling file appender
+            let mut builder = RollingFileAppender::builder()
+                .rotation(args.log_rotation.into())
+                .filename_prefix(file_name);
+
+            if let Some(max_files) = args.log_max_files {
+                builder = builder.max_log_files(max_files);
+            }
+
+            // Create a layer that writes to the file
+            let (non_blocking, guard) = tracing_appender::non_blocking(builder.build(directory)?);
+
+            layers.push(
+                format_layer(args.log_format, non_blocking)
+                    .with_filter(args.log_file_level)
+                    .boxed(),
+            );
+
+            Some(guard)
+        }
+        _ => None,
+    };
+
+    #[cfg(feature = "otlp")]
+    let tracer_provider = otlp_tracer_provider(args)?;
+
+    #[cfg(feature = "otlp")]
+    if let Some(tracer_provider) = &tracer_provider {
+        layers.push(
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer_provider.tracer(env!("CARGO_PKG_NAME")))
+                // The spans of the fetch cycles are at the debug level to keep
+                // them out of the console logs
+                .with_filter(LevelFilter::DEBUG)
+                .boxed(),
+        );
+    }
+
+    tracing_subscriber::registry().with(layers).init();
+
+    Ok(LogGuard {
+        _file: guard,
+        console_filter: filter_handle,
+        #[cfg(feature = "otlp")]
+        tracer_provider,
+    })
+}
+
+fn console_filter(level: LevelFilter) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy()
+}
+
+#[cfg(feature = "otlp")]
+fn otlp_tracer_provider(args: &Args) -> anyhow::Result<Option<SdkTracerProvider>> {
+    let Some(endpoint) = &args.otlp_endpoint else {
+        return Ok(None);
+    };
+
+    let endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let headers = args
+        .otlp_headers
+        .iter()
+        .map(|header| {
+            header
+                .split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| anyhow!("Invalid OTLP header {header}, expected key=value"))
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+    // The exporter's blocking HTTP client can't be created from within the runtime
+    let exporter = std::thread::spawn(move || {
+        SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .with_headers(headers)
+            .build()
+    })
+    .join()
+    .map_err(|_| anyhow!("Failed to create the OTLP exporter"))??;
+
+    Ok(Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(env!("CARGO_PKG_NAME"))
+                    .build(),
+            )
+            .build(),
+    ))
+}
+
+fn format_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Compact => layer()
+            .compact()
+            .with_target(false)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Pretty => layer().pretty().with_writer(writer).boxed(),
+        LogFormat::Json => layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
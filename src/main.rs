@@ -1,81 +1,816 @@
-use std::{collections::HashSet, time::Duration};
+use std::{collections::HashMap, future::pending, pin::pin, time::Instant};
 
+use chrono::{DateTime, TimeDelta, Utc};
 use clap::Parser;
-use tokio::time::sleep;
-use tracing::{error, info, level_filters::LevelFilter, warn};
-use tracing_appender::rolling;
-use tracing_subscriber::{
-    EnvFilter, Layer, fmt::layer, layer::SubscriberExt, util::SubscriberInitExt,
+use tokio::{
+    select,
+    signal::{
+        ctrl_c,
+        unix::{SignalKind, signal},
+    },
+    sync::{broadcast, mpsc},
 };
+use tracing::{Instrument, debug, debug_span, error, info, warn};
 
 use crate::{
-    args::Args,
-    db::{get_latest_orders, init, insert_order},
-    fetch::fetch,
+    account::spawn_my_orders,
+    alerts::{AlertEngine, IndicatorAlerts},
+    anomaly::AnomalyDetector,
+    args::{Args, Command, CtlCommand},
+    buffer::WriteBuffer,
+    client::http_client,
+    collector::Collector,
+    config::Config,
+    control::{CollectorSignal, spawn_control_socket},
+    db::{NewOrder, create_parent_dir, database_size, init},
+    digest::spawn_digest,
+    fx::spawn_fx,
+    health::SharedHealth,
+    http::spawn_server,
+    imbalance::ImbalanceTracker,
+    liveness::LivenessChange,
+    lock::InstanceLock,
+    logging::LogGuard,
+    markets::{spawn_markets, spawn_tickers},
+    metrics::{Metrics, MetricsBackend},
+    numbers::{NumberFormat, OrderStyle},
+    reference::{PremiumTracker, spawn_reference_prices},
+    retention::spawn_retention,
+    scheduler::{spawn_exports, spawn_maintenance, spawn_rollup, spawn_summary_refresh},
+    script::OrderScript,
+    sink::{Cycle, Sink},
+    source::Source,
+    statsd::StatsdEmitter,
+    status::StatusSummary,
+    storage::ObjectStorage,
+    store::Store,
+    volatility::VolatilityTracker,
 };
 
+mod account;
+mod alerts;
+mod anomaly;
 mod args;
+mod atom;
+mod auth;
+mod batches;
+mod buffer;
+mod client;
+mod collector;
+mod commands;
+mod config;
+mod control;
+mod dashboard;
 mod db;
+mod dedup;
+mod digest;
+mod export;
+mod feed;
 mod fetch;
+#[cfg(feature = "flight")]
+mod flight;
+mod fx;
+mod gaps;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod health;
+mod http;
+mod imbalance;
+mod indicators;
+mod liveness;
+mod lock;
+mod logging;
+mod markets;
+mod metrics;
+mod migrations;
+mod numbers;
+mod pnl;
+mod polling;
+mod reference;
+mod retention;
+mod scheduler;
+mod script;
+mod sheets;
+mod sink;
+mod source;
+mod statsd;
+mod status;
+mod storage;
+mod store;
+mod systemd;
+mod volatility;
+mod ws;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Create a rolling file appender
-    let file_appender = rolling::never("/logs", "logs.txt");
-
-    // Create a layer that writes to the file
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-
-    tracing_subscriber::registry()
-        .with(
-            layer().compact().with_target(false).with_filter(
-                EnvFilter::builder()
-                    .with_default_directive(LevelFilter::INFO.into())
-                    .from_env_lossy(),
-            ),
+    let args = Args::parse();
+    let log_guard = logging::init(&args)?;
+
+    create_parent_dir(&args.persist_path)?;
+
+    // The collector and the commands writing to the database lock it. The
+    // reading commands open it read-only in between, DuckDB not letting other
+    // processes open the file while one writes to it, so each side retries
+    // until the other closes it
+    let writes = args.command.as_ref().is_none_or(Command::writes);
+    let _lock = (writes && !args.ephemeral && !args.dry_run)
+        .then(|| InstanceLock::acquire(&args.persist_path, args.force))
+        .transpose()?;
+
+    let session_gap = TimeDelta::from_std(args.session_gap)?;
+
+    if args.dry_run {
+        info!("Dry run, nothing will be written to the DB");
+    } else if matches!(
+        args.command,
+        Some(
+            Command::Migrate { .. }
+                | Command::Doctor
+                | Command::Config { .. }
+                | Command::Ctl { .. }
+                | Command::Init { .. }
+                | Command::Status
         )
-        .with(
-            layer()
-                .compact()
-                .with_target(false)
-                .with_writer(non_blocking)
-                .with_filter(LevelFilter::INFO),
+    ) {
+        // These commands inspect the DB as it is, before any migration is
+        // applied, or don't use it at all, or pick the DB themselves
+    } else if !writes && InstanceLock::is_held(&args.persist_path) {
+        // The holder of the lock keeps the schema up to date and is the only
+        // one writing, a reading command leaving the DB as it is
+    } else {
+        info!("Init DB");
+        init(&args.persist_path, session_gap)?;
+    }
+
+    if let Some(command) = &args.command {
+        return commands::run(command, &args).await;
+    }
+
+    let config = Config::load(args.config.as_deref())?;
+
+    if let Some(level) = config.logging.level {
+        log_guard.set_level(level)?;
+    }
+
+    let storage = ObjectStorage::from_args(&args)?;
+
+    spawn_exports(&args.persist_path, config.exports, storage.clone());
+    spawn_digest(&args)?;
+
+    if !args.dry_run {
+        spawn_summary_refresh(&args.persist_path, args.summary_schedule.clone());
+        spawn_rollup(&args.persist_path, args.rollup_schedule.clone());
+
+        if let Some(schedule) = &args.maintain_schedule {
+            spawn_maintenance(&args.persist_path, schedule.clone());
+        }
+    }
+
+    if let Some(retention_days) = args.retention_days
+        && !args.dry_run
+    {
+        spawn_retention(
+            &args.persist_path,
+            retention_days,
+            args.retention_schedule.clone(),
+            args.archive_dir.clone(),
+            storage,
+        );
+    }
+
+    let client = http_client(&args)?;
+    let mut sinks = Sink::from_args(&args, &config.notifiers, &client)?;
+    let numbers = NumberFormat::from_args(&args);
+
+    if !args.dry_run {
+        spawn_fx(&args, client.clone());
+
+        if let Some(every) = args.markets_interval {
+            spawn_markets(&args, client.clone(), every);
+        }
+
+        if let Some(every) = args.tickers_interval {
+            spawn_tickers(&args, client.clone(), every);
+        }
+
+        if let Some(api_key) = args.nash_api_key.clone() {
+            spawn_my_orders(&args, client.clone(), api_key, args.my_orders_interval);
+        }
+    }
+
+    let store = Store::new(&args.persist_path);
+    let (collected_tx, mut collected_rx) = mpsc::channel(16);
+    let (liveness_tx, mut liveness_rx) = mpsc::channel(16);
+    let (signals, _) = broadcast::channel(16);
+
+    for source in Source::from_args(&args, &client)? {
+        let latest_orders = match store
+            .get_latest_orders(source.name(), args.dedup_window)
+            .await
+        {
+            Ok(orders) => orders,
+            Err(err) if args.dry_run => {
+                warn!("No stored {} orders to compare with: {err}", source.name());
+                Vec::new()
+            }
+            Err(err) => return Err(err),
+        };
+
+        Collector::new(source, latest_orders, &args).spawn(
+            collected_tx.clone(),
+            liveness_tx.clone(),
+            signals.subscribe(),
+        );
+    }
+
+    drop(collected_tx);
+    drop(liveness_tx);
+
+    let mut metrics = Metrics::default();
+    let mut statsd = match args.metrics_backend {
+        MetricsBackend::Statsd => Some(StatsdEmitter::from_args(&args).await?),
+        MetricsBackend::Log => None,
+    };
+    let mut imbalances = ImbalanceTracker::from_args(&args);
+    let mut volatilities = VolatilityTracker::from_args(&args);
+    let mut anomalies = AnomalyDetector::from_args(&args);
+    let mut script = args.script.as_deref().map(OrderScript::load).transpose()?;
+    let mut alert_engine = AlertEngine::new(config.alerts.rules);
+    let mut indicator_alerts = (!config.alerts.indicator_rules.is_empty())
+        .then(|| IndicatorAlerts::new(config.alerts.indicator_rules));
+    let mut premiums = args
+        .reference_interval
+        .map(|every| spawn_reference_prices(&args, client.clone(), every))
+        .transpose()?
+        .map(|prices| PremiumTracker::new(prices, &args));
+    let mut write_buffer = WriteBuffer::from_args(&args).filter(|_| !args.dry_run);
+    let health = SharedHealth::default();
+    let mut healthy_sources = HashMap::new();
+
+    let feed = feed::order_feed();
+
+    if let Some(addr) = args.http_addr {
+        spawn_server(
+            addr,
+            &args.persist_path,
+            health.clone(),
+            store.clone(),
+            config.http,
+            feed.clone(),
+            args.max_fetch_silence,
         )
-        .init();
+        .await?;
+    }
 
-    let args = Args::parse();
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = args.grpc_addr {
+        grpc::spawn_grpc(addr, store.clone(), feed.clone());
+    }
 
-    info!("Init DB");
-    init(&args.persist_path)?;
+    #[cfg(feature = "flight")]
+    if let Some(addr) = args.flight_addr {
+        flight::spawn_flight(addr, store.clone());
+    }
 
-    let client = reqwest::Client::new();
-    let mut previous_orders = HashSet::from_iter(get_latest_orders(&args.persist_path)?);
+    let mut control = args
+        .control_socket
+        .as_deref()
+        .map(spawn_control_socket)
+        .transpose()?;
+    let mut paused = false;
+    let started = Instant::now();
+    let mut totals = StatusSummary::default();
+
+    systemd::ready();
+
+    let mut shutdown = pin!(shutdown_signal());
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut user_defined = signal(SignalKind::user_defined1())?;
 
     info!("Fetching orders...");
     loop {
-        match fetch(&client).await {
-            Ok(current_orders) => {
-                let new_orders = current_orders
-                    .difference(&previous_orders)
-                    .collect::<Vec<_>>();
+        let mut collected = select! {
+            collected = collected_rx.recv() => match collected {
+                Some(collected) => collected,
+                None => break,
+            },
+            _ = async {
+                match &mut write_buffer {
+                    Some(write_buffer) => write_buffer.tick().await,
+                    None => pending().await,
+                }
+            } => {
+                if let Some(write_buffer) = &mut write_buffer {
+                    let written = write_buffer.flush(&store, session_gap).await;
+
+                    record_flushed(written, &mut totals, &health);
+                }
+
+                continue;
+            }
+            Some((command, reply)) = async {
+                match &mut control {
+                    Some(control) => control.recv().await,
+                    None => pending().await,
+                }
+            } => {
+                info!("Control command {command}");
+
+                let answer = match command {
+                    CtlCommand::Pause => {
+                        paused = true;
+                        let _ = signals.send(CollectorSignal::Pause);
+                        Ok("Paused, the fetches in progress complete".to_string())
+                    }
+                    CtlCommand::Resume => {
+                        paused = false;
+                        let _ = signals.send(CollectorSignal::Resume);
+                        Ok("Resumed".to_string())
+                    }
+                    CtlCommand::FetchNow => {
+                        let _ = signals.send(CollectorSignal::FetchNow);
+                        Ok("Fetching every source".to_string())
+                    }
+                    CtlCommand::Flush => match &mut write_buffer {
+                        Some(write_buffer) => {
+                            let written = write_buffer.flush(&store, session_gap).await;
+                            record_flushed(written, &mut totals, &health);
+                            Ok(format!("{written} buffered orders written"))
+                        }
+                        None => Ok("No write buffer, the orders are written as they come".to_string()),
+                    },
+                    CtlCommand::SetInterval { interval } => {
+                        let _ = signals.send(CollectorSignal::SetInterval(interval));
+                        Ok(format!(
+                            "Fetching every {}",
+                            humantime::format_duration(interval)
+                        ))
+                    }
+                    CtlCommand::Status => Ok(status(
+                        paused,
+                        &health,
+                        &metrics,
+                        status_summary(&totals, started, &metrics, &args.persist_path),
+                    )),
+                };
+
+                // The client may have given up waiting
+                let _ = reply.send(answer);
+
+                continue;
+            }
+            Some((source, change)) = liveness_rx.recv() => {
+                report_liveness(source, &change, &sinks).await;
+
+                healthy_sources.insert(source, false);
+
+                if let Ok(mut health) = health.write() {
+                    health.healthy = false;
+                }
+
+                continue;
+            }
+            _ = user_defined.recv() => {
+                info!(
+                    "Status: {}",
+                    status_summary(&totals, started, &metrics, &args.persist_path)
+                );
+
+                continue;
+            }
+            _ = hangup.recv() => {
+                info!("Reloading the config and the script");
+
+                if let Err(err) = reload(
+                    &args,
+                    &log_guard,
+                    &mut sinks,
+                    &mut script,
+                    &mut alert_engine,
+                    &mut indicator_alerts,
+                ) {
+                    error!("Failed to reload, keeping the previous settings: {err:#}");
+                }
+
+                continue;
+            }
+            _ = &mut shutdown => break,
+        };
+
+        if let Some(change) = &collected.liveness_change {
+            report_liveness(collected.run.source, change, &sinks).await;
+        }
+
+        if let Some(kind) = collected.error_kind {
+            metrics.record_error(kind);
+        }
+
+        if let Some(wait) = collected.rate_limited {
+            metrics.record_rate_limit(wait);
+        }
+
+        if let Some(burst) = collected.burst
+            && !args.dry_run
+            && let Err(err) = store.insert_burst(burst, collected.run.source).await
+        {
+            error!("Failed to insert burst: {err}");
+        }
+
+        if let Some(timing) = collected.timing {
+            metrics.record_timing(&timing);
+            metrics.record_window(collected.run.orders_returned.unwrap_or_default());
+
+            if collected.not_modified {
+                metrics.record_not_modified();
+            }
+
+            if args.metrics_backend == MetricsBackend::Log
+                && metrics.fetches % args.metrics_log_interval.max(1) == 0
+            {
+                info!("Metrics: {metrics}");
+            }
+
+            if let Some(gap) = &collected.gap {
+                warn!(
+                    source = collected.run.source,
+                    "All {} orders are new after {}ms, ~{} orders likely missed",
+                    gap.window_size,
+                    gap.since_previous_fetch.as_millis(),
+                    gap.estimated_missed
+                );
+
+                metrics.record_gap(gap);
+
+                if !args.dry_run
+                    && let Err(err) = store.insert_gap(*gap, collected.run.source).await
+                {
+                    error!("Failed to insert gap: {err}");
+                }
+            }
+
+            let cycle_at = Utc::now();
+            // Stamped by the server when it says when it answered, the local
+            // clock may be off
+            let fetched_at = timing.server_date.unwrap_or(collected.run.started_at);
+            let mut processed_orders = Vec::with_capacity(collected.new_orders.len());
+
+            for o in &collected.new_orders {
+                let Some(script) = &script else {
+                    processed_orders.push((o.clone(), None));
+                    continue;
+                };
+
+                match script.process(o) {
+                    Ok(Some(processed)) => processed_orders.push(processed),
+                    Ok(None) => debug!("Order dropped by the script: {o}"),
+                    Err(err) => {
+                        error!("Script failed on {o}, keeping it as is: {err}");
+                        processed_orders.push((o.clone(), None));
+                    }
+                }
+            }
 
-                if new_orders.len() == current_orders.len() {
-                    warn!("New orders possibily missed");
+            for (o, annotations) in &processed_orders {
+                info!(
+                    side = %o.ty,
+                    symbol = %o.crypto_symbol,
+                    amount = o.crypto_amount,
+                    fiat = %o.fiat_symbol,
+                    fiat_amount = o.fiat_amount,
+                    price = o.fiat_price,
+                    blockchain = %o.blockchain,
+                    "New order: {}",
+                    numbers.order(o, OrderStyle::Compact)
+                );
+
+                if let Some(anomaly) = anomalies
+                    .as_mut()
+                    .and_then(|anomalies| anomalies.observe(o))
+                {
+                    let message = format!(
+                        "Price anomaly on {}/{}: {} {} at {:.2}, {:.1} standard deviations from the mean price {:.2}",
+                        o.crypto_symbol,
+                        o.fiat_symbol,
+                        o.ty,
+                        o.crypto_amount,
+                        o.fiat_price,
+                        anomaly.z_score,
+                        anomaly.mean_price
+                    );
+
+                    warn!(
+                        symbol = %o.crypto_symbol,
+                        fiat = %o.fiat_symbol,
+                        price = o.fiat_price,
+                        mean_price = anomaly.mean_price,
+                        z_score = anomaly.z_score,
+                        "{message}"
+                    );
+
+                    alert(&sinks, &message).await;
+
+                    if !args.dry_run
+                        && let Err(err) = store.insert_anomaly(anomaly).await
+                    {
+                        error!("Failed to insert anomaly: {err}");
+                    }
+                }
+
+                if let Some(message) = premiums.as_mut().and_then(|premiums| premiums.observe(o)) {
+                    warn!("{message}");
+
+                    alert(&sinks, &message).await;
+                }
+
+                for (_, rule, message) in alert_engine.evaluate(o, cycle_at) {
+                    info!(rule = %rule.name, "Alert {message}");
+
+                    for sink in sinks.iter().filter(|sink| rule.notifies(sink.name())) {
+                        if let Err(err) = sink.alert(&message).await {
+                            error!("Failed to alert through {}: {err}", sink.name());
+                        }
+                    }
+                }
+
+                // Nobody may be listening, which isn't an error
+                let _ = feed.send(feed::FeedOrder {
+                    received_at: cycle_at,
+                    order: o.clone(),
+                });
+
+                if args.dry_run {
+                    continue;
+                }
+
+                let new_order = NewOrder {
+                    order: o.clone(),
+                    source: collected.run.source,
+                    annotations: annotations.clone(),
+                    created_at: cycle_at,
+                    fetched_at,
+                    window_size: collected.run.orders_returned.unwrap_or_default(),
+                };
+
+                // Buffered orders are counted once written, with the fetch
+                // whose order fills the buffer or with none on the timer
+                if let Some(write_buffer) = &mut write_buffer {
+                    if write_buffer.push(new_order) {
+                        collected.run.orders_inserted += write_buffer
+                            .flush(&store, session_gap)
+                            .instrument(debug_span!(parent: &collected.span, "insert"))
+                            .await;
+                    }
+
+                    continue;
+                }
+
+                match store
+                    .insert_order(new_order, session_gap)
+                    .instrument(debug_span!(parent: &collected.span, "insert"))
+                    .await
+                {
+                    Ok(true) => collected.run.orders_inserted += 1,
+                    Ok(false) => debug!("Order already stored: {o}"),
+                    Err(err) => error!("Failed to insert order: {err}"),
+                }
+            }
+
+            let cycle = Cycle {
+                at: cycle_at,
+                orders: processed_orders.iter().map(|(order, _)| order).collect(),
+                timing,
+            };
+
+            for imbalance in imbalances.observe(&cycle.orders, cycle.at) {
+                metrics.record_imbalance(&imbalance);
+
+                if !args.dry_run
+                    && imbalance.orders > 0
+                    && let Err(err) = store
+                        .insert_imbalance(imbalance.clone(), imbalances.window())
+                        .await
+                {
+                    error!("Failed to insert imbalance: {err}");
+                }
+
+                if let Some(message) = imbalances.alert(&imbalance) {
+                    warn!("{message}");
+
+                    alert(&sinks, &message).await;
                 }
+            }
+
+            for volatility in volatilities.observe(&cycle.orders, cycle.at) {
+                metrics.record_volatility(&volatility);
 
-                for o in new_orders {
-                    info!("New order: {o}");
+                if let Some(message) = volatilities.alert(&volatility) {
+                    warn!("{message}");
+
+                    alert(&sinks, &message).await;
+                }
+            }
 
-                    if let Err(err) = insert_order(o, &args.persist_path) {
-                        error!("Failed to insert order: {err}");
+            for sink in &sinks {
+                if let Err(err) = sink.publish(&cycle).await {
+                    error!("Failed to publish to {}: {err}", sink.name());
+                }
+            }
+
+            if let Some(indicator_alerts) = &mut indicator_alerts {
+                match indicator_alerts.check(&store, cycle.at).await {
+                    Ok(messages) => {
+                        for message in messages {
+                            warn!("{message}");
+                            alert(&sinks, &message).await;
+                        }
                     }
+                    Err(err) => error!("Failed to check indicator rules: {err}"),
                 }
+            }
+        }
+
+        let run = &collected.run;
+
+        if !args.dry_run
+            && let Err(err) = store.insert_fetch_run(run.clone()).await
+        {
+            error!("Failed to insert fetch run: {err}");
+        }
+
+        if let Some(statsd) = &mut statsd {
+            statsd.emit(&metrics, run).await;
+        }
+
+        totals.cycles += 1;
+        totals.orders_inserted += run.orders_inserted as u64;
+        totals.last_latency = run.latency.or(totals.last_latency);
 
-                previous_orders = current_orders;
+        healthy_sources.insert(run.source, collected.healthy);
+
+        if let Ok(mut health) = health.write() {
+            health.healthy = healthy_sources.values().all(|healthy| *healthy);
+
+            if run.error.is_none() {
+                health.last_success = Some(run.started_at);
+            }
+
+            if run.orders_inserted > 0 {
+                health.last_insert = Some(Utc::now());
             }
-            Err(err) => error!("{err}"),
         }
 
-        sleep(Duration::from_secs(args.fetch_interval)).await;
+        systemd::watchdog();
+    }
+
+    info!("Shutting down");
+    systemd::stopping();
+
+    if let Some(write_buffer) = &mut write_buffer {
+        write_buffer.flush(&store, session_gap).await;
+    }
+
+    Ok(())
+}
+
+/// Counts the buffered orders written outside of a fetch
+fn record_flushed(written: usize, totals: &mut StatusSummary, health: &SharedHealth) {
+    totals.orders_inserted += written as u64;
+
+    if written > 0
+        && let Ok(mut health) = health.write()
+    {
+        health.last_insert = Some(Utc::now());
+    }
+}
+
+/// Logs a source going down or recovering and alerts about it
+async fn report_liveness(source: &str, change: &LivenessChange, sinks: &[Sink]) {
+    let message = match change {
+        LivenessChange::Down(message) => {
+            error!(source, "{message}");
+            message
+        }
+        LivenessChange::Recovered(message) => {
+            info!(source, "{message}");
+            message
+        }
+    };
+
+    alert(sinks, message).await;
+}
+
+/// Sends an operational alert through every sink, logging the failures
+async fn alert(sinks: &[Sink], message: &str) {
+    for sink in sinks {
+        if let Err(err) = sink.alert(message).await {
+            error!("Failed to alert through {}: {err}", sink.name());
+        }
+    }
+}
+
+/// Reads the config file and the script again, the collection and its
+/// deduplication state carrying on, while the exports and the HTTP access
+/// only change on restart
+fn reload(
+    args: &Args,
+    log_guard: &LogGuard,
+    sinks: &mut [Sink],
+    script: &mut Option<OrderScript>,
+    alert_engine: &mut AlertEngine,
+    indicator_alerts: &mut Option<IndicatorAlerts>,
+) -> anyhow::Result<()> {
+    // Both are read before anything changes, so that an invalid file is
+    // reported with the previous settings still in use
+    let config = Config::load(args.config.as_deref())?;
+    let new_script = args.script.as_deref().map(OrderScript::load).transpose()?;
+
+    log_guard.set_level(config.logging.level.unwrap_or(args.log_level))?;
+
+    for sink in sinks.iter_mut() {
+        if let Err(err) = sink.configure(args, &config.notifiers) {
+            error!(
+                "Failed to reload the {} settings, keeping the previous ones: {err:#}",
+                sink.name()
+            );
+        }
+    }
+
+    *script = new_script;
+
+    let rules = config.alerts.rules.len();
+    let indicator_rules = config.alerts.indicator_rules.len();
+
+    alert_engine.set_rules(config.alerts.rules);
+
+    if let Some(alerts) = indicator_alerts {
+        alerts.set_rules(config.alerts.indicator_rules);
+    } else if indicator_rules > 0 {
+        *indicator_alerts = Some(IndicatorAlerts::new(config.alerts.indicator_rules));
+    }
+
+    info!("Reloaded {rules} alert rules and {indicator_rules} indicator rules");
+
+    Ok(())
+}
+
+/// Counts of the collector since it started, with its current errors and
+/// database size
+fn status_summary(
+    totals: &StatusSummary,
+    started: Instant,
+    metrics: &Metrics,
+    persist_path: &str,
+) -> StatusSummary {
+    let errors = metrics
+        .errors
+        .iter()
+        .map(|(kind, errors)| (kind.to_string(), *errors))
+        .chain(
+            (metrics.rate_limited > 0).then(|| ("rate_limited".to_string(), metrics.rate_limited)),
+        )
+        .collect();
+
+    StatusSummary {
+        uptime: Some(started.elapsed()),
+        errors,
+        db_size: database_size(persist_path),
+        ..*totals
+    }
+}
+
+/// Answer of the `status` control command
+fn status(
+    paused: bool,
+    health: &SharedHealth,
+    metrics: &Metrics,
+    summary: StatusSummary,
+) -> String {
+    let Ok(health) = health.read() else {
+        return "Health unknown".to_string();
+    };
+    let at =
+        |at: Option<DateTime<Utc>>| at.map_or_else(|| "never".to_string(), |at| at.to_rfc3339());
+
+    format!(
+        "{}, {}, last success {}, last insert {}\n{summary}\n{metrics}",
+        if paused { "paused" } else { "running" },
+        if health.healthy {
+            "healthy"
+        } else {
+            "unhealthy"
+        },
+        at(health.last_success),
+        at(health.last_insert)
+    )
+}
+
+async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen to SIGTERM");
+
+    select! {
+        _ = ctrl_c() => {}
+        _ = terminate.recv() => {}
     }
 }
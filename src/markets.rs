@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::json;
+use tokio::{task::spawn_blocking, time::interval};
+use tracing::{error, info};
+
+use crate::{
+    args::Args,
+    db::{insert_tickers, upsert_markets},
+    fetch::from_str_to_f64,
+};
+
+const MARKETS_QUERY: &str = r"query {
+    listMarkets {
+        name
+        aUnit
+        bUnit
+        aUnitPrecision
+        bUnitPrecision
+        minTickSize
+        minTradeSize
+        status
+    }
+}";
+
+const TICKERS_QUERY: &str = r"query {
+    listTickers {
+        marketName
+        lastPrice { amount }
+        bestBidPrice { amount }
+        bestAskPrice { amount }
+        volume24h { amount }
+        priceChange24hPct
+    }
+}";
+
+/// Market listed on the Nash exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Market {
+    pub name: String,
+    pub a_unit: String,
+    pub b_unit: String,
+    pub a_unit_precision: i32,
+    pub b_unit_precision: i32,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub min_tick_size: f64,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub min_trade_size: f64,
+    pub status: String,
+}
+
+/// Quoted prices and 24h activity of a market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticker {
+    pub market_name: String,
+    pub last_price: Option<Amount>,
+    pub best_bid_price: Option<Amount>,
+    pub best_ask_price: Option<Amount>,
+    #[serde(rename = "volume24h")]
+    pub volume_24h: Option<Amount>,
+    #[serde(rename = "priceChange24hPct")]
+    pub price_change_24h_pct: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Amount {
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub amount: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListMarkets {
+    list_markets: Vec<Market>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListTickers {
+    list_tickers: Vec<Ticker>,
+}
+
+/// Stores the markets listed on Nash every `every`, the rows of the delisted
+/// markets being kept with their latest status
+pub fn spawn_markets(args: &Args, client: reqwest::Client, every: Duration) {
+    let url = args.nash_graphql_url.clone();
+    let persist_path = args.persist_path.clone();
+
+    info!(
+        "Fetching markets every {}",
+        humantime::format_duration(every)
+    );
+
+    tokio::spawn(async move {
+        let mut ticks = interval(every);
+
+        loop {
+            ticks.tick().await;
+
+            let markets = match query::<ListMarkets>(&client, &url, MARKETS_QUERY).await {
+                Ok(response) => response.list_markets,
+                Err(err) => {
+                    error!("Failed to fetch markets: {err}");
+                    continue;
+                }
+            };
+
+            let persist_path = persist_path.clone();
+            let stored = spawn_blocking(move || upsert_markets(&markets, &persist_path)).await;
+
+            if let Err(err) = stored
+                .map_err(anyhow::Error::from)
+                .and_then(|stored| stored)
+            {
+                error!("Failed to store markets: {err}");
+            }
+        }
+    });
+}
+
+/// Stores a snapshot of the tickers of every Nash market every `every`
+pub fn spawn_tickers(args: &Args, client: reqwest::Client, every: Duration) {
+    let url = args.nash_graphql_url.clone();
+    let persist_path = args.persist_path.clone();
+
+    info!(
+        "Fetching tickers every {}",
+        humantime::format_duration(every)
+    );
+
+    tokio::spawn(async move {
+        let mut ticks = interval(every);
+
+        loop {
+            ticks.tick().await;
+
+            let tickers = match query::<ListTickers>(&client, &url, TICKERS_QUERY).await {
+                Ok(response) => response.list_tickers,
+                Err(err) => {
+                    error!("Failed to fetch tickers: {err}");
+                    continue;
+                }
+            };
+
+            let persist_path = persist_path.clone();
+            let stored = spawn_blocking(move || insert_tickers(&tickers, &persist_path)).await;
+
+            if let Err(err) = stored
+                .map_err(anyhow::Error::from)
+                .and_then(|stored| stored)
+            {
+                error!("Failed to insert tickers: {err}");
+            }
+        }
+    });
+}
+
+async fn query<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    query: &str,
+) -> anyhow::Result<T> {
+    let response = client
+        .post(url)
+        .json(&json!({ "query": query }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<GraphQlResponse<T>>()
+        .await?;
+
+    if let Some(error) = response.errors.first() {
+        return Err(anyhow!("GraphQL error: {}", error.message));
+    }
+
+    response
+        .data
+        .ok_or_else(|| anyhow!("GraphQL response without data"))
+}
@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use chrono::TimeDelta;
+use clap::ValueEnum;
+
+use crate::{export::Pair, fetch::Timing, gaps::Gap, imbalance::Imbalance, volatility::Volatility};
+
+/// Weight given to the latest sample in the moving averages
+const SMOOTHING: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MetricsBackend {
+    Log,
+    Statsd,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub fetches: u64,
+    pub latency: Duration,
+    pub latency_avg_ms: f64,
+    pub clock_skew: Option<TimeDelta>,
+    pub clock_skew_avg_ms: f64,
+    pub clock_skew_max_ms: i64,
+    pub window_size: usize,
+    pub window_size_avg: f64,
+    pub gaps: u64,
+    pub estimated_missed_orders: u64,
+    /// Fetches whose response didn't change since the previous one
+    pub not_modified: u64,
+    /// Failed fetches per class of error
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Times the API rate limited the fetches, and the total wait it imposed
+    pub rate_limited: u64,
+    pub rate_limit_wait: Duration,
+    /// Latest buy share of every pair with orders in the imbalance window
+    pub buy_shares: BTreeMap<Pair, f64>,
+    /// Latest realized volatility of every pair with prices in the volatility window
+    pub volatilities: BTreeMap<Pair, f64>,
+}
+
+impl Metrics {
+    pub fn record_timing(&mut self, timing: &Timing) {
+        let latency_ms = timing.latency.as_secs_f64() * 1000.0;
+
+        self.latency_avg_ms = moving_average(self.latency_avg_ms, latency_ms, self.fetches);
+        self.latency = timing.latency;
+
+        if let Some(skew) = timing.skew {
+            let skew_ms = skew.num_milliseconds();
+
+            self.clock_skew_avg_ms =
+                moving_average(self.clock_skew_avg_ms, skew_ms as f64, self.fetches);
+            self.clock_skew_max_ms = self.clock_skew_max_ms.max(skew_ms.abs());
+        }
+
+        self.clock_skew = timing.skew;
+        self.fetches += 1;
+    }
+
+    /// Records the number of orders returned by the API, must be called after [`Metrics::record_timing`]
+    pub fn record_window(&mut self, window_size: usize) {
+        self.window_size_avg =
+            moving_average(self.window_size_avg, window_size as f64, self.fetches - 1);
+        self.window_size = window_size;
+    }
+
+    pub fn record_error(&mut self, kind: &'static str) {
+        *self.errors.entry(kind).or_default() += 1;
+    }
+
+    pub fn record_rate_limit(&mut self, wait: Duration) {
+        self.rate_limited += 1;
+        self.rate_limit_wait += wait;
+    }
+
+    pub fn record_not_modified(&mut self) {
+        self.not_modified += 1;
+    }
+
+    pub fn record_gap(&mut self, gap: &Gap) {
+        self.gaps += 1;
+        self.estimated_missed_orders += gap.estimated_missed;
+    }
+
+    pub fn record_imbalance(&mut self, imbalance: &Imbalance) {
+        if imbalance.orders == 0 {
+            self.buy_shares.remove(&imbalance.pair);
+        } else {
+            self.buy_shares
+                .insert(imbalance.pair.clone(), imbalance.buy_share());
+        }
+    }
+
+    pub fn record_volatility(&mut self, volatility: &Volatility) {
+        if volatility.returns == 0 {
+            self.volatilities.remove(&volatility.pair);
+        } else {
+            self.volatilities
+                .insert(volatility.pair.clone(), volatility.realized);
+        }
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} fetches, latency {}ms (avg {:.0}ms), clock skew {} (avg {:.0}ms, max {}ms), window {} orders (avg {:.1}), {} not modified, {} gaps (~{} missed orders)",
+            self.fetches,
+            self.latency.as_millis(),
+            self.latency_avg_ms,
+            self.clock_skew
+                .map(|skew| format!("{}ms", skew.num_milliseconds()))
+                .unwrap_or_else(|| "unknown".to_string()),
+            self.clock_skew_avg_ms,
+            self.clock_skew_max_ms,
+            self.window_size,
+            self.window_size_avg,
+            self.not_modified,
+            self.gaps,
+            self.estimated_missed_orders
+        )?;
+
+        if !self.errors.is_empty() {
+            write!(f, ", errors")?;
+
+            for (kind, errors) in &self.errors {
+                write!(f, " {kind} {errors}")?;
+            }
+        }
+
+        if self.rate_limited > 0 {
+            write!(
+                f,
+                ", rate limited {} times for {}",
+                self.rate_limited,
+                humantime::format_duration(self.rate_limit_wait)
+            )?;
+        }
+
+        if !self.buy_shares.is_empty() {
+            write!(f, ", buy share")?;
+
+            for (pair, buy_share) in &self.buy_shares {
+                write!(f, " {pair} {:.0}%", buy_share * 100.0)?;
+            }
+        }
+
+        if !self.volatilities.is_empty() {
+            write!(f, ", volatility")?;
+
+            for (pair, volatility) in &self.volatilities {
+                write!(f, " {pair} {:.2}%", volatility * 100.0)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn moving_average(average: f64, sample: f64, samples: u64) -> f64 {
+    if samples == 0 {
+        sample
+    } else {
+        average + SMOOTHING * (sample - average)
+    }
+}
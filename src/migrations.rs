@@ -0,0 +1,441 @@
+use chrono::Utc;
+use duckdb::{Connection, params};
+
+/// A schema change, applied once and in order of version
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+/// Steps must never be edited once released, changes go into new steps. The
+/// first ones use `IF NOT EXISTS` as they predate this table
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create orders",
+        sql: r"CREATE TABLE IF NOT EXISTS orders
+            (
+                created_at TIMESTAMP NOT NULL,
+                type VARCHAR NOT NULL,
+                blockchain VARCHAR NOT NULL,
+                crypto_amount DOUBLE NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_amount DOUBLE NOT NULL,
+                fiat_price DOUBLE NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+            );",
+    },
+    Migration {
+        version: 2,
+        name: "add orders session id",
+        sql: "ALTER TABLE orders ADD COLUMN IF NOT EXISTS session_id BIGINT;",
+    },
+    Migration {
+        version: 3,
+        name: "create fetch runs",
+        sql: r"CREATE TABLE IF NOT EXISTS fetch_runs
+            (
+                started_at TIMESTAMP NOT NULL,
+                http_status SMALLINT,
+                latency_ms BIGINT,
+                orders_returned INTEGER,
+                orders_inserted INTEGER NOT NULL,
+                error VARCHAR,
+            );",
+    },
+    Migration {
+        version: 4,
+        name: "create gaps",
+        sql: r"CREATE TABLE IF NOT EXISTS gaps
+            (
+                detected_at TIMESTAMP NOT NULL,
+                since_previous_fetch_ms BIGINT NOT NULL,
+                window_size INTEGER NOT NULL,
+                estimated_missed INTEGER NOT NULL,
+            );",
+    },
+    Migration {
+        version: 5,
+        name: "create imbalances",
+        sql: r"CREATE TABLE imbalances
+            (
+                computed_at TIMESTAMP NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                window_s BIGINT NOT NULL,
+                orders INTEGER NOT NULL,
+                buy_volume DOUBLE NOT NULL,
+                sell_volume DOUBLE NOT NULL,
+                buy_share DOUBLE NOT NULL,
+            );",
+    },
+    Migration {
+        version: 6,
+        name: "create anomalies",
+        sql: r"CREATE TABLE anomalies
+            (
+                detected_at TIMESTAMP NOT NULL,
+                type VARCHAR NOT NULL,
+                blockchain VARCHAR NOT NULL,
+                crypto_amount DOUBLE NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_amount DOUBLE NOT NULL,
+                fiat_price DOUBLE NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                mean_price DOUBLE NOT NULL,
+                std_dev DOUBLE NOT NULL,
+                z_score DOUBLE NOT NULL,
+            );",
+    },
+    Migration {
+        version: 7,
+        name: "create reference prices",
+        sql: r"CREATE TABLE reference_prices
+            (
+                fetched_at TIMESTAMP NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                price DOUBLE NOT NULL,
+            );",
+    },
+    Migration {
+        version: 8,
+        name: "add fx normalization",
+        sql: r"CREATE TABLE fx_rates
+            (
+                day DATE NOT NULL,
+                base VARCHAR NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                rate DOUBLE NOT NULL,
+                PRIMARY KEY (day, base, fiat_symbol),
+            );
+            ALTER TABLE orders ADD COLUMN base_fiat_amount DOUBLE;
+            ALTER TABLE orders ADD COLUMN base_fiat_symbol VARCHAR;",
+    },
+    Migration {
+        version: 9,
+        name: "add orders annotations",
+        sql: "ALTER TABLE orders ADD COLUMN annotations VARCHAR;",
+    },
+    Migration {
+        version: 10,
+        name: "add sources",
+        sql: r"ALTER TABLE orders ADD COLUMN source VARCHAR NOT NULL DEFAULT 'nash';
+            ALTER TABLE fetch_runs ADD COLUMN source VARCHAR NOT NULL DEFAULT 'nash';
+            ALTER TABLE gaps ADD COLUMN source VARCHAR NOT NULL DEFAULT 'nash';",
+    },
+    Migration {
+        version: 11,
+        name: "add markets and tickers",
+        sql: r"CREATE TABLE markets
+            (
+                name VARCHAR PRIMARY KEY,
+                a_unit VARCHAR NOT NULL,
+                b_unit VARCHAR NOT NULL,
+                a_unit_precision INTEGER NOT NULL,
+                b_unit_precision INTEGER NOT NULL,
+                min_tick_size DOUBLE NOT NULL,
+                min_trade_size DOUBLE NOT NULL,
+                status VARCHAR NOT NULL,
+                updated_at TIMESTAMP NOT NULL,
+            );
+            CREATE TABLE tickers
+            (
+                fetched_at TIMESTAMP NOT NULL,
+                market VARCHAR NOT NULL,
+                last_price DOUBLE,
+                best_bid DOUBLE,
+                best_ask DOUBLE,
+                volume_24h DOUBLE,
+                price_change_24h_pct DOUBLE,
+            );",
+    },
+    Migration {
+        version: 12,
+        name: "add my orders",
+        sql: r"CREATE TABLE my_orders
+            (
+                id VARCHAR PRIMARY KEY,
+                completed_at TIMESTAMP NOT NULL,
+                type VARCHAR NOT NULL,
+                blockchain VARCHAR NOT NULL,
+                crypto_amount DOUBLE NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_amount DOUBLE NOT NULL,
+                fiat_price DOUBLE NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                fetched_at TIMESTAMP NOT NULL,
+            );",
+    },
+    Migration {
+        version: 13,
+        name: "add orders extra fields",
+        sql: "ALTER TABLE orders ADD COLUMN extra VARCHAR;",
+    },
+    Migration {
+        version: 14,
+        name: "add orders api ids and timestamps",
+        sql: r"ALTER TABLE orders ADD COLUMN api_order_id VARCHAR;
+            ALTER TABLE orders ADD COLUMN api_created_at TIMESTAMP;
+            ALTER TABLE orders ADD COLUMN fetched_at TIMESTAMP;",
+    },
+    Migration {
+        version: 15,
+        name: "add orders hash",
+        // Nash orders carry neither id nor timestamp, so identical orders are
+        // told apart by their occurrence rather than by the local insert time,
+        // which would change on every fetch after a restart. None is removed,
+        // repeated orders being common on stablecoin pairs
+        sql: r"ALTER TABLE orders ADD COLUMN hash VARCHAR;
+            UPDATE orders SET hash = hashed.hash
+            FROM (
+                SELECT
+                    rowid AS id,
+                    sha256(concat_ws(
+                        '|',
+                        source,
+                        type,
+                        blockchain,
+                        crypto_amount,
+                        crypto_symbol,
+                        fiat_amount,
+                        fiat_price,
+                        fiat_symbol,
+                        COALESCE(api_order_id, ''),
+                        COALESCE(CAST(CAST(api_created_at AS TIMESTAMP) AS VARCHAR), ''),
+                        ROW_NUMBER() OVER content - 1
+                    )) AS hash,
+                FROM orders
+                WINDOW content AS (
+                    PARTITION BY
+                        source,
+                        type,
+                        blockchain,
+                        crypto_amount,
+                        crypto_symbol,
+                        fiat_amount,
+                        fiat_price,
+                        fiat_symbol,
+                        api_order_id,
+                        api_created_at
+                    ORDER BY created_at, rowid
+                )
+            ) AS hashed
+            WHERE orders.rowid = hashed.id;
+            CREATE UNIQUE INDEX orders_hash ON orders (hash);
+            CREATE OR REPLACE VIEW suspected_duplicates AS
+            SELECT
+                hash,
+                created_at,
+                source,
+                type,
+                crypto_symbol,
+                fiat_symbol,
+                crypto_amount,
+                fiat_amount,
+                fiat_price,
+                api_order_id,
+                COUNT(*) OVER content AS copies,
+                MIN(created_at) OVER content AS first_created_at,
+            FROM orders
+            WINDOW content AS (
+                PARTITION BY
+                    source,
+                    type,
+                    blockchain,
+                    crypto_amount,
+                    crypto_symbol,
+                    fiat_amount,
+                    fiat_price,
+                    fiat_symbol,
+                    api_order_id,
+                    api_created_at
+            )
+            QUALIFY copies > 1;
+            COMMENT ON VIEW suspected_duplicates IS 'Orders with the same content as another one, either repeated orders or the same order stored twice';",
+    },
+    Migration {
+        version: 16,
+        name: "create bursts",
+        sql: r"CREATE TABLE bursts
+            (
+                started_at TIMESTAMP NOT NULL,
+                ended_at TIMESTAMP NOT NULL,
+                fetches INTEGER NOT NULL,
+                gaps INTEGER NOT NULL,
+                source VARCHAR NOT NULL,
+            );",
+    },
+    Migration {
+        version: 17,
+        name: "create curated views",
+        sql: r"CREATE OR REPLACE VIEW daily_volume AS
+            SELECT
+                CAST(created_at AS DATE) AS day,
+                crypto_symbol,
+                fiat_symbol,
+                COUNT(*) AS orders,
+                COUNT(*) FILTER (WHERE type = 'buy') AS buys,
+                COUNT(*) FILTER (WHERE type = 'sell') AS sells,
+                SUM(crypto_amount) AS crypto_volume,
+                SUM(fiat_amount) AS fiat_volume,
+            FROM orders
+            GROUP BY ALL;
+            COMMENT ON VIEW daily_volume IS 'Orders and volumes of every pair per UTC day';
+            CREATE OR REPLACE VIEW pair_summary AS
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                COUNT(*) AS orders,
+                COUNT(*) FILTER (WHERE type = 'buy') AS buys,
+                COUNT(*) FILTER (WHERE type = 'sell') AS sells,
+                SUM(crypto_amount) AS crypto_volume,
+                SUM(fiat_amount) AS fiat_volume,
+                SUM(fiat_amount) / SUM(crypto_amount) AS avg_price,
+                MAX(fiat_amount) AS largest_fiat_amount,
+                MIN(created_at) AS first_order_at,
+                MAX(created_at) AS last_order_at,
+            FROM orders
+            GROUP BY ALL;
+            COMMENT ON VIEW pair_summary IS 'Activity of every pair since the first stored order';
+            CREATE OR REPLACE VIEW hourly_prices AS
+            SELECT
+                date_trunc('hour', created_at) AS hour,
+                crypto_symbol,
+                fiat_symbol,
+                arg_min(fiat_price, created_at) AS open,
+                MAX(fiat_price) AS high,
+                MIN(fiat_price) AS low,
+                arg_max(fiat_price, created_at) AS close,
+                SUM(fiat_amount) / SUM(crypto_amount) AS vwap,
+                COUNT(*) AS orders,
+            FROM orders
+            GROUP BY ALL;
+            COMMENT ON VIEW hourly_prices IS 'Price candles of every pair per UTC hour';
+            CREATE TABLE pair_summary_snapshot AS
+            SELECT *, CAST(NULL AS TIMESTAMP) AS refreshed_at FROM pair_summary LIMIT 0;",
+    },
+    Migration {
+        version: 18,
+        name: "create pair stats",
+        sql: r"CREATE TABLE pair_stats
+            (
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                orders BIGINT NOT NULL,
+                buys BIGINT NOT NULL,
+                sells BIGINT NOT NULL,
+                crypto_volume DOUBLE NOT NULL,
+                fiat_volume DOUBLE NOT NULL,
+                min_price DOUBLE NOT NULL,
+                max_price DOUBLE NOT NULL,
+                first_seen_at TIMESTAMP NOT NULL,
+                last_seen_at TIMESTAMP NOT NULL,
+                PRIMARY KEY (crypto_symbol, fiat_symbol),
+            );
+            COMMENT ON TABLE pair_stats IS 'Cumulative activity of every pair, kept up to date as the orders are inserted';
+            INSERT INTO pair_stats
+            SELECT
+                crypto_symbol,
+                fiat_symbol,
+                COUNT(*),
+                COUNT(*) FILTER (WHERE type = 'buy'),
+                COUNT(*) FILTER (WHERE type = 'sell'),
+                SUM(crypto_amount),
+                SUM(fiat_amount),
+                MIN(fiat_price),
+                MAX(fiat_price),
+                MIN(created_at),
+                MAX(created_at),
+            FROM orders
+            GROUP BY ALL;",
+    },
+    Migration {
+        version: 19,
+        name: "create rollups",
+        sql: r"CREATE TABLE orders_hourly
+            (
+                hour TIMESTAMP NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                orders BIGINT NOT NULL,
+                crypto_volume DOUBLE NOT NULL,
+                fiat_volume DOUBLE NOT NULL,
+                vwap DOUBLE,
+                high DOUBLE NOT NULL,
+                low DOUBLE NOT NULL,
+                PRIMARY KEY (hour, crypto_symbol, fiat_symbol),
+            );
+            COMMENT ON TABLE orders_hourly IS 'Orders of every pair per UTC hour, rolled up on the rollup schedule';
+            CREATE TABLE orders_daily
+            (
+                day DATE NOT NULL,
+                crypto_symbol VARCHAR NOT NULL,
+                fiat_symbol VARCHAR NOT NULL,
+                orders BIGINT NOT NULL,
+                crypto_volume DOUBLE NOT NULL,
+                fiat_volume DOUBLE NOT NULL,
+                vwap DOUBLE,
+                high DOUBLE NOT NULL,
+                low DOUBLE NOT NULL,
+                PRIMARY KEY (day, crypto_symbol, fiat_symbol),
+            );
+            COMMENT ON TABLE orders_daily IS 'Orders of every pair per UTC day, rolled up on the rollup schedule';",
+    },
+];
+
+pub fn current_version(conn: &Connection) -> anyhow::Result<i64> {
+    create_migrations_table(conn)?;
+
+    let version = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations;",
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(version)
+}
+
+/// Returns the migrations not applied yet
+pub fn pending(conn: &Connection) -> anyhow::Result<Vec<&'static Migration>> {
+    let version = current_version(conn)?;
+
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > version)
+        .collect())
+}
+
+/// Applies the pending migrations, each in its own transaction, and returns them
+pub fn migrate(conn: &mut Connection) -> anyhow::Result<Vec<&'static Migration>> {
+    let pending = pending(conn)?;
+
+    for migration in &pending {
+        let transaction = conn.transaction()?;
+
+        transaction.execute_batch(migration.sql)?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, ?);",
+            params![migration.version, migration.name, Utc::now()],
+        )?;
+
+        transaction.commit()?;
+    }
+
+    Ok(pending)
+}
+
+fn create_migrations_table(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        r"CREATE TABLE IF NOT EXISTS schema_migrations
+            (
+                version BIGINT PRIMARY KEY,
+                name VARCHAR NOT NULL,
+                applied_at TIMESTAMP NOT NULL,
+            );",
+    )?;
+
+    Ok(())
+}
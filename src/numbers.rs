@@ -0,0 +1,189 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+
+use crate::{args::Args, fetch::Order};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Locale {
+    En,
+    Fr,
+    De,
+}
+
+impl Locale {
+    /// Thousands and decimal separators
+    fn separators(&self) -> (char, char) {
+        match self {
+            Locale::En => (',', '.'),
+            Locale::Fr => ('\u{202f}', ','),
+            Locale::De => ('.', ','),
+        }
+    }
+}
+
+/// How the amounts of the human output are written: logs, stats tables and
+/// notifications, the machine readable outputs keeping the raw values
+#[derive(Debug, Clone, Copy)]
+pub struct NumberFormat {
+    pub locale: Locale,
+    /// Decimals of every crypto amount, the ones of each currency when not set
+    pub crypto_decimals: Option<usize>,
+    /// Decimals of every fiat amount, the ones of each currency when not set
+    pub fiat_decimals: Option<usize>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            locale: Locale::En,
+            crypto_decimals: None,
+            fiat_decimals: None,
+        }
+    }
+}
+
+/// How much of an order [`NumberFormat::order`] writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStyle {
+    /// A single line, for logs and lists
+    Compact,
+    /// A line per field, for the notifications showing a single order
+    Verbose,
+}
+
+impl NumberFormat {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            locale: args.locale,
+            crypto_decimals: args.crypto_decimals,
+            fiat_decimals: args.fiat_decimals,
+        }
+    }
+
+    /// A crypto amount followed by its symbol, without the trailing zeros
+    pub fn crypto(&self, value: f64, symbol: &str) -> String {
+        let decimals = self
+            .crypto_decimals
+            .unwrap_or_else(|| crypto_decimals(symbol));
+        let number = format_number(value, decimals, self.locale);
+        let (_, decimal) = self.locale.separators();
+        let number = if number.contains(decimal) {
+            number.trim_end_matches('0').trim_end_matches(decimal)
+        } else {
+            &number
+        };
+
+        format!("{number} {symbol}")
+    }
+
+    /// A fiat amount with the sign of its currency where the locale puts it
+    pub fn fiat(&self, value: f64, symbol: &str) -> String {
+        let decimals = self.fiat_decimals.unwrap_or_else(|| fiat_decimals(symbol));
+        let number = format_number(value, decimals, self.locale);
+
+        match (currency_sign(symbol), self.locale) {
+            (Some(sign), Locale::En) => format!("{sign}{number}"),
+            (Some(sign), Locale::Fr | Locale::De) => format!("{number}\u{a0}{sign}"),
+            (None, _) => format!("{number} {symbol}"),
+        }
+    }
+
+    pub fn order<'a>(&self, order: &'a Order, style: OrderStyle) -> impl Display + use<'a> {
+        OrderDisplay {
+            order,
+            format: *self,
+            style,
+        }
+    }
+}
+
+struct OrderDisplay<'a> {
+    order: &'a Order,
+    format: NumberFormat,
+    style: OrderStyle,
+}
+
+impl Display for OrderDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let order = self.order;
+        let crypto_amount = self
+            .format
+            .crypto(order.crypto_amount, &order.crypto_symbol);
+        let fiat_amount = self.format.fiat(order.fiat_amount, &order.fiat_symbol);
+        let fiat_price = self.format.fiat(order.fiat_price, &order.fiat_symbol);
+
+        match self.style {
+            OrderStyle::Compact => write!(
+                f,
+                "{} {crypto_amount} for {fiat_amount} at {fiat_price} on {}",
+                order.ty, order.blockchain
+            ),
+            OrderStyle::Verbose => write!(
+                f,
+                "{} {crypto_amount} on {}\nAmount: {fiat_amount}\nPrice: {fiat_price}/{}",
+                order.ty, order.blockchain, order.crypto_symbol
+            ),
+        }
+    }
+}
+
+/// Decimals the smallest unit of a crypto currency needs, 8 for the unknown ones
+fn crypto_decimals(symbol: &str) -> usize {
+    match symbol.to_uppercase().as_str() {
+        "NEO" => 0,
+        "USDC" | "USDT" => 2,
+        "ETH" => 6,
+        _ => 8,
+    }
+}
+
+/// Decimals of the amounts of a fiat currency, 2 but for the ones without cents
+fn fiat_decimals(symbol: &str) -> usize {
+    match symbol.to_uppercase().as_str() {
+        "JPY" | "KRW" => 0,
+        _ => 2,
+    }
+}
+
+fn currency_sign(symbol: &str) -> Option<&'static str> {
+    match symbol.to_uppercase().as_str() {
+        "USD" => Some("$"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        "JPY" => Some("¥"),
+        _ => None,
+    }
+}
+
+/// `value` with `decimals` decimals and the separators of `locale`
+pub fn format_number(value: f64, decimals: usize, locale: Locale) -> String {
+    let (thousands, decimal) = locale.separators();
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = formatted
+        .split_once('.')
+        .map_or((formatted.as_str(), None), |(integer, fraction)| {
+            (integer, Some(fraction))
+        });
+
+    let mut number = if value < 0.0 {
+        "-".to_string()
+    } else {
+        String::new()
+    };
+
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            number.push(thousands);
+        }
+
+        number.push(digit);
+    }
+
+    if let Some(fraction) = fraction {
+        number.push(decimal);
+        number.push_str(fraction);
+    }
+
+    number
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{export::Pair, fetch::OrderType};
+
+/// How the cost of the sold crypto is determined
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CostMethod {
+    /// The first units bought are the first sold
+    Fifo,
+    /// Every unit held costs the average price paid for the position
+    Average,
+}
+
+/// Order of the account, from the `my_orders` table or an imported CSV
+#[derive(Debug, Clone)]
+pub struct Trade {
+    pub at: DateTime<Utc>,
+    pub ty: OrderType,
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub crypto_amount: f64,
+    pub fiat_amount: f64,
+}
+
+/// Sale of crypto with the cost of the sold units
+#[derive(Debug, Clone, Serialize)]
+pub struct Disposal {
+    pub at: DateTime<Utc>,
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub crypto_amount: f64,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    /// Part of the sold amount not covered by earlier buys, counted at no cost
+    pub uncovered_amount: f64,
+}
+
+impl Disposal {
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost_basis
+    }
+}
+
+/// Crypto still held after the last trade
+#[derive(Debug, Clone, Serialize)]
+pub struct Position {
+    pub crypto_symbol: String,
+    pub fiat_symbol: String,
+    pub crypto_amount: f64,
+    pub cost_basis: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Ledger {
+    pub disposals: Vec<Disposal>,
+    pub positions: Vec<Position>,
+}
+
+/// Units bought together at the same unit cost
+#[derive(Debug, Clone, Copy)]
+struct Lot {
+    amount: f64,
+    unit_cost: f64,
+}
+
+/// Matches the sells of every pair with its earlier buys, `trades` being
+/// sorted by time
+pub fn compute(trades: &[Trade], method: CostMethod) -> Ledger {
+    let mut lots = BTreeMap::<Pair, VecDeque<Lot>>::new();
+    let mut ledger = Ledger::default();
+
+    for trade in trades {
+        let pair = Pair {
+            crypto_symbol: trade.crypto_symbol.clone(),
+            fiat_symbol: trade.fiat_symbol.clone(),
+        };
+        let lots = lots.entry(pair).or_default();
+
+        match trade.ty {
+            OrderType::Buy => {
+                if trade.crypto_amount <= 0.0 {
+                    continue;
+                }
+
+                lots.push_back(Lot {
+                    amount: trade.crypto_amount,
+                    unit_cost: trade.fiat_amount / trade.crypto_amount,
+                });
+
+                if let CostMethod::Average = method {
+                    let (amount, cost) = total(lots);
+
+                    lots.clear();
+                    lots.push_back(Lot {
+                        amount,
+                        unit_cost: cost / amount,
+                    });
+                }
+            }
+            OrderType::Sell => {
+                let mut remaining = trade.crypto_amount;
+                let mut cost_basis = 0.0;
+
+                while remaining > 0.0
+                    && let Some(lot) = lots.front_mut()
+                {
+                    let sold = remaining.min(lot.amount);
+
+                    cost_basis += sold * lot.unit_cost;
+                    remaining -= sold;
+                    lot.amount -= sold;
+
+                    if lot.amount <= f64::EPSILON {
+                        lots.pop_front();
+                    }
+                }
+
+                ledger.disposals.push(Disposal {
+                    at: trade.at,
+                    crypto_symbol: trade.crypto_symbol.clone(),
+                    fiat_symbol: trade.fiat_symbol.clone(),
+                    crypto_amount: trade.crypto_amount,
+                    proceeds: trade.fiat_amount,
+                    cost_basis,
+                    uncovered_amount: remaining.max(0.0),
+                });
+            }
+        }
+    }
+
+    ledger.positions = lots
+        .into_iter()
+        .filter(|(_, lots)| !lots.is_empty())
+        .map(|(pair, lots)| {
+            let (crypto_amount, cost_basis) = total(&lots);
+
+            Position {
+                crypto_symbol: pair.crypto_symbol,
+                fiat_symbol: pair.fiat_symbol,
+                crypto_amount,
+                cost_basis,
+            }
+        })
+        .collect();
+
+    ledger
+}
+
+/// Amount and cost of the lots
+fn total(lots: &VecDeque<Lot>) -> (f64, f64) {
+    lots.iter().fold((0.0, 0.0), |(amount, cost), lot| {
+        (amount + lot.amount, cost + lot.amount * lot.unit_cost)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    fn trade(ty: OrderType, crypto_symbol: &str, crypto_amount: f64, fiat_amount: f64) -> Trade {
+        Trade {
+            at: DateTime::UNIX_EPOCH,
+            ty,
+            crypto_symbol: crypto_symbol.to_string(),
+            fiat_symbol: "USD".to_string(),
+            crypto_amount,
+            fiat_amount,
+        }
+    }
+
+    fn buys_then_sell() -> Vec<Trade> {
+        vec![
+            trade(OrderType::Buy, "BTC", 1.0, 100.0),
+            trade(OrderType::Buy, "BTC", 1.0, 200.0),
+            trade(OrderType::Sell, "BTC", 1.5, 450.0),
+        ]
+    }
+
+    #[test]
+    fn fifo_sells_the_first_lots_first() {
+        let ledger = compute(&buys_then_sell(), CostMethod::Fifo);
+
+        assert_eq!(ledger.disposals.len(), 1);
+        assert_abs_diff_eq!(ledger.disposals[0].cost_basis, 200.0);
+        assert_abs_diff_eq!(ledger.disposals[0].gain(), 250.0);
+        assert_abs_diff_eq!(ledger.disposals[0].uncovered_amount, 0.0);
+
+        assert_eq!(ledger.positions.len(), 1);
+        assert_abs_diff_eq!(ledger.positions[0].crypto_amount, 0.5);
+        assert_abs_diff_eq!(ledger.positions[0].cost_basis, 100.0);
+    }
+
+    #[test]
+    fn average_cost_sells_at_the_average_price() {
+        let ledger = compute(&buys_then_sell(), CostMethod::Average);
+
+        assert_abs_diff_eq!(ledger.disposals[0].cost_basis, 225.0);
+        assert_abs_diff_eq!(ledger.disposals[0].gain(), 225.0);
+
+        assert_abs_diff_eq!(ledger.positions[0].crypto_amount, 0.5);
+        assert_abs_diff_eq!(ledger.positions[0].cost_basis, 75.0);
+    }
+
+    #[test]
+    fn sells_beyond_the_buys_are_uncovered() {
+        let trades = vec![
+            trade(OrderType::Buy, "BTC", 1.0, 100.0),
+            trade(OrderType::Sell, "BTC", 2.0, 300.0),
+        ];
+
+        for method in [CostMethod::Fifo, CostMethod::Average] {
+            let ledger = compute(&trades, method);
+
+            assert_abs_diff_eq!(ledger.disposals[0].cost_basis, 100.0);
+            assert_abs_diff_eq!(ledger.disposals[0].uncovered_amount, 1.0);
+            assert!(ledger.positions.is_empty());
+        }
+    }
+
+    #[test]
+    fn buys_only_cover_the_sells_of_their_pair() {
+        let trades = vec![
+            trade(OrderType::Buy, "BTC", 1.0, 100.0),
+            trade(OrderType::Sell, "ETH", 1.0, 50.0),
+        ];
+
+        let ledger = compute(&trades, CostMethod::Fifo);
+
+        assert_abs_diff_eq!(ledger.disposals[0].cost_basis, 0.0);
+        assert_abs_diff_eq!(ledger.disposals[0].uncovered_amount, 1.0);
+        assert_eq!(ledger.positions.len(), 1);
+        assert_eq!(ledger.positions[0].crypto_symbol, "BTC");
+    }
+}
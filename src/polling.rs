@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::args::Args;
+
+/// Share of the API window being new orders above which the interval shrinks
+const BUSY_RATIO: f64 = 0.5;
+
+/// Interval between two fetches, fixed or adapting to the market activity
+#[derive(Debug, Clone)]
+pub struct PollInterval {
+    current: Duration,
+    bounds: Option<(Duration, Duration)>,
+    /// Largest random deviation from the interval, as a fraction of it
+    jitter: f64,
+}
+
+impl PollInterval {
+    /// Interval of `source`, `--source-intervals` overriding `--fetch-interval`
+    pub fn from_args(args: &Args, source: &str) -> Self {
+        let current = args
+            .source_intervals
+            .iter()
+            .find(|(name, _)| name == source)
+            .map(|(_, interval)| *interval)
+            .unwrap_or(Duration::from_secs(args.fetch_interval));
+        let jitter = (args.fetch_jitter / 100.0).clamp(0.0, 1.0);
+
+        if args.adaptive_polling {
+            let min = Duration::from_secs(args.min_fetch_interval);
+            let max = Duration::from_secs(args.max_fetch_interval).max(min);
+
+            Self {
+                current: current.clamp(min, max),
+                bounds: Some((min, max)),
+                jitter,
+            }
+        } else {
+            Self {
+                current,
+                bounds: None,
+                jitter,
+            }
+        }
+    }
+
+    /// Returns the current interval shifted by a random jitter, so that
+    /// collectors started together don't keep fetching at the same instant
+    pub fn next_delay(&self) -> Duration {
+        if self.jitter == 0.0 {
+            return self.current;
+        }
+
+        self.current
+            .mul_f64(1.0 + rand::random_range(-self.jitter..=self.jitter))
+    }
+
+    /// Replaces the interval, the adaptive polling adapting it from there
+    pub fn set(&mut self, interval: Duration) {
+        self.current = match self.bounds {
+            Some((min, max)) => interval.clamp(min, max),
+            None => interval,
+        };
+    }
+
+    /// Halves the interval when a large part of the window was new, so bursts
+    /// aren't missed, and stretches it when nothing happened
+    pub fn update(&mut self, new_orders: usize, window: usize) {
+        let Some((min, max)) = self.bounds else {
+            return;
+        };
+
+        if window > 0 && new_orders as f64 / window as f64 >= BUSY_RATIO {
+            self.current = (self.current / 2).max(min);
+        } else if new_orders == 0 {
+            self.current = self.current.mul_f64(1.5).min(max);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use tokio::{task::spawn_blocking, time::interval};
+use tracing::{error, info};
+
+use crate::{args::Args, db::insert_reference_prices, export::Pair, fetch::Order};
+
+/// Latest reference price of every tracked pair
+pub type ReferencePrices = Arc<RwLock<HashMap<Pair, f64>>>;
+
+/// Fetches the reference prices of the configured coins every `interval`,
+/// storing them unless in dry run
+pub fn spawn_reference_prices(
+    args: &Args,
+    client: reqwest::Client,
+    every: Duration,
+) -> anyhow::Result<ReferencePrices> {
+    let coin_ids = args
+        .reference_coin_ids
+        .iter()
+        .map(|mapping| {
+            mapping
+                .split_once('=')
+                .map(|(symbol, id)| (id.to_string(), symbol.to_string()))
+                .ok_or_else(|| anyhow!("Coin id {mapping} should be formatted as SYMBOL=id"))
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()?;
+    let url = format!(
+        "{}/simple/price?ids={}&vs_currencies={}",
+        args.reference_url.trim_end_matches('/'),
+        coin_ids.keys().cloned().collect::<Vec<_>>().join(","),
+        args.reference_fiats.join(",").to_lowercase()
+    );
+    let api_key = args.coingecko_api_key.clone();
+    let persist_path = (!args.dry_run).then(|| args.persist_path.clone());
+    let prices = ReferencePrices::default();
+    let shared_prices = prices.clone();
+
+    info!(
+        "Fetching reference prices every {}",
+        humantime::format_duration(every)
+    );
+
+    tokio::spawn(async move {
+        let mut ticks = interval(every);
+
+        loop {
+            ticks.tick().await;
+
+            let fetched =
+                match fetch_reference_prices(&client, &url, api_key.as_deref(), &coin_ids).await {
+                    Ok(fetched) => fetched,
+                    Err(err) => {
+                        error!("Failed to fetch reference prices: {err}");
+                        continue;
+                    }
+                };
+
+            if let Ok(mut prices) = shared_prices.write() {
+                prices.extend(fetched.iter().cloned());
+            }
+
+            if let Some(persist_path) = persist_path.clone() {
+                let stored =
+                    spawn_blocking(move || insert_reference_prices(&fetched, &persist_path)).await;
+
+                if let Err(err) = stored
+                    .map_err(anyhow::Error::from)
+                    .and_then(|stored| stored)
+                {
+                    error!("Failed to insert reference prices: {err}");
+                }
+            }
+        }
+    });
+
+    Ok(prices)
+}
+
+async fn fetch_reference_prices(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: Option<&str>,
+    coin_ids: &HashMap<String, String>,
+) -> anyhow::Result<Vec<(Pair, f64)>> {
+    let mut request = client.get(url);
+
+    if let Some(api_key) = api_key {
+        request = request.header("x-cg-demo-api-key", api_key);
+    }
+
+    let response = request
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<HashMap<String, HashMap<String, f64>>>()
+        .await?;
+
+    let prices = response
+        .into_iter()
+        .filter_map(|(id, prices)| Some((coin_ids.get(&id)?.clone(), prices)))
+        .flat_map(|(crypto_symbol, prices)| {
+            prices.into_iter().map(move |(fiat, price)| {
+                (
+                    Pair {
+                        crypto_symbol: crypto_symbol.clone(),
+                        fiat_symbol: fiat.to_uppercase(),
+                    },
+                    price,
+                )
+            })
+        })
+        .collect();
+
+    Ok(prices)
+}
+
+/// Compares the order prices with the reference prices, to raise a single
+/// alert while a pair trades away from the market
+#[derive(Debug)]
+pub struct PremiumTracker {
+    prices: ReferencePrices,
+    alert_threshold: Option<f64>,
+    alerting: HashSet<Pair>,
+}
+
+impl PremiumTracker {
+    pub fn new(prices: ReferencePrices, args: &Args) -> Self {
+        Self {
+            prices,
+            alert_threshold: args.premium_alert_threshold,
+            alerting: HashSet::new(),
+        }
+    }
+
+    /// Returns an alert message when the pair starts trading beyond the threshold
+    pub fn observe(&mut self, order: &Order) -> Option<String> {
+        let threshold = self.alert_threshold?;
+        let pair = Pair::of(order);
+        let reference = *self.prices.read().ok()?.get(&pair)?;
+        let premium = order.fiat_price / reference - 1.0;
+
+        if premium.abs() < threshold {
+            self.alerting.remove(&pair);
+
+            return None;
+        }
+
+        if !self.alerting.insert(pair.clone()) {
+            return None;
+        }
+
+        Some(format!(
+            "{pair} traded at {:.2}, a {:.1}% {} to the reference price {:.2}",
+            order.fiat_price,
+            premium.abs() * 100.0,
+            if premium > 0.0 { "premium" } else { "discount" },
+            reference
+        ))
+    }
+}
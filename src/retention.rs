@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::task::spawn_blocking;
+use tracing::info;
+
+use crate::{
+    db::{Pruned, archive, prune},
+    scheduler::{Schedule, spawn_job},
+    storage::ObjectStorage,
+};
+
+/// Archives the orders created before `before` when an archive directory is
+/// set, uploading the archive when a storage is set, then deletes the rows,
+/// keeping them when any of the archive steps fails. The archive holds whole
+/// days, `before` being moved back to the start of its day then
+pub async fn apply_retention(
+    persist_path: String,
+    before: DateTime<Utc>,
+    archive_dir: Option<String>,
+    storage: Option<ObjectStorage>,
+) -> anyhow::Result<Pruned> {
+    let before = if let Some(directory) = archive_dir {
+        let before = before
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .map_or(before, |midnight| midnight.and_utc());
+        let (archived, directory) = {
+            let persist_path = persist_path.clone();
+
+            spawn_blocking(move || {
+                archive(&persist_path, before, &directory).map(|archived| (archived, directory))
+            })
+            .await??
+        };
+
+        info!("{} orders archived to {directory}", archived.orders);
+
+        // The rows are kept when an upload fails, so the next run writes and
+        // uploads the same files again
+        if let Some(storage) = &storage {
+            let root = Path::new(&directory);
+
+            for file in &archived.files {
+                let relative = file.strip_prefix(root)?.to_string_lossy().to_string();
+                let key = storage.upload(file, &format!("archive/{relative}")).await?;
+
+                info!("{} uploaded to {key}", file.display());
+            }
+        }
+
+        before
+    } else {
+        before
+    };
+
+    let pruned = spawn_blocking(move || prune(&persist_path, before)).await??;
+
+    Ok(pruned)
+}
+
+/// Prunes the rows older than `retention_days` at every run of `schedule`
+pub fn spawn_retention(
+    persist_path: &str,
+    retention_days: u32,
+    schedule: Schedule,
+    archive_dir: Option<String>,
+    storage: Option<ObjectStorage>,
+) {
+    let persist_path = persist_path.to_string();
+
+    spawn_job(
+        format!("prune older than {retention_days} days"),
+        schedule,
+        move |_, _| {
+            let before = Utc::now() - TimeDelta::days(retention_days.into());
+            let retention = apply_retention(
+                persist_path.clone(),
+                before,
+                archive_dir.clone(),
+                storage.clone(),
+            );
+
+            async move { Ok(retention.await?.to_string()) }
+        },
+    );
+}
@@ -0,0 +1,260 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::Deserialize;
+use tokio::{task::spawn_blocking, time::sleep};
+use tracing::{error, info, warn};
+
+use crate::{
+    db::{maintain, refresh_pair_summary, rollup},
+    export::ExportSubscription,
+    storage::ObjectStorage,
+};
+
+/// When a job runs, `hourly`, `daily` or a cron expression with seconds like `0 */15 * * * *`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Schedule {
+    Hourly,
+    Daily,
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    /// Returns the first run strictly after `time`
+    pub fn next_after(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Schedule::Hourly => {
+                time.duration_trunc(TimeDelta::hours(1)).unwrap_or(time) + TimeDelta::hours(1)
+            }
+            Schedule::Daily => {
+                time.duration_trunc(TimeDelta::days(1)).unwrap_or(time) + TimeDelta::days(1)
+            }
+            Schedule::Cron(schedule) => schedule
+                .after(&time)
+                .next()
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    /// Returns the start of the period ending with the run at `to`, assuming
+    /// the runs are evenly spaced
+    pub fn period_start(&self, to: DateTime<Utc>) -> DateTime<Utc> {
+        to.checked_sub_signed(self.next_after(to) - to)
+            .unwrap_or(to)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hourly" => Ok(Schedule::Hourly),
+            "daily" => Ok(Schedule::Daily),
+            expression => expression
+                .parse::<cron::Schedule>()
+                .map(|schedule| Schedule::Cron(Box::new(schedule)))
+                .map_err(|err| anyhow!("Invalid schedule {expression}: {err}")),
+        }
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::Hourly => write!(f, "hourly"),
+            Schedule::Daily => write!(f, "daily"),
+            Schedule::Cron(schedule) => write!(f, "{schedule}"),
+        }
+    }
+}
+
+/// Runs `job` at every run of `schedule`, with the bounds of the period since
+/// the previous run. A run lasting past the next ones skips them rather than
+/// overlapping with them
+pub fn spawn_job<F, Fut>(name: String, schedule: Schedule, job: F)
+where
+    F: Fn(DateTime<Utc>, DateTime<Utc>) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<String>> + Send,
+{
+    info!("Scheduling {schedule} job {name}");
+
+    tokio::spawn(async move {
+        let mut previous = None;
+
+        loop {
+            let to = schedule.next_after(Utc::now());
+            let from = previous.unwrap_or_else(|| schedule.period_start(to));
+
+            sleep((to - Utc::now()).to_std().unwrap_or_default()).await;
+
+            match job(from, to).await {
+                Ok(outcome) => info!("Job {name}: {outcome}"),
+                Err(err) => error!("Job {name} failed: {err}"),
+            }
+
+            if schedule.next_after(to) < Utc::now() {
+                warn!("Job {name} lasted past its next run, which is skipped");
+            }
+
+            previous = Some(to);
+        }
+    });
+}
+
+/// Runs each export subscription at the end of every period of its schedule,
+/// exporting the orders created during that period
+pub fn spawn_exports(
+    persist_path: &str,
+    subscriptions: Vec<ExportSubscription>,
+    storage: Option<ObjectStorage>,
+) {
+    for subscription in subscriptions {
+        let persist_path = persist_path.to_string();
+        let name = format!(
+            "export {} of {}",
+            subscription.name,
+            subscription
+                .pair
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| "all pairs".to_string())
+        );
+
+        spawn_job(name, subscription.schedule.clone(), move |from, to| {
+            let persist_path = persist_path.clone();
+            let subscription = subscription.clone();
+            let storage = storage.clone();
+
+            async move {
+                let file =
+                    spawn_blocking(move || subscription.run(&persist_path, from, to)).await??;
+
+                match storage {
+                    Some(storage) => {
+                        let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+                        let key = storage
+                            .upload(&file, &format!("exports/{file_name}"))
+                            .await?;
+
+                        Ok(format!(
+                            "written to {} and uploaded to {key}",
+                            file.display()
+                        ))
+                    }
+                    None => Ok(format!("written to {}", file.display())),
+                }
+            }
+        });
+    }
+}
+
+/// Refreshes the `pair_summary_snapshot` table at every run of `schedule`
+pub fn spawn_summary_refresh(persist_path: &str, schedule: Schedule) {
+    let persist_path = persist_path.to_string();
+
+    spawn_job("refresh pair summary".to_string(), schedule, move |_, _| {
+        let persist_path = persist_path.clone();
+
+        async move {
+            let pairs = spawn_blocking(move || refresh_pair_summary(&persist_path)).await??;
+
+            Ok(format!("{pairs} pairs refreshed"))
+        }
+    });
+}
+
+/// Rolls the new orders up into `orders_hourly` and `orders_daily` at every
+/// run of `schedule`
+pub fn spawn_rollup(persist_path: &str, schedule: Schedule) {
+    let persist_path = persist_path.to_string();
+
+    spawn_job("roll up orders".to_string(), schedule, move |_, _| {
+        let persist_path = persist_path.clone();
+
+        async move {
+            let hours = spawn_blocking(move || rollup(&persist_path, None)).await??;
+
+            Ok(format!("{hours} pair hours rolled up"))
+        }
+    });
+}
+
+/// Maintains the database at every run of `schedule`
+pub fn spawn_maintenance(persist_path: &str, schedule: Schedule) {
+    let persist_path = persist_path.to_string();
+
+    spawn_job("maintain database".to_string(), schedule, move |_, _| {
+        let persist_path = persist_path.clone();
+
+        async move {
+            let maintenance = spawn_blocking(move || maintain(&persist_path)).await??;
+
+            Ok(maintenance.to_string())
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, min: u32, sec: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, min, sec).unwrap()
+    }
+
+    #[test]
+    fn hourly_runs_at_the_start_of_every_hour() {
+        let schedule = "hourly".parse::<Schedule>().unwrap();
+
+        assert_eq!(schedule.next_after(at(1, 10, 15, 0)), at(1, 11, 0, 0));
+        assert_eq!(schedule.next_after(at(1, 10, 0, 0)), at(1, 11, 0, 0));
+        assert_eq!(schedule.next_after(at(1, 23, 59, 59)), at(2, 0, 0, 0));
+    }
+
+    #[test]
+    fn daily_runs_at_midnight() {
+        let schedule = "daily".parse::<Schedule>().unwrap();
+
+        assert_eq!(schedule.next_after(at(1, 23, 59, 59)), at(2, 0, 0, 0));
+        assert_eq!(schedule.next_after(at(2, 0, 0, 0)), at(3, 0, 0, 0));
+    }
+
+    #[test]
+    fn cron_runs_strictly_after() {
+        let schedule = "0 */15 * * * *".parse::<Schedule>().unwrap();
+
+        assert_eq!(schedule.next_after(at(1, 10, 7, 0)), at(1, 10, 15, 0));
+        assert_eq!(schedule.next_after(at(1, 10, 15, 0)), at(1, 10, 30, 0));
+        assert_eq!(schedule.next_after(at(1, 23, 45, 0)), at(2, 0, 0, 0));
+    }
+
+    #[test]
+    fn the_period_ends_with_the_run() {
+        let hourly = "hourly".parse::<Schedule>().unwrap();
+        let daily = "daily".parse::<Schedule>().unwrap();
+        let quarterly = "0 */15 * * * *".parse::<Schedule>().unwrap();
+
+        assert_eq!(hourly.period_start(at(1, 11, 0, 0)), at(1, 10, 0, 0));
+        assert_eq!(daily.period_start(at(2, 0, 0, 0)), at(1, 0, 0, 0));
+        assert_eq!(quarterly.period_start(at(1, 10, 15, 0)), at(1, 10, 0, 0));
+    }
+
+    #[test]
+    fn rejects_an_invalid_schedule() {
+        assert!("weekly".parse::<Schedule>().is_err());
+        assert!("0 */15 * *".parse::<Schedule>().is_err());
+    }
+}
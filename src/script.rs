@@ -0,0 +1,103 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, anyhow, bail};
+use rhai::{AST, Dynamic, Engine, Map, Scope};
+
+use crate::fetch::Order;
+
+/// Maximum number of operations of a single call, so that a faulty script can't stall the collector
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// User script whose `process(order)` function filters, transforms or
+/// annotates every new order before it is stored and notified
+pub struct OrderScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl OrderScript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read script {}", path.display()))?;
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let ast = engine
+            .compile(content)
+            .map_err(|err| anyhow!("Failed to compile script {}: {err}", path.display()))?;
+
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == "process")
+        {
+            bail!(
+                "Script {} doesn't define a process function",
+                path.display()
+            );
+        }
+
+        Ok(Self { engine, ast })
+    }
+
+    /// Returns the order as transformed by the script with its annotations as
+    /// a JSON object, or nothing when the script returns `false` or `()`
+    pub fn process(&self, order: &Order) -> anyhow::Result<Option<(Order, Option<String>)>> {
+        let mut map = Map::new();
+        map.insert("type".into(), order.ty.to_string().into());
+        map.insert("blockchain".into(), order.blockchain.clone().into());
+        map.insert("crypto_amount".into(), order.crypto_amount.into());
+        map.insert("crypto_symbol".into(), order.crypto_symbol.clone().into());
+        map.insert("fiat_amount".into(), order.fiat_amount.into());
+        map.insert("fiat_price".into(), order.fiat_price.into());
+        map.insert("fiat_symbol".into(), order.fiat_symbol.clone().into());
+
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, "process", (map,))
+            .map_err(|err| anyhow!("{err}"))?;
+
+        if result.is_unit() || result.as_bool() == Ok(false) {
+            return Ok(None);
+        }
+
+        let mut map = result
+            .try_cast::<Map>()
+            .ok_or_else(|| anyhow!("process should return the order map, false or ()"))?;
+
+        let string = |map: &mut Map, key: &str| -> anyhow::Result<String> {
+            map.remove(key)
+                .ok_or_else(|| anyhow!("Missing {key}"))?
+                .into_string()
+                .map_err(|ty| anyhow!("{key} should be a string, not {ty}"))
+        };
+        let float = |map: &mut Map, key: &str| -> anyhow::Result<f64> {
+            let value = map.remove(key).ok_or_else(|| anyhow!("Missing {key}"))?;
+
+            value
+                .as_float()
+                .or_else(|_| value.as_int().map(|value| value as f64))
+                .map_err(|ty| anyhow!("{key} should be a number, not {ty}"))
+        };
+
+        let processed = Order {
+            ty: string(&mut map, "type")?.parse()?,
+            blockchain: string(&mut map, "blockchain")?,
+            crypto_amount: float(&mut map, "crypto_amount")?,
+            crypto_symbol: string(&mut map, "crypto_symbol")?,
+            fiat_amount: float(&mut map, "fiat_amount")?,
+            fiat_price: float(&mut map, "fiat_price")?,
+            fiat_symbol: string(&mut map, "fiat_symbol")?,
+            api_order_id: order.api_order_id.clone(),
+            api_created_at: order.api_created_at,
+            extra: order.extra.clone(),
+        };
+
+        // The keys left are the ones added by the script
+        let annotations = (!map.is_empty())
+            .then(|| serde_json::to_string(&map))
+            .transpose()?;
+
+        Ok(Some((processed, annotations)))
+    }
+}
@@ -0,0 +1,118 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+const SHEETS_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets/";
+
+/// Key file of a Google Cloud service account, the sheet must be shared with its email
+#[derive(Debug, Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Google Sheets API, authenticated as a service account
+pub struct SheetsClient {
+    client: reqwest::Client,
+    access_token: String,
+}
+
+impl SheetsClient {
+    pub async fn connect(client: reqwest::Client, credentials: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(credentials).with_context(|| {
+            format!("Failed to read credentials file {}", credentials.display())
+        })?;
+        let account = serde_json::from_str::<ServiceAccount>(&content).with_context(|| {
+            format!(
+                "Failed to parse service account credentials {}",
+                credentials.display()
+            )
+        })?;
+
+        let now = Utc::now().timestamp();
+        let assertion = encode(
+            &Header::new(Algorithm::RS256),
+            &Claims {
+                iss: &account.client_email,
+                scope: SCOPE,
+                aud: &account.token_uri,
+                iat: now,
+                exp: now + 3600,
+            },
+            &EncodingKey::from_rsa_pem(account.private_key.as_bytes())?,
+        )?;
+
+        let token = client
+            .post(&account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", &assertion),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .context("Google refused the service account")?
+            .json::<TokenResponse>()
+            .await?;
+
+        Ok(Self {
+            client,
+            access_token: token.access_token,
+        })
+    }
+
+    /// Appends `rows` after the last row of the table found in `range`, like `Daily!A1`
+    pub async fn append(
+        &self,
+        spreadsheet_id: &str,
+        range: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> anyhow::Result<()> {
+        let url = Url::parse_with_params(
+            &format!("{SHEETS_URL}{spreadsheet_id}/values/{range}:append"),
+            &[
+                ("valueInputOption", "USER_ENTERED"),
+                ("insertDataOption", "INSERT_ROWS"),
+            ],
+        )?;
+
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({ "values": rows }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "Google Sheets answered {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default().trim()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::{Duration, Instant};
+
+use reqwest::Url;
+use serde_json::json;
+use tokio::sync::{Mutex, OnceCell};
+use tracing::{debug, warn};
+
+use crate::{
+    args::Args,
+    fetch::{Order, OrderType},
+    sink::Cycle,
+};
+
+/// Batches kept while ClickHouse is unreachable, the oldest rows being dropped beyond
+const MAX_PENDING_BATCHES: usize = 10;
+
+pub struct ClickHouseSink {
+    client: reqwest::Client,
+    url: Url,
+    user: Option<String>,
+    password: Option<String>,
+    database: String,
+    orders_table: String,
+    cycles_table: String,
+    /// Tells the collectors writing to the same tables apart
+    collector: String,
+    async_insert: bool,
+    batch_rows: usize,
+    batch_interval: Duration,
+    bootstrapped: OnceCell<()>,
+    batch: Mutex<Batch>,
+}
+
+/// Rows as `JSONEachRow` lines
+#[derive(Default)]
+struct Batch {
+    orders: Vec<String>,
+    cycles: Vec<String>,
+    since: Option<Instant>,
+}
+
+impl ClickHouseSink {
+    pub fn new(client: reqwest::Client, url: &str, args: &Args) -> anyhow::Result<Self> {
+        Ok(Self {
+            client,
+            url: Url::parse(url)?,
+            user: args.clickhouse_user.clone(),
+            password: args.clickhouse_password.clone(),
+            database: args.clickhouse_database.clone(),
+            orders_table: args.clickhouse_orders_table.clone(),
+            cycles_table: args.clickhouse_cycles_table.clone(),
+            collector: args
+                .clickhouse_collector
+                .clone()
+                .or_else(|| std::env::var("HOSTNAME").ok())
+                .unwrap_or_else(|| "nash-stats".to_string()),
+            async_insert: args.clickhouse_async_insert,
+            batch_rows: args.clickhouse_batch_rows.max(1),
+            batch_interval: args.clickhouse_batch_interval,
+            bootstrapped: OnceCell::new(),
+            batch: Mutex::default(),
+        })
+    }
+
+    /// Adds the cycle to the batch, written when it is full or old enough
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        let mut batch = self.batch.lock().await;
+
+        for order in &cycle.orders {
+            batch.orders.push(self.order_row(order, cycle));
+        }
+
+        batch.cycles.push(self.cycle_row(cycle));
+        let since = *batch.since.get_or_insert_with(Instant::now);
+
+        if batch.orders.len() < self.batch_rows && since.elapsed() < self.batch_interval {
+            return Ok(());
+        }
+
+        self.bootstrapped
+            .get_or_try_init(|| self.bootstrap())
+            .await?;
+
+        if let Err(err) = self.write(&mut batch).await {
+            // Kept for the next cycle, within bounds
+            let max_rows = self.batch_rows * MAX_PENDING_BATCHES;
+            let overflow = batch.orders.len().saturating_sub(max_rows);
+
+            if overflow > 0 {
+                batch.orders.drain(..overflow);
+                warn!("ClickHouse unreachable, the {overflow} oldest orders were dropped");
+            }
+
+            let overflow = batch.cycles.len().saturating_sub(max_rows);
+            batch.cycles.drain(..overflow);
+
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    async fn write(&self, batch: &mut Batch) -> anyhow::Result<()> {
+        if !batch.orders.is_empty() {
+            self.insert(&self.orders_table, &batch.orders).await?;
+            debug!("Wrote {} orders to ClickHouse", batch.orders.len());
+            batch.orders.clear();
+        }
+
+        if !batch.cycles.is_empty() {
+            self.insert(&self.cycles_table, &batch.cycles).await?;
+            batch.cycles.clear();
+        }
+
+        batch.since = None;
+
+        Ok(())
+    }
+
+    async fn insert(&self, table: &str, rows: &[String]) -> anyhow::Result<()> {
+        let query = format!(
+            "INSERT INTO {}.{} FORMAT JSONEachRow",
+            quote_identifier(&self.database),
+            quote_identifier(table)
+        );
+        let mut settings = vec![("date_time_input_format", "best_effort")];
+
+        if self.async_insert {
+            settings.extend([("async_insert", "1"), ("wait_for_async_insert", "1")]);
+        }
+
+        self.query(&query, &settings, rows.join("\n")).await
+    }
+
+    /// Creates the database and the tables when missing
+    async fn bootstrap(&self) -> anyhow::Result<()> {
+        let database = quote_identifier(&self.database);
+
+        self.query(
+            &format!("CREATE DATABASE IF NOT EXISTS {database}"),
+            &[],
+            String::new(),
+        )
+        .await?;
+
+        self.query(
+            &format!(
+                r"CREATE TABLE IF NOT EXISTS {database}.{} (
+                    collector LowCardinality(String),
+                    created_at DateTime64(3, 'UTC'),
+                    type LowCardinality(String),
+                    blockchain LowCardinality(String),
+                    crypto_amount Float64,
+                    crypto_symbol LowCardinality(String),
+                    fiat_amount Float64,
+                    fiat_price Float64,
+                    fiat_symbol LowCardinality(String),
+                    api_order_id Nullable(String)
+                )
+                ENGINE = MergeTree
+                ORDER BY (crypto_symbol, fiat_symbol, created_at)",
+                quote_identifier(&self.orders_table)
+            ),
+            &[],
+            String::new(),
+        )
+        .await?;
+
+        self.query(
+            &format!(
+                r"CREATE TABLE IF NOT EXISTS {database}.{} (
+                    collector LowCardinality(String),
+                    at DateTime64(3, 'UTC'),
+                    new_orders UInt32,
+                    buy_fiat_amount Float64,
+                    sell_fiat_amount Float64,
+                    latency_ms UInt32
+                )
+                ENGINE = MergeTree
+                ORDER BY (collector, at)",
+                quote_identifier(&self.cycles_table)
+            ),
+            &[],
+            String::new(),
+        )
+        .await
+    }
+
+    async fn query(
+        &self,
+        query: &str,
+        settings: &[(&str, &str)],
+        body: String,
+    ) -> anyhow::Result<()> {
+        let mut url = self.url.clone();
+        url.query_pairs_mut()
+            .append_pair("query", query)
+            .extend_pairs(settings);
+
+        let mut request = self.client.post(url).body(body);
+
+        if let Some(user) = &self.user {
+            request = request.basic_auth(user, self.password.as_ref());
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "ClickHouse answered {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default().trim()
+            );
+        }
+
+        Ok(())
+    }
+
+    fn order_row(&self, order: &Order, cycle: &Cycle<'_>) -> String {
+        json!({
+            "collector": self.collector,
+            "created_at": cycle.at.to_rfc3339(),
+            "type": order.ty.to_string(),
+            "blockchain": order.blockchain,
+            "crypto_amount": order.crypto_amount,
+            "crypto_symbol": order.crypto_symbol,
+            "fiat_amount": order.fiat_amount,
+            "fiat_price": order.fiat_price,
+            "fiat_symbol": order.fiat_symbol,
+            "api_order_id": order.api_order_id,
+        })
+        .to_string()
+    }
+
+    fn cycle_row(&self, cycle: &Cycle<'_>) -> String {
+        let fiat_amount = |ty: OrderType| -> f64 {
+            cycle
+                .orders
+                .iter()
+                .filter(|order| order.ty == ty)
+                .map(|order| order.fiat_amount)
+                .sum()
+        };
+
+        json!({
+            "collector": self.collector,
+            "at": cycle.at.to_rfc3339(),
+            "new_orders": cycle.orders.len(),
+            "buy_fiat_amount": fiat_amount(OrderType::Buy),
+            "sell_fiat_amount": fiat_amount(OrderType::Sell),
+            "latency_ms": cycle.timing.latency.as_millis() as u64,
+        })
+        .to_string()
+    }
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('\\', "\\\\").replace('`', "\\`"))
+}
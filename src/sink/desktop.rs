@@ -0,0 +1,163 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use notify_rust::Notification;
+use tokio::task::spawn_blocking;
+
+use crate::{
+    args::Args,
+    config::NotifierConfig,
+    db::OrderFilter,
+    fetch::{Order, OrderType},
+    numbers::NumberFormat,
+    sink::Cycle,
+};
+
+const CAP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Native notifications of the workstation running the collector
+pub struct DesktopSink {
+    filter: OrderFilter,
+    max_per_minute: usize,
+    cap: Mutex<Cap>,
+    numbers: NumberFormat,
+}
+
+#[derive(Default)]
+struct Cap {
+    /// When the notifications of the last minute were shown
+    shown: VecDeque<Instant>,
+    /// Orders not shown since the cap was reached
+    skipped: usize,
+}
+
+impl Cap {
+    fn allow(&mut self, max_per_minute: usize) -> bool {
+        let now = Instant::now();
+
+        while self
+            .shown
+            .front()
+            .is_some_and(|shown| now.duration_since(*shown) >= CAP_WINDOW)
+        {
+            self.shown.pop_front();
+        }
+
+        if self.shown.len() >= max_per_minute {
+            return false;
+        }
+
+        self.shown.push_back(now);
+
+        true
+    }
+}
+
+impl DesktopSink {
+    pub fn new(args: &Args, notifiers: &NotifierConfig) -> Self {
+        let mut sink = Self {
+            filter: OrderFilter {
+                pair: args.desktop_pair.clone(),
+                ty: args.desktop_type.clone(),
+                min_fiat_amount: Some(args.desktop_min_fiat_amount),
+            },
+            max_per_minute: args.desktop_max_per_minute.max(1),
+            cap: Mutex::default(),
+            numbers: NumberFormat::from_args(args),
+        };
+
+        sink.configure(args, notifiers);
+
+        sink
+    }
+
+    /// Applies the threshold of the config
+    pub fn configure(&mut self, args: &Args, notifiers: &NotifierConfig) {
+        self.filter.min_fiat_amount = Some(
+            notifiers
+                .desktop_min_fiat_amount
+                .unwrap_or(args.desktop_min_fiat_amount),
+        );
+    }
+
+    pub async fn alert(&self, message: &str) -> anyhow::Result<()> {
+        show("Nash collector alert".to_string(), message.to_string()).await
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        let mut notifications = Vec::new();
+
+        if let Ok(mut cap) = self.cap.lock() {
+            for order in cycle
+                .orders
+                .iter()
+                .filter(|order| self.filter.matches(order))
+            {
+                if !cap.allow(self.max_per_minute) {
+                    cap.skipped += 1;
+                    continue;
+                }
+
+                // The first notification after a storm tells how much was missed
+                if cap.skipped > 0 {
+                    notifications.push((
+                        "Nash orders skipped".to_string(),
+                        format!(
+                            "{} orders weren't shown, more than {} per minute",
+                            cap.skipped, self.max_per_minute
+                        ),
+                    ));
+                    cap.skipped = 0;
+
+                    if !cap.allow(self.max_per_minute) {
+                        cap.skipped += 1;
+                        continue;
+                    }
+                }
+
+                notifications.push(notification(order, self.numbers));
+            }
+        }
+
+        for (summary, body) in notifications {
+            show(summary, body).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn notification(order: &Order, numbers: NumberFormat) -> (String, String) {
+    let side = match order.ty {
+        OrderType::Buy => "bought",
+        OrderType::Sell => "sold",
+    };
+
+    (
+        format!("Nash {} {}", order.ty, order.crypto_symbol),
+        format!(
+            "{} {side} for {} at {}",
+            numbers.crypto(order.crypto_amount, &order.crypto_symbol),
+            numbers.fiat(order.fiat_amount, &order.fiat_symbol),
+            numbers.fiat(order.fiat_price, &order.fiat_symbol)
+        ),
+    )
+}
+
+/// Shows the notification off the runtime, the desktop bus being synchronous
+async fn show(summary: String, body: String) -> anyhow::Result<()> {
+    spawn_blocking(move || {
+        Notification::new()
+            .appname("nash-stats")
+            .summary(&summary)
+            .body(&body)
+            .show()
+            .map(|_| ())
+    })
+    .await??;
+
+    Ok(())
+}
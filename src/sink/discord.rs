@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use tokio::task::spawn_blocking;
+
+use crate::{
+    args::Args,
+    db::{PairSummary, get_summary},
+    fetch::{Order, OrderType},
+    numbers::NumberFormat,
+    scheduler::{Schedule, spawn_job},
+    sink::{Cycle, MessageTemplate},
+};
+
+/// Discord rejects messages with more embeds than this
+const MAX_EMBEDS: usize = 10;
+
+/// Discord rejects embeds with more fields than this
+const MAX_FIELDS: usize = 25;
+
+pub struct DiscordSink {
+    client: reqwest::Client,
+    webhook_url: String,
+    template: Option<MessageTemplate>,
+    numbers: NumberFormat,
+}
+
+impl DiscordSink {
+    pub fn new(client: reqwest::Client, webhook_url: &str, args: &Args) -> anyhow::Result<Self> {
+        if args.discord_daily_summary {
+            let client = client.clone();
+            let webhook_url = webhook_url.to_string();
+            let persist_path = args.persist_path.clone();
+            let numbers = NumberFormat::from_args(args);
+
+            spawn_job(
+                "Discord daily summary".to_string(),
+                Schedule::Daily,
+                move |from, to| {
+                    let client = client.clone();
+                    let webhook_url = webhook_url.clone();
+                    let persist_path = persist_path.clone();
+
+                    async move {
+                        let summaries =
+                            spawn_blocking(move || get_summary(&persist_path, from, to)).await??;
+
+                        post(
+                            &client,
+                            &webhook_url,
+                            vec![summary_embed(&summaries, from, numbers)],
+                        )
+                        .await?;
+
+                        Ok(format!("{} pairs posted", summaries.len()))
+                    }
+                },
+            );
+        }
+
+        let mut sink = Self {
+            client,
+            webhook_url: webhook_url.to_string(),
+            template: None,
+            numbers: NumberFormat::from_args(args),
+        };
+
+        sink.configure(args)?;
+
+        Ok(sink)
+    }
+
+    /// Reads the template again, leaving the sink as it was when it is invalid
+    pub fn configure(&mut self, args: &Args) -> anyhow::Result<()> {
+        self.template = args
+            .discord_template
+            .as_deref()
+            .map(|path| MessageTemplate::load(path, args.notification_locale, args.timezone))
+            .transpose()?;
+
+        Ok(())
+    }
+
+    pub async fn alert(&self, message: &str) -> anyhow::Result<()> {
+        let embed = json!({
+            "title": "Nash collector alert",
+            "description": message,
+            "color": 0xC62828,
+        });
+
+        post(&self.client, &self.webhook_url, vec![embed]).await
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        for orders in cycle.orders.chunks(MAX_EMBEDS) {
+            let embeds = orders
+                .iter()
+                .map(|order| {
+                    let mut embed = order_embed(order, cycle, self.numbers);
+
+                    if let Some(template) = &self.template {
+                        embed["description"] = template.render(order, cycle)?.into();
+                    }
+
+                    Ok(embed)
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            post(&self.client, &self.webhook_url, embeds).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn post(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    embeds: Vec<Value>,
+) -> anyhow::Result<()> {
+    client
+        .post(webhook_url)
+        .json(&json!({ "embeds": embeds }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+fn order_embed(order: &Order, cycle: &Cycle<'_>, numbers: NumberFormat) -> Value {
+    let action = match order.ty {
+        OrderType::Buy => "Buy",
+        OrderType::Sell => "Sell",
+    };
+
+    json!({
+        "title": format!(
+            "{action} {}",
+            numbers.crypto(order.crypto_amount, &order.crypto_symbol)
+        ),
+        "description": format!(
+            "{} at {}",
+            numbers.fiat(order.fiat_amount, &order.fiat_symbol),
+            numbers.fiat(order.fiat_price, &order.fiat_symbol)
+        ),
+        "color": symbol_color(&order.crypto_symbol),
+        "fields": [{ "name": "Blockchain", "value": order.blockchain, "inline": true }],
+        "timestamp": cycle.at.to_rfc3339(),
+    })
+}
+
+fn summary_embed(summaries: &[PairSummary], day: DateTime<Utc>, numbers: NumberFormat) -> Value {
+    let fields = summaries
+        .iter()
+        .take(MAX_FIELDS)
+        .map(|summary| {
+            json!({
+                "name": format!("{}/{}", summary.crypto_symbol, summary.fiat_symbol),
+                "value": format!(
+                    "{} orders ({} buys, {} sells)\n{} volume, avg price {}",
+                    summary.orders,
+                    summary.buys,
+                    summary.sells,
+                    numbers.fiat(summary.fiat_volume, &summary.fiat_symbol),
+                    numbers.fiat(summary.avg_price, &summary.fiat_symbol)
+                ),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut embed = json!({
+        "title": format!("Nash activity on {}", day.format("%Y-%m-%d")),
+        "fields": fields,
+    });
+
+    if summaries.is_empty() {
+        embed["description"] = "No orders".into();
+    }
+
+    embed
+}
+
+/// Brand color of well known symbols, and a stable arbitrary color for the others
+fn symbol_color(symbol: &str) -> u32 {
+    match symbol {
+        "btc" | "BTC" => 0xF7931A,
+        "eth" | "ETH" => 0x627EEA,
+        "usdc" | "USDC" => 0x2775CA,
+        "usdt" | "USDT" => 0x26A17B,
+        "neo" | "NEO" => 0x58BF00,
+        // 32-bit FNV-1a, fixed unlike the standard hasher, so that a symbol
+        // keeps its color across releases
+        other => {
+            let hash = other
+                .to_uppercase()
+                .bytes()
+                .fold(0x811C9DC5_u32, |hash, byte| {
+                    (hash ^ byte as u32).wrapping_mul(0x01000193)
+                });
+
+            hash & 0xFFFFFF
+        }
+    }
+}
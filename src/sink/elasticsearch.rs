@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use reqwest::{StatusCode, Url};
+use serde_json::{Value, json};
+use tokio::{sync::OnceCell, time::sleep};
+use tracing::warn;
+
+use crate::{args::Args, fetch::Order, sink::Cycle};
+
+/// Attempts of a bulk request rejected with 429, the wait doubling between them
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub struct ElasticsearchSink {
+    client: reqwest::Client,
+    url: Url,
+    username: Option<String>,
+    password: Option<String>,
+    api_key: Option<String>,
+    index_prefix: String,
+    template_installed: OnceCell<()>,
+}
+
+impl ElasticsearchSink {
+    pub fn new(client: reqwest::Client, url: &str, args: &Args) -> anyhow::Result<Self> {
+        Ok(Self {
+            client,
+            url: Url::parse(&format!("{}/", url.trim_end_matches('/')))?,
+            username: args.elasticsearch_username.clone(),
+            password: args.elasticsearch_password.clone(),
+            api_key: args.elasticsearch_api_key.clone(),
+            index_prefix: args.elasticsearch_index_prefix.clone(),
+            template_installed: OnceCell::new(),
+        })
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        if cycle.orders.is_empty() {
+            return Ok(());
+        }
+
+        self.template_installed
+            .get_or_try_init(|| self.install_template())
+            .await?;
+
+        let index = format!("{}-{}", self.index_prefix, cycle.at.format("%Y.%m"));
+        let mut actions = cycle
+            .orders
+            .iter()
+            .map(|order| {
+                let action = json!({ "create": { "_index": index } });
+                format!("{action}\n{}\n", document(order, cycle))
+            })
+            .collect::<Vec<_>>();
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let response = self
+                .request(reqwest::Method::POST, "_bulk")?
+                .header("Content-Type", "application/x-ndjson")
+                .body(actions.concat())
+                .send()
+                .await?;
+
+            let retried = match response.status() {
+                StatusCode::TOO_MANY_REQUESTS => actions,
+                status if status.is_success() => {
+                    let body = response.json::<Value>().await?;
+
+                    rejected_actions(&body, actions)?
+                }
+                status => bail!(
+                    "Elasticsearch answered {status}: {}",
+                    response.text().await.unwrap_or_default().trim()
+                ),
+            };
+
+            if retried.is_empty() {
+                return Ok(());
+            }
+
+            if attempt < MAX_ATTEMPTS {
+                warn!(
+                    "Elasticsearch is overloaded, retrying {} orders",
+                    retried.len()
+                );
+                sleep(RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+            }
+
+            actions = retried;
+        }
+
+        bail!(
+            "Elasticsearch kept rejecting {} orders with 429",
+            actions.len()
+        )
+    }
+
+    /// Maps the orders of the dated indices, created on their first order
+    async fn install_template(&self) -> anyhow::Result<()> {
+        let template = json!({
+            "index_patterns": [format!("{}-*", self.index_prefix)],
+            "template": {
+                "mappings": {
+                    "properties": {
+                        "@timestamp": { "type": "date" },
+                        "type": { "type": "keyword" },
+                        "blockchain": { "type": "keyword" },
+                        "crypto_amount": { "type": "double" },
+                        "crypto_symbol": { "type": "keyword" },
+                        "fiat_amount": { "type": "double" },
+                        "fiat_price": { "type": "double" },
+                        "fiat_symbol": { "type": "keyword" },
+                        "pair": { "type": "keyword" },
+                        "api_order_id": { "type": "keyword" },
+                        "api_created_at": { "type": "date" }
+                    }
+                }
+            }
+        });
+
+        self.request(
+            reqwest::Method::PUT,
+            &format!("_index_template/{}", self.index_prefix),
+        )?
+        .json(&template)
+        .send()
+        .await?
+        .error_for_status()?;
+
+        Ok(())
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> anyhow::Result<reqwest::RequestBuilder> {
+        let mut request = self.client.request(method, self.url.join(path)?);
+
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("ApiKey {api_key}"));
+        } else if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+
+        Ok(request)
+    }
+}
+
+fn document(order: &Order, cycle: &Cycle<'_>) -> Value {
+    json!({
+        "@timestamp": cycle.at.to_rfc3339(),
+        "type": order.ty.to_string(),
+        "blockchain": order.blockchain,
+        "crypto_amount": order.crypto_amount,
+        "crypto_symbol": order.crypto_symbol,
+        "fiat_amount": order.fiat_amount,
+        "fiat_price": order.fiat_price,
+        "fiat_symbol": order.fiat_symbol,
+        "pair": format!("{}/{}", order.crypto_symbol, order.fiat_symbol),
+        "api_order_id": order.api_order_id,
+        "api_created_at": order.api_created_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// Returns the actions rejected with 429, to be retried, failing on the other
+/// errors of the bulk response
+fn rejected_actions(body: &Value, actions: Vec<String>) -> anyhow::Result<Vec<String>> {
+    if body["errors"] != Value::Bool(true) {
+        return Ok(Vec::new());
+    }
+
+    let items = body["items"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut retried = Vec::new();
+
+    for (action, item) in actions.into_iter().zip(items) {
+        let result = &item["create"];
+
+        match result["status"].as_u64() {
+            Some(429) => retried.push(action),
+            Some(status) if status >= 300 => bail!(
+                "Elasticsearch rejected an order with {status}: {}",
+                result["error"]["reason"]
+                    .as_str()
+                    .unwrap_or("unknown reason")
+            ),
+            _ => {}
+        }
+    }
+
+    Ok(retried)
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use reqwest::Url;
+
+use crate::{
+    args::Args,
+    fetch::{Order, OrderType},
+    sink::Cycle,
+};
+
+pub struct InfluxSink {
+    client: reqwest::Client,
+    write_url: Url,
+    token: Option<String>,
+}
+
+impl InfluxSink {
+    pub fn new(client: reqwest::Client, url: &str, args: &Args) -> anyhow::Result<Self> {
+        let org = args
+            .influx_org
+            .as_deref()
+            .ok_or_else(|| anyhow!("An InfluxDB org is required"))?;
+        let bucket = args
+            .influx_bucket
+            .as_deref()
+            .ok_or_else(|| anyhow!("An InfluxDB bucket is required"))?;
+
+        let write_url = Url::parse_with_params(
+            &format!("{}/api/v2/write", url.trim_end_matches('/')),
+            &[("org", org), ("bucket", bucket), ("precision", "ns")],
+        )?;
+
+        Ok(Self {
+            client,
+            write_url,
+            token: args.influx_token.clone(),
+        })
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        let mut request = self
+            .client
+            .post(self.write_url.clone())
+            .body(to_line_protocol(cycle));
+
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Token {token}"));
+        }
+
+        request.send().await?.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+fn to_line_protocol(cycle: &Cycle<'_>) -> String {
+    let timestamp = cycle.at.timestamp_nanos_opt().unwrap_or_default();
+    let mut lines = String::new();
+    let mut buy_fiat_amount = 0.0;
+    let mut sell_fiat_amount = 0.0;
+
+    for (i, order) in cycle.orders.iter().enumerate() {
+        match order.ty {
+            OrderType::Buy => buy_fiat_amount += order.fiat_amount,
+            OrderType::Sell => sell_fiat_amount += order.fiat_amount,
+        }
+
+        // Points sharing a series and a timestamp overwrite each other,
+        // so each order of the cycle is shifted by a nanosecond
+        write_order(&mut lines, order, timestamp + i as i64);
+    }
+
+    let _ = writeln!(
+        lines,
+        "nash_cycle new_orders={}i,buy_fiat_amount={},sell_fiat_amount={},latency_ms={}i {}",
+        cycle.orders.len(),
+        buy_fiat_amount,
+        sell_fiat_amount,
+        cycle.timing.latency.as_millis(),
+        timestamp
+    );
+
+    lines
+}
+
+fn write_order(lines: &mut String, order: &Order, timestamp: i64) {
+    let _ = writeln!(
+        lines,
+        "nash_order,type={},blockchain={},crypto_symbol={},fiat_symbol={} crypto_amount={},fiat_amount={},fiat_price={} {}",
+        order.ty,
+        escape_tag(&order.blockchain),
+        escape_tag(&order.crypto_symbol),
+        escape_tag(&order.fiat_symbol),
+        order.crypto_amount,
+        order.fiat_amount,
+        order.fiat_price,
+        timestamp
+    );
+}
+
+fn escape_tag(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
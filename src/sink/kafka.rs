@@ -0,0 +1,57 @@
+use rdkafka::{
+    ClientConfig,
+    producer::{FutureProducer, FutureRecord},
+};
+use tracing::error;
+
+use crate::{args::Args, sink::Cycle};
+
+pub struct KafkaSink {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl KafkaSink {
+    pub fn new(brokers: &str, args: &Args) -> anyhow::Result<Self> {
+        // librdkafka keeps undelivered messages in its own queue and retries
+        // them until the timeout, which covers short broker outages
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set(
+                "message.timeout.ms",
+                (args.kafka_buffer_timeout * 1000).to_string(),
+            )
+            .set("enable.idempotence", "true")
+            .create()?;
+
+        Ok(Self {
+            producer,
+            topic: args.kafka_topic.clone(),
+        })
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        for order in &cycle.orders {
+            let payload = serde_json::to_vec(order)?;
+            let record = FutureRecord::to(&self.topic)
+                .key(&order.crypto_symbol)
+                .payload(&payload)
+                .timestamp(cycle.at.timestamp_millis());
+
+            let delivery = self.producer.send_result(record).map_err(|(err, _)| err)?;
+
+            // Waiting for the delivery report here would stall the fetch loop
+            // during an outage, so it is awaited in the background instead
+            let order = order.to_string();
+            tokio::spawn(async move {
+                match delivery.await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err((err, _))) => error!("Failed to deliver order {order} to Kafka: {err}"),
+                    Err(_) => error!("Delivery of order {order} to Kafka was cancelled"),
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
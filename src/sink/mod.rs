@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    args::Args,
+    config::NotifierConfig,
+    fetch::{Order, Timing},
+};
+
+pub use self::redis::RedisSink;
+pub use clickhouse::ClickHouseSink;
+#[cfg(feature = "desktop")]
+pub use desktop::DesktopSink;
+pub use discord::DiscordSink;
+pub use elasticsearch::ElasticsearchSink;
+pub use influx::InfluxSink;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaSink;
+pub use mqtt::MqttSink;
+pub use nats::NatsSink;
+pub use ntfy::NtfySink;
+pub use slack::SlackSink;
+pub use template::MessageTemplate;
+
+mod clickhouse;
+#[cfg(feature = "desktop")]
+mod desktop;
+mod discord;
+mod elasticsearch;
+mod influx;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod mqtt;
+mod nats;
+mod ntfy;
+mod redis;
+mod slack;
+mod template;
+
+/// New orders found by a single fetch
+#[derive(Debug)]
+pub struct Cycle<'a> {
+    pub at: DateTime<Utc>,
+    pub orders: Vec<&'a Order>,
+    pub timing: Timing,
+}
+
+pub enum Sink {
+    Influx(InfluxSink),
+    ClickHouse(ClickHouseSink),
+    Elasticsearch(ElasticsearchSink),
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaSink),
+    Mqtt(MqttSink),
+    Redis(RedisSink),
+    Nats(NatsSink),
+    Discord(DiscordSink),
+    Slack(SlackSink),
+    Ntfy(NtfySink),
+    #[cfg(feature = "desktop")]
+    Desktop(DesktopSink),
+}
+
+impl Sink {
+    pub fn from_args(
+        args: &Args,
+        notifiers: &NotifierConfig,
+        client: &reqwest::Client,
+    ) -> anyhow::Result<Vec<Sink>> {
+        let mut sinks = Vec::new();
+
+        if let Some(url) = &args.influx_url {
+            sinks.push(Sink::Influx(InfluxSink::new(client.clone(), url, args)?));
+        }
+
+        if let Some(url) = &args.clickhouse_url {
+            sinks.push(Sink::ClickHouse(ClickHouseSink::new(
+                client.clone(),
+                url,
+                args,
+            )?));
+        }
+
+        if let Some(url) = &args.elasticsearch_url {
+            sinks.push(Sink::Elasticsearch(ElasticsearchSink::new(
+                client.clone(),
+                url,
+                args,
+            )?));
+        }
+
+        #[cfg(feature = "kafka")]
+        if let Some(brokers) = &args.kafka_brokers {
+            sinks.push(Sink::Kafka(KafkaSink::new(brokers, args)?));
+        }
+
+        if let Some(host) = &args.mqtt_host {
+            sinks.push(Sink::Mqtt(MqttSink::new(host, args)?));
+        }
+
+        if let Some(url) = &args.redis_url {
+            sinks.push(Sink::Redis(RedisSink::new(url, args)?));
+        }
+
+        if let Some(url) = &args.nats_url {
+            sinks.push(Sink::Nats(NatsSink::new(url, args)?));
+        }
+
+        if let Some(webhook_url) = &args.discord_webhook_url {
+            sinks.push(Sink::Discord(DiscordSink::new(
+                client.clone(),
+                webhook_url,
+                args,
+            )?));
+        }
+
+        if let Some(webhook_url) = &args.slack_webhook_url {
+            sinks.push(Sink::Slack(SlackSink::new(
+                client.clone(),
+                webhook_url,
+                args,
+                notifiers,
+            )?));
+        }
+
+        if let Some(topic_url) = &args.ntfy_url {
+            sinks.push(Sink::Ntfy(NtfySink::new(
+                client.clone(),
+                topic_url,
+                args,
+                notifiers,
+            )?));
+        }
+
+        #[cfg(feature = "desktop")]
+        if args.desktop_notify {
+            sinks.push(Sink::Desktop(DesktopSink::new(args, notifiers)));
+        }
+
+        Ok(sinks)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sink::Influx(_) => "InfluxDB",
+            Sink::ClickHouse(_) => "ClickHouse",
+            Sink::Elasticsearch(_) => "Elasticsearch",
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(_) => "Kafka",
+            Sink::Mqtt(_) => "MQTT",
+            Sink::Redis(_) => "Redis",
+            Sink::Nats(_) => "NATS",
+            Sink::Discord(_) => "Discord",
+            Sink::Slack(_) => "Slack",
+            Sink::Ntfy(_) => "ntfy",
+            #[cfg(feature = "desktop")]
+            Sink::Desktop(_) => "desktop",
+        }
+    }
+
+    /// Reads the message templates again and applies the notifier thresholds
+    /// of the config, the other sinks being left as they are
+    pub fn configure(&mut self, args: &Args, notifiers: &NotifierConfig) -> anyhow::Result<()> {
+        match self {
+            Sink::Discord(sink) => sink.configure(args),
+            Sink::Slack(sink) => sink.configure(args, notifiers),
+            Sink::Ntfy(sink) => sink.configure(args, notifiers),
+            #[cfg(feature = "desktop")]
+            Sink::Desktop(sink) => {
+                sink.configure(args, notifiers);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sends an operational alert, sinks that aren't notifiers ignore it
+    pub async fn alert(&self, message: &str) -> anyhow::Result<()> {
+        match self {
+            Sink::Discord(sink) => sink.alert(message).await,
+            Sink::Slack(sink) => sink.alert(message).await,
+            Sink::Ntfy(sink) => sink.alert(message).await,
+            #[cfg(feature = "desktop")]
+            Sink::Desktop(sink) => sink.alert(message).await,
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        match self {
+            Sink::Influx(sink) => sink.publish(cycle).await,
+            Sink::ClickHouse(sink) => sink.publish(cycle).await,
+            Sink::Elasticsearch(sink) => sink.publish(cycle).await,
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(sink) => sink.publish(cycle).await,
+            Sink::Mqtt(sink) => sink.publish(cycle).await,
+            Sink::Redis(sink) => sink.publish(cycle).await,
+            Sink::Nats(sink) => sink.publish(cycle).await,
+            Sink::Discord(sink) => sink.publish(cycle).await,
+            Sink::Slack(sink) => sink.publish(cycle).await,
+            Sink::Ntfy(sink) => sink.publish(cycle).await,
+            #[cfg(feature = "desktop")]
+            Sink::Desktop(sink) => sink.publish(cycle).await,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use rumqttc::{AsyncClient, MqttOptions, QoS, Transport};
+use tokio::time::sleep;
+use tracing::{error, warn};
+
+use crate::{args::Args, fetch::Order, sink::Cycle};
+
+pub struct MqttSink {
+    client: AsyncClient,
+    topic: String,
+    qos: QoS,
+}
+
+impl MqttSink {
+    pub fn new(host: &str, args: &Args) -> anyhow::Result<Self> {
+        let qos = match args.mqtt_qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            other => bail!("MQTT QoS {other} not supported"),
+        };
+
+        let mut options = MqttOptions::new("nash-stats", host, args.mqtt_port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let Some(username) = &args.mqtt_username {
+            options.set_credentials(username, args.mqtt_password.as_deref().unwrap_or_default());
+        }
+
+        if args.mqtt_tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, 100);
+
+        // The event loop drives the connection, and reconnects on the next poll after an error
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = event_loop.poll().await {
+                    error!("MQTT connection error: {err}");
+                    sleep(Duration::from_secs(5)).await;
+                }
+            }
+        });
+
+        Ok(Self {
+            client,
+            topic: args.mqtt_topic.clone(),
+            qos,
+        })
+    }
+
+    /// Queues the orders without waiting, so that a broker outage doesn't
+    /// stall the fetch loop once the request channel is full
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        let mut dropped = 0;
+
+        for order in &cycle.orders {
+            let queued = self.client.try_publish(
+                self.topic_for(order),
+                self.qos,
+                false,
+                serde_json::to_vec(order)?,
+            );
+
+            if queued.is_err() {
+                dropped += 1;
+            }
+        }
+
+        if dropped > 0 {
+            warn!("MQTT request queue full, {dropped} orders were dropped");
+        }
+
+        Ok(())
+    }
+
+    fn topic_for(&self, order: &Order) -> String {
+        self.topic
+            .replace("{symbol}", &order.crypto_symbol)
+            .replace("{fiat}", &order.fiat_symbol)
+            .replace("{type}", &order.ty.to_string())
+    }
+}
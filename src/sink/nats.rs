@@ -0,0 +1,141 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use async_nats::{Client, ConnectOptions, jetstream};
+use tokio::{
+    sync::{Mutex, Notify},
+    time::sleep,
+};
+use tracing::{error, info, warn};
+
+use crate::{args::Args, fetch::Order, sink::Cycle};
+
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Orders not acknowledged yet, oldest first, as their subject and payload
+type Pending = Arc<Mutex<VecDeque<(String, Vec<u8>)>>>;
+
+pub struct NatsSink {
+    pending: Pending,
+    published: Arc<Notify>,
+    subject: String,
+    buffer_size: usize,
+}
+
+impl NatsSink {
+    pub fn new(url: &str, args: &Args) -> anyhow::Result<Self> {
+        let pending = Pending::default();
+        let published = Arc::new(Notify::new());
+
+        tokio::spawn(deliver(
+            url.to_string(),
+            args.nats_jetstream,
+            pending.clone(),
+            published.clone(),
+        ));
+
+        Ok(Self {
+            pending,
+            published,
+            subject: args.nats_subject.clone(),
+            buffer_size: args.nats_buffer_size,
+        })
+    }
+
+    /// Queues the orders, delivered in the background so that an outage
+    /// doesn't stall the fetch loop
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        if cycle.orders.is_empty() {
+            return Ok(());
+        }
+
+        let mut pending = self.pending.lock().await;
+
+        for order in &cycle.orders {
+            pending.push_back((self.subject_for(order), serde_json::to_vec(order)?));
+        }
+
+        let overflow = pending.len().saturating_sub(self.buffer_size);
+
+        if overflow > 0 {
+            pending.drain(..overflow);
+            warn!("NATS buffer full, the {overflow} oldest orders were dropped");
+        }
+
+        drop(pending);
+        self.published.notify_one();
+
+        Ok(())
+    }
+
+    fn subject_for(&self, order: &Order) -> String {
+        self.subject
+            .replace("{symbol}", &order.crypto_symbol)
+            .replace("{fiat}", &order.fiat_symbol)
+            .replace("{type}", &order.ty.to_string())
+    }
+}
+
+/// Publishes the pending orders in order, each being kept until NATS, or the
+/// JetStream stream when `jetstream`, acknowledged it
+async fn deliver(url: String, jetstream: bool, pending: Pending, published: Arc<Notify>) {
+    let client = match ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect(url.as_str())
+        .await
+    {
+        Ok(client) => client,
+        Err(err) => {
+            error!("Failed to connect to NATS at {url}: {err}");
+            return;
+        }
+    };
+
+    info!("Connected to NATS at {url}");
+
+    let context = jetstream.then(|| jetstream::new(client.clone()));
+
+    loop {
+        published.notified().await;
+
+        loop {
+            let Some((subject, payload)) = pending.lock().await.front().cloned() else {
+                break;
+            };
+
+            let result = match &context {
+                Some(context) => publish_acked(context, subject, payload).await,
+                None => publish(&client, subject, payload).await,
+            };
+
+            match result {
+                Ok(()) => {
+                    pending.lock().await.pop_front();
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to publish to NATS, {} orders pending: {err}",
+                        pending.lock().await.len()
+                    );
+                    sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+}
+
+async fn publish(client: &Client, subject: String, payload: Vec<u8>) -> anyhow::Result<()> {
+    client.publish(subject, payload.into()).await?;
+    client.flush().await?;
+
+    Ok(())
+}
+
+async fn publish_acked(
+    context: &jetstream::Context,
+    subject: String,
+    payload: Vec<u8>,
+) -> anyhow::Result<()> {
+    context.publish(subject, payload.into()).await?.await?;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use crate::{
+    args::Args,
+    config::NotifierConfig,
+    fetch::{Order, OrderType},
+    numbers::{NumberFormat, OrderStyle},
+    sink::{Cycle, MessageTemplate},
+};
+
+pub struct NtfySink {
+    client: reqwest::Client,
+    topic_url: String,
+    token: Option<String>,
+    min_fiat_amount: f64,
+    high_fiat_amount: f64,
+    urgent_fiat_amount: f64,
+    template: Option<MessageTemplate>,
+    numbers: NumberFormat,
+}
+
+impl NtfySink {
+    pub fn new(
+        client: reqwest::Client,
+        topic_url: &str,
+        args: &Args,
+        notifiers: &NotifierConfig,
+    ) -> anyhow::Result<Self> {
+        let mut sink = Self {
+            client,
+            topic_url: topic_url.to_string(),
+            token: args.ntfy_token.clone(),
+            min_fiat_amount: args.ntfy_min_fiat_amount,
+            high_fiat_amount: args.ntfy_high_fiat_amount,
+            urgent_fiat_amount: args.ntfy_urgent_fiat_amount,
+            template: None,
+            numbers: NumberFormat::from_args(args),
+        };
+
+        sink.configure(args, notifiers)?;
+
+        Ok(sink)
+    }
+
+    /// Reads the template again and applies the thresholds of the config,
+    /// leaving the sink as it was when the template is invalid
+    pub fn configure(&mut self, args: &Args, notifiers: &NotifierConfig) -> anyhow::Result<()> {
+        self.template = args
+            .ntfy_template
+            .as_deref()
+            .map(|path| MessageTemplate::load(path, args.notification_locale, args.timezone))
+            .transpose()?;
+        self.min_fiat_amount = notifiers
+            .ntfy_min_fiat_amount
+            .unwrap_or(args.ntfy_min_fiat_amount);
+        self.high_fiat_amount = notifiers
+            .ntfy_high_fiat_amount
+            .unwrap_or(args.ntfy_high_fiat_amount);
+        self.urgent_fiat_amount = notifiers
+            .ntfy_urgent_fiat_amount
+            .unwrap_or(args.ntfy_urgent_fiat_amount);
+
+        Ok(())
+    }
+
+    pub async fn alert(&self, message: &str) -> anyhow::Result<()> {
+        let mut request = self
+            .client
+            .post(&self.topic_url)
+            .header("Title", "Nash collector alert")
+            .header("Priority", "5")
+            .header("Tags", "warning")
+            .body(message.to_string());
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        request.send().await?.error_for_status()?;
+
+        Ok(())
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        for order in &cycle.orders {
+            if order.fiat_amount < self.min_fiat_amount {
+                continue;
+            }
+
+            let tag = match order.ty {
+                OrderType::Buy => "chart_with_upwards_trend",
+                OrderType::Sell => "chart_with_downwards_trend",
+            };
+
+            let mut request = self
+                .client
+                .post(&self.topic_url)
+                .header(
+                    "Title",
+                    format!("Nash {} {}", order.ty, order.crypto_symbol),
+                )
+                .header("Priority", self.priority(order).to_string())
+                .header("Tags", tag)
+                .body(match &self.template {
+                    Some(template) => template.render(order, cycle)?,
+                    None => self.numbers.order(order, OrderStyle::Verbose).to_string(),
+                });
+
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            request.send().await?.error_for_status()?;
+        }
+
+        Ok(())
+    }
+
+    /// Maps the order size to a ntfy priority, from 3 (default) to 5 (max)
+    fn priority(&self, order: &Order) -> u8 {
+        if order.fiat_amount >= self.urgent_fiat_amount {
+            5
+        } else if order.fiat_amount >= self.high_fiat_amount {
+            4
+        } else {
+            3
+        }
+    }
+}
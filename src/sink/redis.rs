@@ -0,0 +1,62 @@
+use redis::{Client, aio::ConnectionManager};
+use tokio::sync::OnceCell;
+
+use crate::{args::Args, fetch::Order, sink::Cycle};
+
+pub struct RedisSink {
+    client: Client,
+    /// Connected on the first publish, then reconnected by the manager with
+    /// an exponential backoff whenever the connection drops
+    connection: OnceCell<ConnectionManager>,
+    channel: String,
+    symbol_channel: Option<String>,
+}
+
+impl RedisSink {
+    pub fn new(url: &str, args: &Args) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::open(url)?,
+            connection: OnceCell::new(),
+            channel: args.redis_channel.clone(),
+            symbol_channel: Some(args.redis_symbol_channel.clone())
+                .filter(|channel| !channel.is_empty()),
+        })
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        if cycle.orders.is_empty() {
+            return Ok(());
+        }
+
+        // A failed first connection is retried by the next publish
+        let mut connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?
+            .clone();
+        let mut pipeline = redis::pipe();
+
+        for order in &cycle.orders {
+            let payload = serde_json::to_string(order)?;
+
+            pipeline.publish(&self.channel, &payload).ignore();
+
+            if let Some(channel) = self.channel_for(order) {
+                pipeline.publish(channel, &payload).ignore();
+            }
+        }
+
+        pipeline.query_async::<()>(&mut connection).await?;
+
+        Ok(())
+    }
+
+    fn channel_for(&self, order: &Order) -> Option<String> {
+        self.symbol_channel.as_ref().map(|channel| {
+            channel
+                .replace("{symbol}", &order.crypto_symbol)
+                .replace("{fiat}", &order.fiat_symbol)
+                .replace("{type}", &order.ty.to_string())
+        })
+    }
+}
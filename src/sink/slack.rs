@@ -0,0 +1,182 @@
+use serde_json::{Value, json};
+
+use crate::{
+    args::Args,
+    config::NotifierConfig,
+    fetch::{Order, OrderType},
+    numbers::{NumberFormat, OrderStyle},
+    sink::{Cycle, MessageTemplate},
+};
+
+/// Slack rejects messages with more blocks than this, each order taking up to two
+const MAX_ORDERS_PER_MESSAGE: usize = 24;
+
+pub struct SlackSink {
+    client: reqwest::Client,
+    webhook_url: String,
+    min_fiat_amount: f64,
+    alert_fiat_amount: Option<f64>,
+    template: Option<MessageTemplate>,
+    numbers: NumberFormat,
+}
+
+impl SlackSink {
+    pub fn new(
+        client: reqwest::Client,
+        webhook_url: &str,
+        args: &Args,
+        notifiers: &NotifierConfig,
+    ) -> anyhow::Result<Self> {
+        let mut sink = Self {
+            client,
+            webhook_url: webhook_url.to_string(),
+            min_fiat_amount: args.slack_min_fiat_amount,
+            alert_fiat_amount: args.slack_alert_fiat_amount,
+            template: None,
+            numbers: NumberFormat::from_args(args),
+        };
+
+        sink.configure(args, notifiers)?;
+
+        Ok(sink)
+    }
+
+    /// Reads the template again and applies the thresholds of the config,
+    /// leaving the sink as it was when the template is invalid
+    pub fn configure(&mut self, args: &Args, notifiers: &NotifierConfig) -> anyhow::Result<()> {
+        self.template = args
+            .slack_template
+            .as_deref()
+            .map(|path| MessageTemplate::load(path, args.notification_locale, args.timezone))
+            .transpose()?;
+        self.min_fiat_amount = notifiers
+            .slack_min_fiat_amount
+            .unwrap_or(args.slack_min_fiat_amount);
+        self.alert_fiat_amount = notifiers
+            .slack_alert_fiat_amount
+            .or(args.slack_alert_fiat_amount);
+
+        Ok(())
+    }
+
+    pub async fn alert(&self, message: &str) -> anyhow::Result<()> {
+        let blocks = json!([
+            {
+                "type": "header",
+                "text": { "type": "plain_text", "text": ":warning: Nash collector alert", "emoji": true },
+            },
+            {
+                "type": "section",
+                "text": { "type": "plain_text", "text": message },
+            },
+        ]);
+
+        self.client
+            .post(&self.webhook_url)
+            .json(&json!({ "text": message, "blocks": blocks }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    pub async fn publish(&self, cycle: &Cycle<'_>) -> anyhow::Result<()> {
+        let orders = cycle
+            .orders
+            .iter()
+            .filter(|order| order.fiat_amount >= self.min_fiat_amount)
+            .collect::<Vec<_>>();
+
+        if let Some(template) = &self.template {
+            for order in orders {
+                self.client
+                    .post(&self.webhook_url)
+                    .json(&json!({ "text": template.render(order, cycle)? }))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+
+            return Ok(());
+        }
+
+        for orders in orders.chunks(MAX_ORDERS_PER_MESSAGE) {
+            let blocks = orders
+                .iter()
+                .flat_map(|order| self.order_blocks(order))
+                .collect::<Vec<_>>();
+
+            let text = orders
+                .iter()
+                .map(|order| self.numbers.order(order, OrderStyle::Compact).to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            self.client
+                .post(&self.webhook_url)
+                .json(&json!({ "text": text, "blocks": blocks }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+
+        Ok(())
+    }
+
+    fn order_blocks(&self, order: &Order) -> Vec<Value> {
+        let mut blocks = Vec::new();
+
+        if self
+            .alert_fiat_amount
+            .is_some_and(|threshold| order.fiat_amount >= threshold)
+        {
+            blocks.push(json!({
+                "type": "header",
+                "text": {
+                    "type": "plain_text",
+                    "text": format!(":rotating_light: Large {} order", order.crypto_symbol),
+                    "emoji": true,
+                },
+            }));
+        }
+
+        let side = match order.ty {
+            OrderType::Buy => ":large_green_circle: *Buy*",
+            OrderType::Sell => ":red_circle: *Sell*",
+        };
+
+        blocks.push(json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!(
+                    "{side} {}",
+                    self.numbers.crypto(order.crypto_amount, &order.crypto_symbol)
+                ),
+            },
+            "fields": [
+                {
+                    "type": "mrkdwn",
+                    "text": format!(
+                        "*Amount*\n{}",
+                        self.numbers.fiat(order.fiat_amount, &order.fiat_symbol)
+                    ),
+                },
+                {
+                    "type": "mrkdwn",
+                    "text": format!(
+                        "*Price*\n{}",
+                        self.numbers.fiat(order.fiat_price, &order.fiat_symbol)
+                    ),
+                },
+                {
+                    "type": "mrkdwn",
+                    "text": format!("*Blockchain*\n{}", order.blockchain),
+                },
+            ],
+        }));
+
+        blocks
+    }
+}
@@ -0,0 +1,87 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use chrono_tz::Tz;
+use minijinja::{Environment, context};
+
+use crate::{
+    fetch::{Order, OrderType},
+    numbers::{Locale, format_number},
+    sink::Cycle,
+};
+
+/// User template of the message a notifier sends for each order, with the
+/// `order` and `cycle` variables and a locale aware `number` filter
+pub struct MessageTemplate {
+    env: Environment<'static>,
+    source: String,
+    locale: Locale,
+    timezone: Tz,
+}
+
+impl MessageTemplate {
+    pub fn load(path: &Path, locale: Locale, timezone: Tz) -> anyhow::Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read message template {}", path.display()))?;
+
+        let mut env = Environment::new();
+        env.add_filter("number", move |value: f64, decimals: Option<usize>| {
+            format_number(value, decimals.unwrap_or(2), locale)
+        });
+
+        // Fails early on a broken template rather than at the first order
+        env.template_from_str(&source)
+            .with_context(|| format!("Failed to parse message template {}", path.display()))?;
+
+        Ok(Self {
+            env,
+            source,
+            locale,
+            timezone,
+        })
+    }
+
+    pub fn render(&self, order: &Order, cycle: &Cycle<'_>) -> anyhow::Result<String> {
+        let fiat_volume = cycle
+            .orders
+            .iter()
+            .filter(|other| other.fiat_symbol == order.fiat_symbol)
+            .map(|other| other.fiat_amount)
+            .sum::<f64>();
+
+        let message = self.env.render_str(
+            &self.source,
+            context! {
+                order => context! {
+                    type => order.ty.to_string(),
+                    side => side(self.locale, &order.ty),
+                    blockchain => order.blockchain,
+                    crypto_amount => order.crypto_amount,
+                    crypto_symbol => order.crypto_symbol,
+                    fiat_amount => order.fiat_amount,
+                    fiat_price => order.fiat_price,
+                    fiat_symbol => order.fiat_symbol,
+                    pair => format!("{}/{}", order.crypto_symbol, order.fiat_symbol),
+                },
+                cycle => context! {
+                    at => cycle.at.with_timezone(&self.timezone).to_rfc3339(),
+                    orders => cycle.orders.len(),
+                    fiat_volume => fiat_volume,
+                },
+            },
+        )?;
+
+        Ok(message)
+    }
+}
+
+fn side(locale: Locale, ty: &OrderType) -> &'static str {
+    match (locale, ty) {
+        (Locale::En, OrderType::Buy) => "buy",
+        (Locale::En, OrderType::Sell) => "sell",
+        (Locale::Fr, OrderType::Buy) => "achat",
+        (Locale::Fr, OrderType::Sell) => "vente",
+        (Locale::De, OrderType::Buy) => "Kauf",
+        (Locale::De, OrderType::Sell) => "Verkauf",
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+
+use anyhow::bail;
+use clap::ValueEnum;
+
+use crate::{args::Args, fetch::Fetched};
+
+pub use nash::NashSource;
+pub use stream::NashStreamSource;
+
+mod nash;
+mod stream;
+
+/// Exchanges the orders can be collected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum SourceKind {
+    /// Polls the latest completed orders of Nash
+    Nash,
+    /// Streams the Nash orders over the WebSocket set with `--stream-url`,
+    /// polling while it is down, instead of `nash`
+    NashStream,
+}
+
+pub enum Source {
+    Nash(NashSource),
+    NashStream(NashStreamSource),
+}
+
+impl Source {
+    pub fn from_args(args: &Args, client: &reqwest::Client) -> anyhow::Result<Vec<Source>> {
+        let mut kinds = args.sources.clone();
+        kinds.sort();
+        kinds.dedup();
+
+        let sources = kinds
+            .into_iter()
+            .map(|kind| {
+                Ok(match kind {
+                    SourceKind::Nash => Source::Nash(NashSource::new(client.clone())),
+                    SourceKind::NashStream => {
+                        Source::NashStream(NashStreamSource::new(client.clone(), args)?)
+                    }
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // Sources stored under the same name would share their orders, and
+        // overwrite each other's health
+        let mut names = HashSet::new();
+
+        for source in &sources {
+            if !names.insert(source.name()) {
+                bail!(
+                    "Several of the sources store their orders as {}, pick only one of them",
+                    source.name()
+                );
+            }
+        }
+
+        Ok(sources)
+    }
+
+    /// Name the orders of this source are stored under
+    pub fn name(&self) -> &'static str {
+        match self {
+            // Both collect the same orders, which are stored under the same name
+            Source::Nash(_) | Source::NashStream(_) => "nash",
+        }
+    }
+
+    /// Fetches the latest orders of the source
+    pub async fn fetch(&self) -> anyhow::Result<Fetched> {
+        match self {
+            Source::Nash(source) => Ok(source.fetch().await?),
+            Source::NashStream(source) => source.fetch().await,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::Display,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{
+    StatusCode,
+    header::{
+        DATE, ETAG, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+        RETRY_AFTER,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug_span, warn};
+
+use crate::fetch::{FetchError, Fetched, Order, Timing};
+
+const LATEST_ORDERS_URL: &str = "https://app.nash.io/api/cash/latest_completed_orders";
+
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// Resets above this are Unix timestamps rather than a number of seconds
+const RESET_TIMESTAMP_THRESHOLD: i64 = 1_000_000_000;
+
+/// Latest completed orders of the Nash cash on/off ramp
+pub struct NashSource {
+    client: reqwest::Client,
+    validators: Mutex<Validators>,
+}
+
+/// What identifies the previous response, to detect the unchanged ones
+/// without parsing them
+#[derive(Debug, Default)]
+struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body_hash: Option<u64>,
+}
+
+impl NashSource {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            validators: Mutex::default(),
+        }
+    }
+
+    pub async fn fetch(&self) -> Result<Fetched, FetchError> {
+        let sent_at = Utc::now();
+        let start = Instant::now();
+
+        let mut request = self.client.get(LATEST_ORDERS_URL);
+
+        if let Ok(validators) = self.validators.lock() {
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(FetchError::RateLimited {
+                retry_after: advised_wait(response.headers()),
+            });
+        }
+
+        let response = response.error_for_status()?;
+        let retry_after = advised_wait(response.headers());
+
+        let latency = start.elapsed();
+        let status = response.status().as_u16();
+        let server_date = response
+            .headers()
+            .get(DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .map(|date| date.with_timezone(&Utc));
+
+        // The server stamps its `Date` header somewhere during the round-trip,
+        // the midpoint is the best local estimate of that instant
+        let skew = server_date
+            .map(|date| date - (sent_at + TimeDelta::from_std(latency / 2).unwrap_or_default()));
+        let timing = Timing {
+            latency,
+            skew,
+            server_date,
+        };
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched {
+                retry_after,
+                ..Fetched::not_modified(status, timing)
+            });
+        }
+
+        let etag = response.headers().get(ETAG).cloned();
+        let last_modified = response.headers().get(LAST_MODIFIED).cloned();
+        let body = response.bytes().await?;
+
+        // Without validators from the API, an identical body is as good a sign
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        let body_hash = hasher.finish();
+
+        if let Ok(mut validators) = self.validators.lock() {
+            let unchanged = validators.body_hash == Some(body_hash);
+
+            *validators = Validators {
+                etag,
+                last_modified,
+                body_hash: Some(body_hash),
+            };
+
+            if unchanged {
+                return Ok(Fetched {
+                    retry_after,
+                    ..Fetched::not_modified(status, timing)
+                });
+            }
+        }
+
+        let current_orders = debug_span!("parse", bytes = body.len())
+            .in_scope(|| {
+                serde_json::from_slice::<OrdersResponse>(&body)
+                    .map_err(|err| FetchError::deserialize(&body, err))
+                    .and_then(|response| {
+                        LatestOrders::try_from(response).map_err(|err| FetchError::Api(err.message))
+                    })
+            })
+            .inspect_err(|_| {
+                // An error body must not be mistaken for unchanged orders next time
+                if let Ok(mut validators) = self.validators.lock() {
+                    *validators = Validators::default();
+                }
+            })?;
+
+        Ok(Fetched {
+            status,
+            orders: current_orders.into_set(),
+            not_modified: false,
+            timing,
+            retry_after,
+        })
+    }
+}
+
+/// How long the API asks to wait, from a `Retry-After` header in seconds or
+/// as a date, or from the reset of an exhausted `X-RateLimit` quota
+fn advised_wait(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(retry_after) = header(RETRY_AFTER.as_str()) {
+        return retry_after
+            .trim()
+            .parse()
+            .map(Duration::from_secs)
+            .ok()
+            .or_else(|| {
+                DateTime::parse_from_rfc2822(retry_after)
+                    .ok()
+                    .and_then(|date| (date.with_timezone(&Utc) - Utc::now()).to_std().ok())
+            });
+    }
+
+    if header(RATE_LIMIT_REMAINING)?.trim() != "0" {
+        return None;
+    }
+
+    let reset = header(RATE_LIMIT_RESET)?.trim().parse::<i64>().ok()?;
+    let seconds = if reset > RESET_TIMESTAMP_THRESHOLD {
+        reset - Utc::now().timestamp()
+    } else {
+        reset
+    };
+
+    u64::try_from(seconds).ok().map(Duration::from_secs)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OrdersResponse {
+    Ok(LatestOrders),
+    Err(OrdersError),
+}
+
+impl TryFrom<OrdersResponse> for LatestOrders {
+    type Error = OrdersError;
+
+    fn try_from(value: OrdersResponse) -> Result<Self, Self::Error> {
+        match value {
+            OrdersResponse::Ok(latest_orders) => Ok(latest_orders),
+            OrdersResponse::Err(orders_error) => Err(orders_error),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestOrders {
+    /// Parsed one by one, so that an order in an unexpected shape doesn't fail
+    /// the whole fetch
+    latest_orders: Vec<Value>,
+}
+
+impl LatestOrders {
+    fn into_set(self) -> HashSet<Order> {
+        self.latest_orders
+            .into_iter()
+            .filter_map(|order| {
+                serde_json::from_value::<Order>(order.clone())
+                    .inspect_err(|err| warn!("Skipping the unexpected order {order}: {err}"))
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OrdersError {
+    message: String,
+}
+
+impl Display for OrdersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for OrdersError {}
@@ -0,0 +1,233 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::{
+    select,
+    time::{interval, sleep},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, info, warn};
+
+use crate::{
+    args::Args,
+    fetch::{Fetched, Order, Timing},
+    source::NashSource,
+};
+
+/// Number of latest streamed orders returned by each fetch, so that the
+/// collector sees a window like the one of the polled endpoint
+const WINDOW: usize = 100;
+
+/// Longest wait between two reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Reference of the channel join message, to recognize its reply
+const JOIN_REF: &str = "1";
+
+/// Orders pushed by the Nash Phoenix socket, polling the HTTP endpoint while
+/// the socket is down
+pub struct NashStreamSource {
+    state: Arc<Mutex<StreamState>>,
+    fallback: NashSource,
+}
+
+/// Where the orders are pushed, as set on the command line
+#[derive(Debug)]
+struct Channel {
+    url: String,
+    topic: String,
+    /// Event of the messages carrying an order
+    event: String,
+}
+
+#[derive(Debug, Default)]
+struct StreamState {
+    joined: bool,
+    window: VecDeque<Order>,
+}
+
+impl NashStreamSource {
+    pub fn new(client: reqwest::Client, args: &Args) -> anyhow::Result<Self> {
+        let (Some(url), Some(topic), Some(event)) =
+            (&args.stream_url, &args.stream_topic, &args.stream_event)
+        else {
+            bail!("The nash-stream source needs --stream-url, --stream-topic and --stream-event");
+        };
+
+        let state = Arc::new(Mutex::new(StreamState::default()));
+
+        tokio::spawn(stream(
+            Channel {
+                url: url.clone(),
+                topic: topic.clone(),
+                event: event.clone(),
+            },
+            args.stream_heartbeat,
+            state.clone(),
+        ));
+
+        Ok(Self {
+            state,
+            fallback: NashSource::new(client),
+        })
+    }
+
+    pub async fn fetch(&self) -> anyhow::Result<Fetched> {
+        let start = Instant::now();
+        let streamed = {
+            let state = self
+                .state
+                .lock()
+                .map_err(|_| anyhow!("Stream state lock poisoned"))?;
+
+            state.joined.then(|| state.window.iter().cloned().collect())
+        };
+
+        match streamed {
+            Some(orders) => Ok(Fetched {
+                status: 101,
+                orders,
+                not_modified: false,
+                timing: Timing {
+                    latency: start.elapsed(),
+                    skew: None,
+                    server_date: None,
+                },
+                retry_after: None,
+            }),
+            None => Ok(self.fallback.fetch().await?),
+        }
+    }
+}
+
+/// Keeps the socket connected, reconnecting with an exponential backoff
+async fn stream(channel: Channel, heartbeat: Duration, state: Arc<Mutex<StreamState>>) {
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        match listen(&channel, heartbeat, &state).await {
+            Ok(()) => {
+                warn!("Order stream closed, polling until it reconnects");
+                backoff = Duration::from_secs(1);
+            }
+            Err(err) => warn!("Order stream failed: {err}, polling until it reconnects"),
+        }
+
+        // A stream which was joined worked, the next failure backing off from
+        // the start again
+        if let Ok(mut state) = state.lock()
+            && std::mem::take(&mut state.joined)
+        {
+            backoff = Duration::from_secs(1);
+        }
+
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Joins the channel topic and buffers the pushed orders until the socket
+/// closes or stays silent for two heartbeats
+async fn listen(
+    channel: &Channel,
+    heartbeat: Duration,
+    state: &Mutex<StreamState>,
+) -> anyhow::Result<()> {
+    let (mut socket, _) = connect_async(&channel.url).await?;
+
+    socket
+        .send(Message::text(
+            json!([JOIN_REF, JOIN_REF, channel.topic, "phx_join", {}]).to_string(),
+        ))
+        .await?;
+
+    let mut beats = interval(heartbeat);
+    let mut last_message = Instant::now();
+    let mut reference = 1_u64;
+
+    loop {
+        select! {
+            _ = beats.tick() => {
+                if last_message.elapsed() > heartbeat * 2 {
+                    bail!(
+                        "nothing received for {}",
+                        humantime::format_duration(heartbeat * 2)
+                    );
+                }
+
+                reference += 1;
+                socket
+                    .send(Message::text(
+                        json!([null, reference.to_string(), "phoenix", "heartbeat", {}]).to_string(),
+                    ))
+                    .await?;
+            }
+            message = socket.next() => {
+                let Some(message) = message else {
+                    return Ok(());
+                };
+
+                last_message = Instant::now();
+
+                match message? {
+                    Message::Text(text) => handle(text.as_str(), channel, state)?,
+                    Message::Close(_) => return Ok(()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Handles a Phoenix message, formatted as `[join_ref, ref, topic, event, payload]`
+fn handle(text: &str, channel: &Channel, state: &Mutex<StreamState>) -> anyhow::Result<()> {
+    let topic = &channel.topic;
+    let (join_ref, _, message_topic, event, payload) =
+        serde_json::from_str::<(Option<String>, Option<String>, String, String, Value)>(text)?;
+
+    if message_topic != channel.topic {
+        return Ok(());
+    }
+
+    let mut state = state
+        .lock()
+        .map_err(|_| anyhow!("Stream state lock poisoned"))?;
+
+    match event.as_str() {
+        "phx_reply" if join_ref.as_deref() == Some(JOIN_REF) && !state.joined => {
+            if payload["status"] != "ok" {
+                bail!("failed to join {topic}: {}", payload["response"]);
+            }
+
+            info!("Streaming orders from {topic}");
+            state.joined = true;
+        }
+        "phx_error" | "phx_close" => bail!("channel {topic} closed by the server"),
+        event if event == channel.event => {
+            // A single malformed order doesn't tear the socket down
+            let order = match serde_json::from_value::<Order>(payload) {
+                Ok(order) => order,
+                Err(err) => {
+                    warn!("Skipping the streamed order which can't be read: {err}");
+                    return Ok(());
+                }
+            };
+
+            debug!("Streamed order: {order}");
+            state.window.push_back(order);
+
+            if state.window.len() > WINDOW {
+                state.window.pop_front();
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use tokio::net::UdpSocket;
+use tracing::{debug, info};
+
+use crate::{args::Args, db::FetchRun, metrics::Metrics};
+
+/// Kept under the usual MTU so that the datagrams aren't fragmented
+const MAX_DATAGRAM: usize = 1432;
+
+/// Publishes the metrics to a StatsD server after every fetch, the counters as
+/// the increase since the previous fetch
+pub struct StatsdEmitter {
+    socket: UdpSocket,
+    prefix: String,
+    /// DogStatsD suffix of every metric, empty without tags
+    tags: String,
+    /// Counters as published by the previous fetch
+    counters: BTreeMap<String, u64>,
+}
+
+impl StatsdEmitter {
+    pub async fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).await?;
+        socket
+            .connect((args.statsd_host.as_str(), args.statsd_port))
+            .await?;
+
+        info!(
+            "Publishing the metrics to StatsD at {}:{}",
+            args.statsd_host, args.statsd_port
+        );
+
+        Ok(Self {
+            socket,
+            prefix: args.statsd_prefix.trim_end_matches('.').to_string(),
+            tags: if args.statsd_tags.is_empty() {
+                String::new()
+            } else {
+                format!("|#{}", args.statsd_tags.join(","))
+            },
+            counters: BTreeMap::new(),
+        })
+    }
+
+    pub async fn emit(&mut self, metrics: &Metrics, run: &FetchRun) {
+        let mut lines = Vec::new();
+        let source = format!("source:{}", run.source);
+
+        self.counter(&mut lines, "fetches", metrics.fetches, &[]);
+        self.counter(&mut lines, "not_modified", metrics.not_modified, &[]);
+        self.counter(&mut lines, "gaps", metrics.gaps, &[]);
+        self.counter(
+            &mut lines,
+            "estimated_missed_orders",
+            metrics.estimated_missed_orders,
+            &[],
+        );
+        self.counter(&mut lines, "rate_limited", metrics.rate_limited, &[]);
+
+        for (kind, errors) in &metrics.errors {
+            self.counter(&mut lines, "errors", *errors, &[&format!("kind:{kind}")]);
+        }
+
+        if run.orders_inserted > 0 {
+            lines.push(self.line(
+                "orders_inserted",
+                run.orders_inserted.to_string(),
+                "c",
+                &[&source],
+            ));
+        }
+
+        if let Some(latency) = run.latency {
+            lines.push(self.line("latency", latency.as_millis().to_string(), "ms", &[&source]));
+        }
+
+        lines.push(self.line(
+            "latency_avg_ms",
+            format!("{:.1}", metrics.latency_avg_ms),
+            "g",
+            &[],
+        ));
+        lines.push(self.line("window_size", metrics.window_size.to_string(), "g", &[]));
+
+        if let Some(skew) = metrics.clock_skew {
+            lines.push(self.line(
+                "clock_skew_ms",
+                skew.num_milliseconds().to_string(),
+                "g",
+                &[],
+            ));
+        }
+
+        for (pair, buy_share) in &metrics.buy_shares {
+            lines.push(self.line(
+                "buy_share",
+                format!("{buy_share:.3}"),
+                "g",
+                &[&format!("pair:{pair}")],
+            ));
+        }
+
+        for (pair, volatility) in &metrics.volatilities {
+            lines.push(self.line(
+                "volatility",
+                format!("{volatility:.5}"),
+                "g",
+                &[&format!("pair:{pair}")],
+            ));
+        }
+
+        self.send(lines).await;
+    }
+
+    /// Adds the increase of the counter `name` since the previous fetch, if any
+    fn counter(&mut self, lines: &mut Vec<String>, name: &str, value: u64, tags: &[&str]) {
+        let key = format!("{name}{}", tags.join(","));
+        let previous = self.counters.insert(key, value).unwrap_or_default();
+
+        if value > previous {
+            lines.push(self.line(name, (value - previous).to_string(), "c", tags));
+        }
+    }
+
+    fn line(&self, name: &str, value: String, kind: &str, tags: &[&str]) -> String {
+        let mut line = if self.prefix.is_empty() {
+            format!("{name}:{value}|{kind}{}", self.tags)
+        } else {
+            format!("{}.{name}:{value}|{kind}{}", self.prefix, self.tags)
+        };
+
+        if !tags.is_empty() {
+            let separator = if self.tags.is_empty() { "|#" } else { "," };
+            let _ = write!(line, "{separator}{}", tags.join(","));
+        }
+
+        line
+    }
+
+    /// Packs the lines in as few datagrams as possible, the losses being
+    /// expected with StatsD
+    async fn send(&self, lines: Vec<String>) {
+        let mut datagram = String::new();
+
+        for line in lines {
+            if !datagram.is_empty() && datagram.len() + 1 + line.len() > MAX_DATAGRAM {
+                self.send_datagram(&datagram).await;
+                datagram.clear();
+            }
+
+            if !datagram.is_empty() {
+                datagram.push('\n');
+            }
+
+            datagram.push_str(&line);
+        }
+
+        if !datagram.is_empty() {
+            self.send_datagram(&datagram).await;
+        }
+    }
+
+    async fn send_datagram(&self, datagram: &str) {
+        if let Err(err) = self.socket.send(datagram.as_bytes()).await {
+            debug!("Failed to send the metrics to StatsD: {err}");
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fmt::Display, time::Duration};
+
+/// One-shot summary of a collector, kept by the running instance or read
+/// back from the fetch runs it stored
+#[derive(Debug, Default)]
+pub struct StatusSummary {
+    /// Since the collector started, unknown when read from the database
+    pub uptime: Option<Duration>,
+    pub cycles: u64,
+    pub orders_inserted: u64,
+    /// Failed fetches per class of error, or per source when read from the database
+    pub errors: Vec<(String, u64)>,
+    pub last_latency: Option<Duration>,
+    /// Size of the database file and its WAL, unknown in memory
+    pub db_size: Option<u64>,
+}
+
+impl Display for StatusSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(uptime) = self.uptime {
+            // Seconds are enough, the rest being noise
+            let uptime = Duration::from_secs(uptime.as_secs());
+
+            write!(f, "up {}, ", humantime::format_duration(uptime))?;
+        }
+
+        write!(
+            f,
+            "{} cycles, {} orders inserted",
+            self.cycles, self.orders_inserted
+        )?;
+
+        if self.errors.is_empty() {
+            write!(f, ", no errors")?;
+        } else {
+            write!(f, ", errors")?;
+
+            for (kind, errors) in &self.errors {
+                write!(f, " {kind} {errors}")?;
+            }
+        }
+
+        if let Some(latency) = self.last_latency {
+            write!(f, ", last fetch latency {}ms", latency.as_millis())?;
+        }
+
+        if let Some(db_size) = self.db_size {
+            write!(f, ", database {db_size} bytes")?;
+        }
+
+        Ok(())
+    }
+}
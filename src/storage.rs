@@ -0,0 +1,72 @@
+use std::{path::Path, sync::Arc};
+
+use object_store::{
+    ObjectStore, RetryConfig, aws::AmazonS3Builder, buffered::BufWriter, path::Path as ObjectPath,
+};
+use tokio::{fs::File, io::AsyncWriteExt};
+
+use crate::args::Args;
+
+/// S3 compatible bucket the exports and archives are pushed to
+#[derive(Clone)]
+pub struct ObjectStorage {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+}
+
+impl ObjectStorage {
+    /// Credentials not given as arguments are read from the usual `AWS_*` variables
+    pub fn from_args(args: &Args) -> anyhow::Result<Option<Self>> {
+        let Some(bucket) = &args.s3_bucket else {
+            return Ok(None);
+        };
+
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .with_retry(RetryConfig {
+                max_retries: args.s3_max_retries,
+                ..Default::default()
+            });
+
+        if let Some(region) = &args.s3_region {
+            builder = builder.with_region(region);
+        }
+
+        if let Some(endpoint) = &args.s3_endpoint {
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_allow_http(endpoint.starts_with("http://"))
+                .with_virtual_hosted_style_request(false);
+        }
+
+        if let Some(access_key_id) = &args.s3_access_key_id {
+            builder = builder.with_access_key_id(access_key_id);
+        }
+
+        if let Some(secret_access_key) = &args.s3_secret_access_key {
+            builder = builder.with_secret_access_key(secret_access_key);
+        }
+
+        Ok(Some(Self {
+            store: Arc::new(builder.build()?),
+            prefix: args.s3_prefix.trim_matches('/').to_string(),
+        }))
+    }
+
+    /// Uploads a local file under `key`, large files being sent as multipart uploads
+    pub async fn upload(&self, file: &Path, key: &str) -> anyhow::Result<String> {
+        let key = if self.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{key}", self.prefix)
+        };
+
+        let mut writer = BufWriter::new(self.store.clone(), ObjectPath::from(key.as_str()));
+        let mut file = File::open(file).await?;
+
+        tokio::io::copy(&mut file, &mut writer).await?;
+        writer.shutdown().await?;
+
+        Ok(key)
+    }
+}
@@ -0,0 +1,158 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::task::spawn_blocking;
+
+#[cfg(feature = "grpc")]
+use crate::db::get_orders_after;
+use crate::{
+    anomaly::Anomaly,
+    db::{
+        FetchRun, NewOrder, OrderCursor, OrderFilter, PairSummary, VolumeProfile, get_candles,
+        get_latest_orders, get_orders_page, get_summary, get_volume_profile, insert_anomaly,
+        insert_burst, insert_fetch_run, insert_gap, insert_imbalance, insert_order, insert_orders,
+    },
+    dedup::DedupWindow,
+    export::Pair,
+    fetch::Order,
+    gaps::{Burst, Gap},
+    imbalance::Imbalance,
+    indicators::Candle,
+};
+
+/// Database of the collector, running the DuckDB calls on the blocking thread
+/// pool so that a slow disk doesn't stall the fetch loop, the calls running at
+/// the same time cloning their connection from a single instance
+#[derive(Debug, Clone)]
+pub struct Store {
+    persist_path: Arc<str>,
+}
+
+impl Store {
+    pub fn new(persist_path: &str) -> Self {
+        Self {
+            persist_path: persist_path.into(),
+        }
+    }
+
+    async fn run<T, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&str) -> anyhow::Result<T> + Send + 'static,
+    {
+        let persist_path = self.persist_path.clone();
+
+        spawn_blocking(move || f(&persist_path)).await?
+    }
+
+    pub async fn get_latest_orders(
+        &self,
+        source: &'static str,
+        window: DedupWindow,
+    ) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+        self.run(move |persist_path| get_latest_orders(persist_path, source, &window))
+            .await
+    }
+
+    pub async fn insert_order(
+        &self,
+        order: NewOrder,
+        session_gap: TimeDelta,
+    ) -> anyhow::Result<bool> {
+        self.run(move |persist_path| insert_order(&order, persist_path, session_gap))
+            .await
+    }
+
+    pub async fn insert_orders(
+        &self,
+        orders: Vec<NewOrder>,
+        session_gap: TimeDelta,
+    ) -> anyhow::Result<usize> {
+        self.run(move |persist_path| insert_orders(&orders, persist_path, session_gap))
+            .await
+    }
+
+    pub async fn insert_fetch_run(&self, run: FetchRun) -> anyhow::Result<()> {
+        self.run(move |persist_path| insert_fetch_run(&run, persist_path))
+            .await
+    }
+
+    pub async fn insert_gap(&self, gap: Gap, source: &'static str) -> anyhow::Result<()> {
+        self.run(move |persist_path| insert_gap(&gap, source, persist_path))
+            .await
+    }
+
+    pub async fn insert_burst(&self, burst: Burst, source: &'static str) -> anyhow::Result<()> {
+        self.run(move |persist_path| insert_burst(&burst, source, persist_path))
+            .await
+    }
+
+    pub async fn insert_imbalance(
+        &self,
+        imbalance: Imbalance,
+        window: Duration,
+    ) -> anyhow::Result<()> {
+        self.run(move |persist_path| insert_imbalance(&imbalance, window, persist_path))
+            .await
+    }
+
+    pub async fn insert_anomaly(&self, anomaly: Anomaly) -> anyhow::Result<()> {
+        self.run(move |persist_path| insert_anomaly(&anomaly, persist_path))
+            .await
+    }
+
+    pub async fn get_candles(
+        &self,
+        pair: Pair,
+        timeframe: TimeDelta,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Candle>> {
+        self.run(move |persist_path| get_candles(persist_path, &pair, timeframe, from, to))
+            .await
+    }
+
+    pub async fn get_volume_profile(
+        &self,
+        pair: Pair,
+        levels: usize,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<VolumeProfile> {
+        self.run(move |persist_path| get_volume_profile(persist_path, &pair, levels, from, to))
+            .await
+    }
+
+    #[cfg(feature = "grpc")]
+    pub async fn get_orders_after(
+        &self,
+        after: DateTime<Utc>,
+        filter: OrderFilter,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(DateTime<Utc>, Order)>> {
+        self.run(move |persist_path| get_orders_after(persist_path, after, &filter, limit))
+            .await
+    }
+
+    pub async fn get_orders_page(
+        &self,
+        filter: OrderFilter,
+        after: Option<OrderCursor>,
+        limit: usize,
+        descending: bool,
+    ) -> anyhow::Result<Vec<(OrderCursor, Order)>> {
+        self.run(move |persist_path| {
+            get_orders_page(persist_path, &filter, after.as_ref(), limit, descending)
+        })
+        .await
+    }
+
+    pub async fn get_summary(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<PairSummary>> {
+        self.run(move |persist_path| get_summary(persist_path, from, to))
+            .await
+    }
+}
@@ -0,0 +1,21 @@
+use sd_notify::NotifyState;
+use tracing::debug;
+
+/// Notifications are no-ops when the collector isn't run by systemd
+fn notify(state: NotifyState<'_>) {
+    if let Err(err) = sd_notify::notify(false, &[state]) {
+        debug!("Failed to notify systemd: {err}");
+    }
+}
+
+pub fn ready() {
+    notify(NotifyState::Ready);
+}
+
+pub fn watchdog() {
+    notify(NotifyState::Watchdog);
+}
+
+pub fn stopping() {
+    notify(NotifyState::Stopping);
+}
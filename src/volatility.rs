@@ -0,0 +1,125 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{args::Args, export::Pair, fetch::Order};
+
+/// Realized volatility of the prices of a pair over the rolling window
+#[derive(Debug, Clone)]
+pub struct Volatility {
+    pub pair: Pair,
+    /// Number of log returns between consecutive prices in the window
+    pub returns: usize,
+    /// Square root of the sum of the squared log returns, 0.01 being 1%
+    pub realized: f64,
+}
+
+/// Keeps the prices of the rolling window of every pair, to raise a single
+/// alert while the volatility of a pair stays above the threshold
+#[derive(Debug)]
+pub struct VolatilityTracker {
+    window: Duration,
+    alert_threshold: Option<f64>,
+    min_orders: usize,
+    prices: HashMap<Pair, VecDeque<(DateTime<Utc>, f64)>>,
+    alerting: HashSet<Pair>,
+}
+
+impl VolatilityTracker {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            window: args.volatility_window,
+            alert_threshold: args.volatility_alert_threshold,
+            min_orders: args.volatility_min_orders,
+            prices: HashMap::new(),
+            alerting: HashSet::new(),
+        }
+    }
+
+    /// Adds the prices of the new orders and drops the ones out of the window,
+    /// returning the volatility of every pair that changed
+    pub fn observe(&mut self, new_orders: &[&Order], at: DateTime<Utc>) -> Vec<Volatility> {
+        let mut changed = HashSet::new();
+
+        for order in new_orders {
+            if order.fiat_price <= 0.0 {
+                continue;
+            }
+
+            let pair = Pair::of(order);
+
+            self.prices
+                .entry(pair.clone())
+                .or_default()
+                .push_back((at, order.fiat_price));
+            changed.insert(pair);
+        }
+
+        let start = TimeDelta::from_std(self.window)
+            .ok()
+            .and_then(|window| at.checked_sub_signed(window))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        for (pair, prices) in &mut self.prices {
+            while prices.front().is_some_and(|(seen_at, _)| *seen_at < start) {
+                prices.pop_front();
+                changed.insert(pair.clone());
+            }
+        }
+
+        self.prices.retain(|_, prices| !prices.is_empty());
+
+        let mut volatilities = changed
+            .into_iter()
+            .map(|pair| {
+                let prices = self.prices.get(&pair);
+                let returns = prices
+                    .into_iter()
+                    .flat_map(|prices| {
+                        prices
+                            .iter()
+                            .zip(prices.iter().skip(1))
+                            .map(|((_, previous), (_, price))| (price / previous).ln())
+                    })
+                    .collect::<Vec<_>>();
+
+                Volatility {
+                    returns: returns.len(),
+                    realized: returns.iter().map(|r| r * r).sum::<f64>().sqrt(),
+                    pair,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        volatilities.sort_by(|a, b| a.pair.cmp(&b.pair));
+
+        volatilities
+    }
+
+    /// Returns an alert message when the volatility of the pair rises beyond the threshold
+    pub fn alert(&mut self, volatility: &Volatility) -> Option<String> {
+        let threshold = self.alert_threshold?;
+        let spiking = volatility.returns + 1 >= self.min_orders && volatility.realized >= threshold;
+
+        if !spiking {
+            self.alerting.remove(&volatility.pair);
+
+            return None;
+        }
+
+        if !self.alerting.insert(volatility.pair.clone()) {
+            return None;
+        }
+
+        Some(format!(
+            "{}: realized volatility of {:.2}% over the last {} ({} orders)",
+            volatility.pair,
+            volatility.realized * 100.0,
+            humantime::format_duration(self.window),
+            volatility.returns + 1
+        ))
+    }
+}
@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        State, WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    response::Response,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    select,
+    sync::{
+        broadcast::error::RecvError,
+        mpsc::{self, error::TrySendError},
+    },
+    time::{MissedTickBehavior, interval},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    db::OrderFilter,
+    export::Pair,
+    feed::{FeedOrder, OrderFeed},
+    http::OrderItem,
+};
+
+/// Messages waiting to be written to a client, the orders beyond being dropped
+const CLIENT_QUEUE: usize = 256;
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sent by the client to start receiving orders, or to change its filter
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe {
+        /// Pairs like `BTC/USD`, all of them when empty
+        #[serde(default)]
+        pairs: Vec<String>,
+        /// `buy` or `sell`
+        side: Option<String>,
+        min_fiat_amount: Option<f64>,
+    },
+    Unsubscribe,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ServerMessage {
+    Subscribed,
+    Unsubscribed,
+    Order(OrderItem),
+    /// Orders dropped because the client didn't read them fast enough
+    Dropped {
+        count: u64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl ServerMessage {
+    fn into_message(self) -> Message {
+        Message::Text(
+            serde_json::to_string(&self)
+                .expect("Server messages are always serializable")
+                .into(),
+        )
+    }
+}
+
+#[derive(Debug)]
+struct ClientFilter {
+    pairs: Vec<Pair>,
+    filter: OrderFilter,
+}
+
+impl ClientFilter {
+    fn parse(
+        pairs: Vec<String>,
+        side: Option<String>,
+        min_fiat_amount: Option<f64>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            pairs: pairs
+                .iter()
+                .map(|pair| pair.parse())
+                .collect::<anyhow::Result<_>>()?,
+            filter: OrderFilter {
+                pair: None,
+                ty: side.map(|side| side.parse()).transpose()?,
+                min_fiat_amount,
+            },
+        })
+    }
+
+    fn matches(&self, feed_order: &FeedOrder) -> bool {
+        let order = &feed_order.order;
+
+        (self.pairs.is_empty()
+            || self.pairs.iter().any(|pair| {
+                pair.crypto_symbol == order.crypto_symbol && pair.fiat_symbol == order.fiat_symbol
+            }))
+            && self.filter.matches(order)
+    }
+}
+
+pub async fn ws(State(feed): State<OrderFeed>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| serve(socket, feed))
+}
+
+/// Pushes the orders matching the filter of the last subscribe message until
+/// the client leaves or stops answering the pings
+async fn serve(socket: WebSocket, feed: OrderFeed) {
+    let (mut sink, mut stream) = socket.split();
+    let (queue, mut queued) = mpsc::channel::<Message>(CLIENT_QUEUE);
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = queued.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut orders = feed.subscribe();
+    let mut filter: Option<ClientFilter> = None;
+    let mut dropped = 0;
+    let mut pong_pending = false;
+    let mut ping = interval(PING_INTERVAL);
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ping.tick().await;
+
+    loop {
+        select! {
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Pong(_))) => {
+                        pong_pending = false;
+                        continue;
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(err)) => {
+                        debug!("WebSocket client failed: {err}");
+                        break;
+                    }
+                };
+
+                let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Subscribe { pairs, side, min_fiat_amount }) => {
+                        match ClientFilter::parse(pairs, side, min_fiat_amount) {
+                            Ok(client_filter) => {
+                                filter = Some(client_filter);
+                                ServerMessage::Subscribed
+                            }
+                            Err(err) => ServerMessage::Error { message: err.to_string() },
+                        }
+                    }
+                    Ok(ClientMessage::Unsubscribe) => {
+                        filter = None;
+                        ServerMessage::Unsubscribed
+                    }
+                    Err(err) => ServerMessage::Error { message: err.to_string() },
+                };
+
+                if queue.send(reply.into_message()).await.is_err() {
+                    break;
+                }
+            }
+            feed_order = orders.recv() => {
+                let feed_order = match feed_order {
+                    Ok(feed_order) => feed_order,
+                    Err(RecvError::Lagged(skipped)) => {
+                        dropped += skipped;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if !filter.as_ref().is_some_and(|filter| filter.matches(&feed_order)) {
+                    continue;
+                }
+
+                if dropped > 0 {
+                    match queue.try_send(ServerMessage::Dropped { count: dropped }.into_message()) {
+                        Ok(()) => dropped = 0,
+                        Err(TrySendError::Full(_)) => {
+                            dropped += 1;
+                            continue;
+                        }
+                        Err(TrySendError::Closed(_)) => break,
+                    }
+                }
+
+                let order = OrderItem::new(feed_order.received_at, &feed_order.order);
+
+                match queue.try_send(ServerMessage::Order(order).into_message()) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => dropped += 1,
+                    Err(TrySendError::Closed(_)) => break,
+                }
+            }
+            _ = ping.tick() => {
+                if pong_pending {
+                    warn!("WebSocket client not answering the pings, disconnecting it");
+                    break;
+                }
+
+                match queue.try_send(Message::Ping(Default::default())) {
+                    Ok(()) => pong_pending = true,
+                    // The client is already slow to read, the next tick pings it
+                    Err(TrySendError::Full(_)) => {}
+                    Err(TrySendError::Closed(_)) => break,
+                }
+            }
+        }
+    }
+
+    writer.abort();
+}